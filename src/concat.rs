@@ -0,0 +1,165 @@
+//! Concatenating WAV files, e.g. song renders into a continuous live album.
+//!
+//! [`concat()`] appends the audio of WAV files with the same format, streaming it from
+//! each `data` chunk. The markers and regions of every file are shifted by the length of
+//! the files before it and renumbered in order. Optionally, a region spanning each file
+//! is added, named from its title (`INAM`) or file stem.
+//!
+//! ```rust,no_run
+//! use reaper_regions::concat::{self, ConcatOptions};
+//!
+//! let options = ConcatOptions {
+//!     source_regions: true,
+//!     ..Default::default()
+//! };
+//! let result = concat::concat(&["01.wav", "02.wav"], "album.wav", &options).unwrap();
+//! println!("{} markers", result.markers.len());
+//! ```
+
+use std::collections::BTreeMap;
+use std::fs::File;
+use std::io::{self, Read, Seek, SeekFrom};
+use std::path::Path;
+
+use log::debug;
+
+use crate::diagnostic::Diagnostic;
+use crate::select::Filter;
+use crate::write::{AudioSource, replace_file, write_wav};
+use crate::{Marker, ParseError};
+
+/// Options for [`concat()`].
+#[derive(Debug, Clone, Default)]
+pub struct ConcatOptions {
+    /// Add a region spanning each input file
+    pub source_regions: bool,
+    /// Title (`INAM`) of the output file
+    pub title: Option<String>,
+    /// Markers and regions of the inputs to keep; the regions spanning each file are
+    /// always added
+    pub filter: Filter,
+}
+
+/// The result of [`concat()`].
+#[derive(Debug, Clone, Default)]
+pub struct Concat {
+    /// Markers and regions of the output file, as it parses back
+    pub markers: Vec<Marker>,
+    /// Length of the output in samples
+    pub length: u32,
+    /// Markers past the end of their file
+    pub diagnostics: Vec<Diagnostic>,
+}
+
+/// Appends WAV files into one, with their markers shifted to their new positions.
+///
+/// # Arguments
+/// * `inputs` - Paths of the WAV files, in order
+/// * `output` - Path of the file to write, which may be one of the inputs
+/// * `options` - Whether to add a region per file, and the output title
+///
+/// # Returns
+/// * `Result<Concat, ParseError>` - The markers written, the length of the output and
+///   warnings for markers that reach past the end of their file
+///
+/// # Errors
+/// * [`ParseError::Io`] - If a file cannot be read or written
+/// * [`ParseError::Other`] - If there are no inputs, their formats differ, their audio
+///   is compressed, or the output or a marker past the end of its file would exceed the
+///   4 GiB limit of WAV files
+pub fn concat(
+    inputs: &[impl AsRef<str>],
+    output: &str,
+    options: &ConcatOptions,
+) -> Result<Concat, ParseError> {
+    let sources = inputs
+        .iter()
+        .map(|input| AudioSource::open(input.as_ref(), "concatenate"))
+        .collect::<Result<Vec<_>, _>>()?;
+    let Some(first) = sources.first() else {
+        return Err(ParseError::Other("no files to concatenate".into()));
+    };
+    for (source, input) in sources.iter().zip(inputs).skip(1) {
+        if source.format != first.format {
+            return Err(ParseError::Other(format!(
+                "the format of {} differs from that of {}",
+                input.as_ref(),
+                inputs[0].as_ref()
+            )));
+        }
+    }
+
+    let too_long = || ParseError::Other("the output would be larger than 4 GiB".into());
+    let mut result = Concat::default();
+    let mut offset: u32 = 0;
+    let mut size: u32 = 0;
+    for (source, input) in sources.iter().zip(inputs) {
+        let input = input.as_ref();
+        let mut markers = Vec::new();
+        if options.source_regions {
+            let name = source.data.info.get("INAM").cloned().unwrap_or_else(|| {
+                Path::new(input)
+                    .file_stem()
+                    .map_or_else(|| input.to_string(), |stem| stem.to_string_lossy().into())
+            });
+            markers.push((name, 0, Some(source.length)));
+        }
+        for marker in source
+            .data
+            .markers
+            .iter()
+            .filter(|marker| options.filter.matches(marker))
+        {
+            if marker.end.unwrap_or(marker.start) > source.length {
+                result.diagnostics.push(Diagnostic::warning(
+                    Some(marker.id),
+                    format!("'{}' reaches past the end of {input}", marker.name),
+                ));
+            }
+            markers.push((marker.name.clone(), marker.start, marker.end));
+        }
+        for (name, start, end) in markers {
+            let id = result.markers.len() as u32 + 1;
+            let start = start.checked_add(offset).ok_or_else(too_long)?;
+            let end = end
+                .map(|end| end.checked_add(offset).ok_or_else(too_long))
+                .transpose()?;
+            result
+                .markers
+                .push(Marker::new(id, name, start, end, first.data.sample_rate));
+        }
+
+        offset = offset.checked_add(source.length).ok_or_else(too_long)?;
+        let bytes = (source.length as u64 * source.block_align) as u32;
+        size = size.checked_add(bytes).ok_or_else(too_long)?;
+    }
+    result.length = offset;
+    result
+        .markers
+        .sort_by_key(|marker| (marker.start, marker.id));
+
+    let mut info = BTreeMap::new();
+    if let Some(title) = &options.title {
+        info.insert("INAM".to_string(), title.clone());
+    }
+    let mut audio: Box<dyn Read> = Box::new(io::empty());
+    for (source, input) in sources.iter().zip(inputs) {
+        let mut file = File::open(input.as_ref())?;
+        file.seek(SeekFrom::Start(source.location.offset))?;
+        audio = Box::new(audio.chain(file.take(source.length as u64 * source.block_align)));
+    }
+    debug!("Writing {} samples to {output}", result.length);
+    // The output may be one of the inputs, so it is only replaced once written
+    replace_file(Path::new(output), |file| {
+        write_wav(
+            file,
+            &first.format_chunk(),
+            &info,
+            &result.markers,
+            audio,
+            size,
+        )
+    })?;
+
+    Ok(result)
+}
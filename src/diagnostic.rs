@@ -0,0 +1,58 @@
+//! Non-fatal issues reported while exporting or editing markers.
+//!
+//! Operations that have to adapt markers to the limits of a target format
+//! (rounding, dropped or clamped markers, ...) return a list of [`Diagnostic`]s
+//! alongside their result instead of failing.
+
+use serde::Serialize;
+use std::fmt;
+
+/// How important a [`Diagnostic`] is.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize)]
+pub enum Severity {
+    /// Something was adjusted as expected, e.g. a position was rounded
+    Info,
+    /// The output differs from the input in a way the user should check
+    Warning,
+}
+
+/// A non-fatal issue, optionally tied to a single marker.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct Diagnostic {
+    /// Severity of the issue
+    pub severity: Severity,
+    /// ID of the marker the issue refers to, if any
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub marker_id: Option<u32>,
+    /// Human-readable description
+    pub message: String,
+}
+
+impl Diagnostic {
+    /// Creates an informational diagnostic.
+    pub fn info(marker_id: Option<u32>, message: impl Into<String>) -> Self {
+        Diagnostic {
+            severity: Severity::Info,
+            marker_id,
+            message: message.into(),
+        }
+    }
+
+    /// Creates a warning diagnostic.
+    pub fn warning(marker_id: Option<u32>, message: impl Into<String>) -> Self {
+        Diagnostic {
+            severity: Severity::Warning,
+            marker_id,
+            message: message.into(),
+        }
+    }
+}
+
+impl fmt::Display for Diagnostic {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.marker_id {
+            Some(id) => write!(f, "marker {id}: {}", self.message),
+            None => write!(f, "{}", self.message),
+        }
+    }
+}
@@ -0,0 +1,310 @@
+//! Differences between the markers of two files, e.g. two renders of the same mix.
+//!
+//! [`WavData::diff`] matches the markers and regions of two files and reports those
+//! added, removed, renamed or moved as [`Change`]s. Markers are matched in passes, in the
+//! order given by [`DiffOptions::match_by`]: by cue point ID, by name (the nearest one
+//! wins), and by nearest position within the tolerance. Only markers of the same type
+//! match.
+//!
+//! ```
+//! use reaper_regions::diff::DiffOptions;
+//! use reaper_regions::{Marker, WavData};
+//!
+//! let old = WavData {
+//!     sample_rate: 48000,
+//!     markers: vec![Marker::new(1, "Verse".to_string(), 0, Some(96000), 48000)],
+//!     ..Default::default()
+//! };
+//! let new = WavData {
+//!     sample_rate: 48000,
+//!     markers: vec![Marker::new(1, "Verse 1".to_string(), 4800, Some(96000), 48000)],
+//!     ..Default::default()
+//! };
+//! let diff = old.diff(&new, &DiffOptions::default());
+//! assert_eq!(diff.changes.len(), 2);
+//! assert_eq!(diff.changes[0].to_string(), "renamed: region 'Verse' at 0.000 s is now 'Verse 1'");
+//! assert_eq!(
+//!     diff.changes[1].to_string(),
+//!     "moved: region 'Verse 1' from 0.000 s to 0.100 s (+0.100 s, +4800 samples)"
+//! );
+//! ```
+
+use std::fmt;
+
+use serde::Serialize;
+
+use crate::select::Filter;
+use crate::transplant::rescale;
+use crate::{Marker, WavData, serialize_f64};
+
+/// How markers of two files are matched.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, strum::Display, strum::EnumString)]
+#[strum(serialize_all = "lowercase", ascii_case_insensitive)]
+#[serde(rename_all = "lowercase")]
+pub enum MatchBy {
+    /// Same cue point ID
+    Id,
+    /// Same name, nearest position
+    Name,
+    /// Nearest position within the tolerance
+    Position,
+}
+
+/// Options for [`WavData::diff`].
+#[derive(Debug, Clone)]
+pub struct DiffOptions {
+    /// Matching passes, in order
+    pub match_by: Vec<MatchBy>,
+    /// Positions closer than this many seconds are considered equal
+    pub tolerance: f64,
+    /// Markers and regions of both files to compare
+    pub filter: Filter,
+}
+
+impl Default for DiffOptions {
+    fn default() -> Self {
+        DiffOptions {
+            match_by: vec![MatchBy::Id, MatchBy::Name, MatchBy::Position],
+            tolerance: 0.001,
+            filter: Filter::All,
+        }
+    }
+}
+
+/// A difference in position, from the old file to the new one.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize)]
+pub struct Delta {
+    /// Difference in samples, at the sample rate of the old file
+    pub samples: i64,
+    /// Difference in seconds
+    #[serde(serialize_with = "serialize_f64")]
+    pub seconds: f64,
+}
+
+impl Delta {
+    fn new(from: u32, to: u32, sample_rate: u32) -> Self {
+        let samples = to as i64 - from as i64;
+        Delta {
+            samples,
+            seconds: samples as f64 / sample_rate as f64,
+        }
+    }
+}
+
+/// A marker or region that differs between two files.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(tag = "change", rename_all = "lowercase")]
+pub enum Change {
+    /// Only in the new file
+    Added { marker: Marker },
+    /// Only in the old file
+    Removed { marker: Marker },
+    /// Matched, with a different name
+    Renamed { old: Marker, new: Marker },
+    /// Matched, at a different position or with a different end
+    Moved {
+        old: Marker,
+        new: Marker,
+        /// Change of the start
+        start: Delta,
+        /// Change of the end of a region
+        #[serde(skip_serializing_if = "Option::is_none")]
+        end: Option<Delta>,
+    },
+}
+
+impl fmt::Display for Change {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Change::Added { marker } => write!(
+                f,
+                "added: {} '{}' at {:.3} s",
+                marker.r#type, marker.name, marker.start_time
+            ),
+            Change::Removed { marker } => write!(
+                f,
+                "removed: {} '{}' at {:.3} s",
+                marker.r#type, marker.name, marker.start_time
+            ),
+            Change::Renamed { old, new } => write!(
+                f,
+                "renamed: {} '{}' at {:.3} s is now '{}'",
+                old.r#type, old.name, old.start_time, new.name
+            ),
+            Change::Moved {
+                old,
+                new,
+                start,
+                end,
+            } => {
+                write!(
+                    f,
+                    "moved: {} '{}' from {:.3} s to {:.3} s ({:+.3} s, {:+} samples)",
+                    new.r#type,
+                    new.name,
+                    old.start_time,
+                    new.start_time,
+                    start.seconds,
+                    start.samples
+                )?;
+                if let (Some(end), Some(old_end), Some(new_end)) = (end, old.end_time, new.end_time)
+                {
+                    write!(
+                        f,
+                        ", end from {old_end:.3} s to {new_end:.3} s ({:+.3} s, {:+} samples)",
+                        end.seconds, end.samples
+                    )?;
+                }
+                Ok(())
+            }
+        }
+    }
+}
+
+/// The differences found by [`WavData::diff`].
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct Diff {
+    /// Changes, by position
+    pub changes: Vec<Change>,
+}
+
+impl Diff {
+    /// Whether the files have the same markers.
+    pub fn is_empty(&self) -> bool {
+        self.changes.is_empty()
+    }
+}
+
+/// Whether two markers are at the same position, within `tolerance` samples.
+pub(crate) fn same_position(a: &Marker, b: &Marker, tolerance: u32) -> bool {
+    a.start.abs_diff(b.start) <= tolerance
+        && match (a.end, b.end) {
+            (Some(x), Some(y)) => x.abs_diff(y) <= tolerance,
+            _ => true,
+        }
+}
+
+/// Matches markers of two lists in passes, returning for each marker of `old` the index
+/// of its match in `new` and the pass that found it.
+pub(crate) fn match_markers(
+    old: &[Marker],
+    new: &[Marker],
+    match_by: &[MatchBy],
+    tolerance: u32,
+) -> Vec<Option<(usize, MatchBy)>> {
+    let mut matches = vec![None; old.len()];
+    let mut used = vec![false; new.len()];
+    for &pass in match_by {
+        for (o, marker) in old.iter().enumerate() {
+            if matches[o].is_some() {
+                continue;
+            }
+            let candidates = new.iter().enumerate().filter(|(n, other)| {
+                !used[*n]
+                    && other.r#type == marker.r#type
+                    && match pass {
+                        MatchBy::Id => other.id == marker.id,
+                        MatchBy::Name => other.name == marker.name,
+                        MatchBy::Position => same_position(marker, other, tolerance),
+                    }
+            });
+            let nearest = candidates.min_by_key(|(_, other)| marker.start.abs_diff(other.start));
+            if let Some((n, _)) = nearest {
+                used[n] = true;
+                matches[o] = Some((n, pass));
+            }
+        }
+    }
+    matches
+}
+
+impl WavData {
+    /// Compares the markers of this file (the old one) with those of another.
+    ///
+    /// If the sample rates differ, the positions of `other` are converted to the sample
+    /// rate of this file before matching, and deltas are in samples at this rate.
+    /// Changes are sorted by their position in the new file, or the old one for removed
+    /// markers; a marker both renamed and moved is reported twice.
+    pub fn diff(&self, other: &WavData, options: &DiffOptions) -> Diff {
+        let sample_rate = self.sample_rate;
+        let old: Vec<Marker> = self
+            .markers
+            .iter()
+            .filter(|marker| options.filter.matches(marker))
+            .cloned()
+            .collect();
+        let new: Vec<Marker> = other
+            .markers
+            .iter()
+            .filter(|marker| options.filter.matches(marker))
+            .map(|marker| {
+                let convert = |position| {
+                    rescale(position, other.sample_rate, sample_rate).min(u32::MAX as u64) as u32
+                };
+                Marker::new(
+                    marker.id,
+                    marker.name.clone(),
+                    convert(marker.start),
+                    marker.end.map(convert),
+                    sample_rate,
+                )
+            })
+            .collect();
+        let tolerance = (options.tolerance * sample_rate as f64).round() as u32;
+        let matches = match_markers(&old, &new, &options.match_by, tolerance);
+
+        let mut changes = Vec::new();
+        let mut matched = vec![false; new.len()];
+        for (old, found) in old.iter().zip(&matches) {
+            let Some((n, _)) = *found else {
+                changes.push((
+                    old.start,
+                    Change::Removed {
+                        marker: old.clone(),
+                    },
+                ));
+                continue;
+            };
+            matched[n] = true;
+            let new = &new[n];
+            if old.name != new.name {
+                changes.push((
+                    new.start,
+                    Change::Renamed {
+                        old: old.clone(),
+                        new: new.clone(),
+                    },
+                ));
+            }
+            if !same_position(old, new, tolerance) {
+                let end = match (old.end, new.end) {
+                    (Some(old_end), Some(new_end)) if old_end.abs_diff(new_end) > tolerance => {
+                        Some(Delta::new(old_end, new_end, sample_rate))
+                    }
+                    _ => None,
+                };
+                changes.push((
+                    new.start,
+                    Change::Moved {
+                        old: old.clone(),
+                        new: new.clone(),
+                        start: Delta::new(old.start, new.start, sample_rate),
+                        end,
+                    },
+                ));
+            }
+        }
+        for (marker, _) in new.iter().zip(&matched).filter(|(_, matched)| !**matched) {
+            changes.push((
+                marker.start,
+                Change::Added {
+                    marker: marker.clone(),
+                },
+            ));
+        }
+        changes.sort_by_key(|(position, _)| *position);
+        Diff {
+            changes: changes.into_iter().map(|(_, change)| change).collect(),
+        }
+    }
+}
@@ -0,0 +1,419 @@
+//! Cropping, padding and trimming WAV files while keeping their markers in place.
+//!
+//! The edit operations of [`RiffFile`] rewrite the `data` chunk and shift every cue point,
+//! `smpl` loop and `ltxt` segment to the new start of the audio. Markers that end up
+//! outside the audio are dropped along with their labels, or clamped to its edges (see
+//! [`OutOfRange`]); regions that stick out are shortened. Each change is reported as a
+//! [`Diagnostic`].
+//!
+//! The `fact` sample count and the `bext` time reference are updated too.
+//!
+//! ```rust,no_run
+//! use reaper_regions::edit::OutOfRange;
+//! use reaper_regions::wavtag::RiffFile;
+//!
+//! let mut riff = RiffFile::read(std::fs::File::open("in.wav").unwrap(), "in.wav".into()).unwrap();
+//! // Cut the first second at 48 kHz and leave two seconds of silence for a sync offset
+//! let mut diagnostics = riff.trim_start(48_000, OutOfRange::Drop).unwrap();
+//! diagnostics.extend(riff.insert_silence(96_000).unwrap());
+//! riff.write(std::fs::File::create("out.wav").unwrap()).unwrap();
+//! ```
+
+use std::collections::{HashMap, HashSet};
+
+use crate::ParseError;
+use crate::diagnostic::Diagnostic;
+use crate::wavtag::{ChunkType, RiffFile};
+
+/// Offset of the 64-bit TimeReference in a `bext` chunk.
+const BEXT_TIME_REFERENCE: usize = 338;
+
+/// What happens to markers outside the audio left after an edit.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, strum::Display, strum::EnumString)]
+#[strum(serialize_all = "lowercase", ascii_case_insensitive)]
+pub enum OutOfRange {
+    /// Remove them, along with their labels and loops
+    #[default]
+    Drop,
+    /// Move them to the nearest edge of the audio; regions become empty
+    Clamp,
+}
+
+/// Maps positions of the old audio to the new one: the samples `from..to` are kept and
+/// `pad` samples of silence are inserted before them.
+struct Rebase {
+    from: u32,
+    to: u32,
+    pad: u32,
+    out_of_range: OutOfRange,
+}
+
+/// Where a position or range ended up.
+pub(crate) enum Mapped<T> {
+    /// Inside the kept audio, shifted
+    Kept(T),
+    /// Partly outside: a region that was shortened
+    Cut(T),
+    /// Outside and moved to the nearest edge
+    Clamped(T),
+    /// Outside and removed
+    Dropped,
+}
+
+impl Rebase {
+    fn shift(&self, position: u32) -> u32 {
+        (position.clamp(self.from, self.to) - self.from).saturating_add(self.pad)
+    }
+
+    fn point(&self, position: u32) -> Mapped<u32> {
+        if (self.from..=self.to).contains(&position) {
+            Mapped::Kept(self.shift(position))
+        } else if self.out_of_range == OutOfRange::Clamp {
+            Mapped::Clamped(self.shift(position))
+        } else {
+            Mapped::Dropped
+        }
+    }
+
+    fn range(&self, start: u32, end: u32) -> Mapped<(u32, u32)> {
+        let shifted = (self.shift(start), self.shift(end));
+        if start >= self.from && end <= self.to {
+            Mapped::Kept(shifted)
+        } else if start < self.to && end > self.from {
+            Mapped::Cut(shifted)
+        } else if self.out_of_range == OutOfRange::Clamp {
+            Mapped::Clamped(shifted)
+        } else {
+            Mapped::Dropped
+        }
+    }
+}
+
+/// Records the diagnostic of a mapped marker and returns its new position, if kept.
+pub(crate) fn report<T>(
+    diagnostics: &mut Vec<Diagnostic>,
+    id: u32,
+    mapped: Mapped<T>,
+    what: &str,
+) -> Option<T> {
+    match mapped {
+        Mapped::Kept(value) => Some(value),
+        Mapped::Cut(value) => {
+            diagnostics.push(Diagnostic::info(
+                Some(id),
+                format!("{what} cut at the edge of the audio"),
+            ));
+            Some(value)
+        }
+        Mapped::Clamped(value) => {
+            diagnostics.push(Diagnostic::warning(
+                Some(id),
+                format!("{what} outside the audio, moved to its edge"),
+            ));
+            Some(value)
+        }
+        Mapped::Dropped => {
+            diagnostics.push(Diagnostic::warning(
+                Some(id),
+                format!("{what} outside the audio, removed"),
+            ));
+            None
+        }
+    }
+}
+
+fn read_u32(data: &[u8], offset: usize) -> u32 {
+    u32::from_le_bytes([
+        data[offset],
+        data[offset + 1],
+        data[offset + 2],
+        data[offset + 3],
+    ])
+}
+
+fn write_u32(data: &mut [u8], offset: usize, value: u32) {
+    data[offset..offset + 4].copy_from_slice(&value.to_le_bytes());
+}
+
+impl RiffFile {
+    /// Length of the audio in samples (per channel).
+    ///
+    /// # Errors
+    /// * [`ParseError::MissingFormatChunk`] - If the format chunk is missing
+    /// * [`ParseError::InvalidFormatChunk`] - If the format chunk has no block alignment
+    /// * [`ParseError::Other`] - If there is no 'data' chunk
+    pub fn audio_length(&self) -> Result<u32, ParseError> {
+        let (block_align, _) = self.block_format()?;
+        let data = self
+            .find_chunk_by_type(ChunkType::Data)
+            .ok_or_else(|| ParseError::Other("no 'data' chunk found".into()))?;
+        Ok((data.data.len() / block_align) as u32)
+    }
+
+    /// Keeps only the samples `start..end` of the audio.
+    ///
+    /// # Errors
+    /// * [`ParseError::Other`] - If the range is empty or past the end of the audio
+    pub fn crop(
+        &mut self,
+        start: u32,
+        end: u32,
+        out_of_range: OutOfRange,
+    ) -> Result<Vec<Diagnostic>, ParseError> {
+        let length = self.audio_length()?;
+        if start >= end || end > length {
+            return Err(ParseError::Other(format!(
+                "cannot crop to samples {start}..{end} of {length}"
+            )));
+        }
+        self.rebase(Rebase {
+            from: start,
+            to: end,
+            pad: 0,
+            out_of_range,
+        })
+    }
+
+    /// Keeps only the audio of a region, from its `smpl` loop.
+    ///
+    /// # Errors
+    /// * [`ParseError::Other`] - If there is no region with the ID
+    pub fn crop_to_region(
+        &mut self,
+        id: u32,
+        out_of_range: OutOfRange,
+    ) -> Result<Vec<Diagnostic>, ParseError> {
+        let sampler = match self.find_chunk_by_type(ChunkType::Sampler) {
+            Some(chunk) => crate::wavtag::SamplerChunk::from_chunk(chunk)?,
+            None => Default::default(),
+        };
+        let region = sampler
+            .sample_loops
+            .iter()
+            .find(|sample_loop| sample_loop.id == id)
+            .ok_or_else(|| ParseError::Other(format!("no region with ID {id}")))?;
+        let end = region.end.min(self.audio_length()?);
+        self.crop(region.start, end, out_of_range)
+    }
+
+    /// Inserts `samples` samples of silence before the audio.
+    ///
+    /// All markers are moved, even those past the end of the audio.
+    pub fn insert_silence(&mut self, samples: u32) -> Result<Vec<Diagnostic>, ParseError> {
+        self.rebase(Rebase {
+            from: 0,
+            to: u32::MAX,
+            pad: samples,
+            out_of_range: OutOfRange::Drop,
+        })
+    }
+
+    /// Removes the first `samples` samples of the audio.
+    pub fn trim_start(
+        &mut self,
+        samples: u32,
+        out_of_range: OutOfRange,
+    ) -> Result<Vec<Diagnostic>, ParseError> {
+        let length = self.audio_length()?;
+        self.crop(samples, length, out_of_range)
+    }
+
+    /// Removes the last `samples` samples of the audio.
+    pub fn trim_end(
+        &mut self,
+        samples: u32,
+        out_of_range: OutOfRange,
+    ) -> Result<Vec<Diagnostic>, ParseError> {
+        let length = self.audio_length()?;
+        self.crop(0, length.saturating_sub(samples), out_of_range)
+    }
+
+    /// Block alignment and silence byte of the audio format.
+    fn block_format(&self) -> Result<(usize, u8), ParseError> {
+        let format = self
+            .find_chunk_by_type(ChunkType::Format)
+            .ok_or(ParseError::MissingFormatChunk)?;
+        if format.data.len() < 16 {
+            return Err(ParseError::InvalidFormatChunk(format.data.len()));
+        }
+        let format_tag = u16::from_le_bytes([format.data[0], format.data[1]]);
+        let block_align = u16::from_le_bytes([format.data[12], format.data[13]]) as usize;
+        let bits_per_sample = u16::from_le_bytes([format.data[14], format.data[15]]);
+        if block_align == 0 {
+            return Err(ParseError::InvalidFormatChunk(format.data.len()));
+        }
+        // 8-bit PCM is unsigned
+        let silence = if format_tag == 1 && bits_per_sample == 8 {
+            0x80
+        } else {
+            0
+        };
+        Ok((block_align, silence))
+    }
+
+    /// Rewrites the audio and moves the markers as described by a [`Rebase`].
+    fn rebase(&mut self, rebase: Rebase) -> Result<Vec<Diagnostic>, ParseError> {
+        let (block_align, silence) = self.block_format()?;
+        let mut diagnostics = Vec::new();
+        let cue_positions = self.cue_positions();
+
+        // Regions: the loops of the smpl chunk, which also set the cue points of regions
+        let mut regions = HashMap::new();
+        let mut dropped = HashSet::new();
+        if let Some(chunk) = self
+            .chunks
+            .iter_mut()
+            .find(|chunk| chunk.header == ChunkType::Sampler)
+        {
+            let mut sampler = crate::wavtag::SamplerChunk::from_chunk(chunk)?;
+            sampler.sample_loops.retain_mut(|sample_loop| {
+                let mapped = rebase.range(sample_loop.start, sample_loop.end);
+                match report(&mut diagnostics, sample_loop.id, mapped, "region") {
+                    Some((start, end)) => {
+                        regions.insert(sample_loop.id, start);
+                        sample_loop.start = start;
+                        sample_loop.end = end;
+                        true
+                    }
+                    None => {
+                        dropped.insert(sample_loop.id);
+                        false
+                    }
+                }
+            });
+            chunk.data = sampler.serialise();
+        }
+
+        // Cue points
+        for chunk in &mut self.chunks {
+            if chunk.header != ChunkType::Cue || chunk.data.len() < 4 {
+                continue;
+            }
+            let count = read_u32(&chunk.data, 0) as usize;
+            let mut data = Vec::with_capacity(chunk.data.len());
+            let mut kept = 0u32;
+            data.extend(0u32.to_le_bytes());
+            for record in chunk.data[4..].chunks_exact(24).take(count) {
+                let mut record = record.to_vec();
+                let id = read_u32(&record, 0);
+                let offset = read_u32(&record, 20);
+                let new_offset = if dropped.contains(&id) {
+                    None
+                } else if let Some(&start) = regions.get(&id) {
+                    Some(start)
+                } else {
+                    report(&mut diagnostics, id, rebase.point(offset), "marker")
+                };
+                let Some(new_offset) = new_offset else {
+                    dropped.insert(id);
+                    continue;
+                };
+                // REAPER writes the sample offset as the play order position too
+                if read_u32(&record, 4) == offset {
+                    write_u32(&mut record, 4, new_offset);
+                }
+                write_u32(&mut record, 20, new_offset);
+                data.extend(record);
+                kept += 1;
+            }
+            write_u32(&mut data, 0, kept);
+            chunk.data = data;
+        }
+
+        // Labels of removed markers, and ltxt segments
+        self.chunks.retain(|chunk| {
+            !(chunk.header == ChunkType::Label
+                && chunk.data.len() >= 4
+                && dropped.contains(&read_u32(&chunk.data, 0)))
+        });
+        for chunk in &mut self.chunks {
+            if chunk.header == ChunkType::List && chunk.data.starts_with(b"adtl") {
+                chunk.data = rebase_adtl(&chunk.data, &dropped, &cue_positions, &rebase);
+            }
+        }
+
+        // Audio
+        let to = rebase.to.min(self.audio_length()?);
+        let new_length = rebase.pad + to - rebase.from;
+        for chunk in &mut self.chunks {
+            match &chunk.header {
+                ChunkType::Data => {
+                    let mut data = vec![silence; rebase.pad as usize * block_align];
+                    data.extend(
+                        &chunk.data[rebase.from as usize * block_align..to as usize * block_align],
+                    );
+                    chunk.data = data;
+                }
+                ChunkType::Fact if chunk.data.len() >= 4 => {
+                    write_u32(&mut chunk.data, 0, new_length)
+                }
+                ChunkType::Unknown(tag)
+                    if tag == "bext" && chunk.data.len() >= BEXT_TIME_REFERENCE + 8 =>
+                {
+                    let range = BEXT_TIME_REFERENCE..BEXT_TIME_REFERENCE + 8;
+                    let reference =
+                        u64::from_le_bytes(chunk.data[range.clone()].try_into().unwrap());
+                    let reference =
+                        (reference + rebase.from as u64).saturating_sub(rebase.pad as u64);
+                    chunk.data[range].copy_from_slice(&reference.to_le_bytes());
+                }
+                _ => {}
+            }
+        }
+
+        Ok(diagnostics)
+    }
+
+    /// Sample offsets of the cue points, by ID.
+    fn cue_positions(&self) -> HashMap<u32, u32> {
+        let mut positions = HashMap::new();
+        for chunk in self.find_chunks_by_type(ChunkType::Cue) {
+            if chunk.data.len() < 4 {
+                continue;
+            }
+            let count = read_u32(&chunk.data, 0) as usize;
+            for record in chunk.data[4..].chunks_exact(24).take(count) {
+                positions.insert(read_u32(record, 0), read_u32(record, 20));
+            }
+        }
+        positions
+    }
+}
+
+/// Removes the subchunks of dropped cue points from a `LIST`-`adtl` chunk and updates the
+/// sample lengths of `ltxt` segments, from the cue positions before the edit.
+fn rebase_adtl(
+    data: &[u8],
+    dropped: &HashSet<u32>,
+    cue_positions: &HashMap<u32, u32>,
+    rebase: &Rebase,
+) -> Vec<u8> {
+    let mut output = b"adtl".to_vec();
+    let mut pos = 4;
+    while pos + 8 <= data.len() {
+        let size = read_u32(data, pos + 4) as usize;
+        let end = pos.saturating_add(8 + size).min(data.len());
+        let mut subchunk = data[pos..end].to_vec();
+        pos = pos.saturating_add(8 + size + size % 2);
+        // Too short to hold a cue point ID, so copied through as it is
+        let id = (subchunk.len() >= 12).then(|| read_u32(&subchunk, 8));
+        if id.is_some_and(|id| dropped.contains(&id)) {
+            continue;
+        }
+        if let Some(id) = id
+            && &subchunk[0..4] == b"ltxt"
+            && subchunk.len() >= 16
+        {
+            let length = read_u32(&subchunk, 12);
+            if let Some(&start) = cue_positions.get(&id) {
+                let end = start.saturating_add(length);
+                write_u32(&mut subchunk, 12, rebase.shift(end) - rebase.shift(start));
+            }
+        }
+        if subchunk.len() % 2 == 1 {
+            subchunk.push(0);
+        }
+        output.extend(subchunk);
+    }
+    output
+}
@@ -0,0 +1,136 @@
+//! Audacity label track import and export.
+//!
+//! Audacity label files are plain text with one label per line:
+//!
+//! ```text
+//! start<TAB>end<TAB>name
+//! ```
+//!
+//! Times are in seconds. Point labels have `start` equal to `end`.
+//! Lines starting with `\` hold the frequency range of spectral selections and are ignored on import.
+
+use crate::{Marker, MarkerType, ParseError, WavData};
+
+/// Exports markers and regions as an Audacity label track.
+///
+/// Markers become point labels and regions become range labels. Times are written
+/// at full precision (not rounded with [`round3`](crate::round3)), so that importing
+/// the labels at the same sample rate gives back the exact sample positions.
+///
+/// # Example
+/// ```
+/// use reaper_regions::formats::audacity;
+/// use reaper_regions::{Marker, WavData};
+///
+/// let data = WavData {
+///     sample_rate: 48000,
+///     markers: vec![
+///         Marker::new(1, "Intro".to_string(), 12000, None, 48000),
+///         Marker::new(2, "Song".to_string(), 48000, Some(96000), 48000),
+///     ],
+///     ..WavData::default()
+/// };
+/// assert_eq!(audacity::export(&data), "0.25\t0.25\tIntro\n1\t2\tSong\n");
+/// ```
+pub fn export(data: &WavData) -> String {
+    let mut output = String::new();
+    for marker in &data.markers {
+        let end_time = match marker.r#type {
+            MarkerType::Region => marker.end_time.unwrap_or(marker.start_time),
+            MarkerType::Marker => marker.start_time,
+        };
+        output.push_str(&format!(
+            "{}\t{}\t{}\n",
+            marker.start_time,
+            end_time,
+            marker.name.replace(['\t', '\r', '\n'], " ")
+        ));
+    }
+    output
+}
+
+/// Parses an Audacity label track into markers.
+///
+/// Point labels become markers and range labels become regions. Marker IDs are
+/// assigned from 1 in file order.
+///
+/// # Arguments
+/// * `text` - Contents of the label file
+/// * `sample_rate` - Sample rate used to convert seconds into sample positions
+///
+/// # Errors
+/// * [`ParseError::InvalidLine`] - If a line doesn't start with two valid times,
+///   or a label ends before it starts
+///
+/// # Example
+/// ```
+/// use reaper_regions::formats::audacity;
+/// use reaper_regions::MarkerType;
+///
+/// let markers = audacity::import("0.5\t0.5\tIntro\n1.0\t2.0\tSong\n", 48000).unwrap();
+/// assert_eq!(markers[0].r#type, MarkerType::Marker);
+/// assert_eq!(markers[0].start, 24000);
+/// assert_eq!(markers[1].r#type, MarkerType::Region);
+/// assert_eq!(markers[1].end, Some(96000));
+/// ```
+pub fn import(text: &str, sample_rate: u32) -> Result<Vec<Marker>, ParseError> {
+    let mut markers = Vec::new();
+
+    for (index, line) in text.lines().enumerate() {
+        if line.trim().is_empty() || line.starts_with('\\') {
+            continue;
+        }
+        let invalid = || ParseError::InvalidLine(index + 1, line.to_string());
+
+        let mut fields = line.splitn(3, '\t');
+        let mut next_time = || -> Result<f64, ParseError> {
+            let seconds: f64 = fields
+                .next()
+                .and_then(|field| field.trim().parse().ok())
+                .ok_or_else(invalid)?;
+            if !seconds.is_finite() || seconds < 0.0 {
+                return Err(invalid());
+            }
+            Ok(seconds)
+        };
+        let start_time = next_time()?;
+        let end_time = next_time()?;
+        if end_time < start_time {
+            return Err(invalid());
+        }
+        let name = fields.next().unwrap_or_default().trim_end_matches('\r');
+
+        let start = seconds_to_samples(start_time, sample_rate);
+        let end = seconds_to_samples(end_time, sample_rate);
+        let end = (end > start).then_some(end);
+        let id = markers.len() as u32 + 1;
+
+        markers.push(Marker::new(id, name.to_string(), start, end, sample_rate));
+    }
+
+    Ok(markers)
+}
+
+/// Reads an Audacity label file into [`WavData`].
+///
+/// # Arguments
+/// * `file_path` - Path to the label file
+/// * `sample_rate` - Sample rate used to convert seconds into sample positions
+///
+/// # Errors
+/// * [`ParseError::Io`] - If the file cannot be read
+/// * [`ParseError::InvalidLine`] - If the file is not a valid label track
+pub fn import_file(file_path: &str, sample_rate: u32) -> Result<WavData, ParseError> {
+    let text = std::fs::read_to_string(file_path)?;
+    Ok(WavData {
+        path: file_path.to_string(),
+        sample_rate,
+        markers: import(&text, sample_rate)?,
+        ..WavData::default()
+    })
+}
+
+/// Converts seconds to the nearest sample position.
+fn seconds_to_samples(seconds: f64, sample_rate: u32) -> u32 {
+    (seconds * sample_rate as f64).round() as u32
+}
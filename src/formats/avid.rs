@@ -0,0 +1,117 @@
+//! Avid Media Composer marker list export.
+//!
+//! Media Composer imports markers from tab-delimited text files with one marker per line:
+//!
+//! ```text
+//! user<TAB>timecode<TAB>track<TAB>color<TAB>comment<TAB>duration
+//! ```
+//!
+//! The duration is given in frames, so regions land on the sequence as spanned markers.
+
+use crate::timecode::{FrameRate, Timecode};
+use crate::{MarkerType, WavData};
+
+/// Marker colors supported by Avid Media Composer.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, strum::Display, strum::EnumString)]
+#[strum(serialize_all = "lowercase", ascii_case_insensitive)]
+pub enum AvidColor {
+    Red,
+    Green,
+    Blue,
+    Cyan,
+    Magenta,
+    Yellow,
+    Black,
+    White,
+}
+
+/// Options for [`export`].
+#[derive(Debug, Clone)]
+pub struct AvidOptions {
+    /// User name written in the first column
+    pub user: String,
+    /// Track the markers are placed on (e.g. `A1`, `V1`, `TC`)
+    pub track: String,
+    /// Timecode of the sequence start, which corresponds to sample 0 of the WAV file.
+    /// Its frame rate is the frame rate of the Avid sequence.
+    pub start_timecode: Timecode,
+    /// Color used for simple markers
+    pub marker_color: AvidColor,
+    /// Color used for regions
+    pub region_color: AvidColor,
+}
+
+impl Default for AvidOptions {
+    fn default() -> Self {
+        AvidOptions {
+            user: "reaper-regions".to_string(),
+            track: "A1".to_string(),
+            // 01:00:00:00 at 25 fps, the usual sequence start
+            start_timecode: Timecode::new(3600 * 25, FrameRate::Fps25),
+            marker_color: AvidColor::Red,
+            region_color: AvidColor::Green,
+        }
+    }
+}
+
+/// Exports markers and regions as an Avid Media Composer marker list.
+///
+/// Positions are rounded to the nearest frame at the frame rate of `options.start_timecode`
+/// and offset by it. Regions get their duration converted to frames (at least 1);
+/// simple markers always have a duration of 1 frame.
+///
+/// # Arguments
+/// * `data` - Parsed WAV data containing the markers
+/// * `options` - User, track, frame rate, start timecode and colors
+///
+/// # Returns
+/// * `String` - The marker list, one line per marker
+///
+/// # Example
+/// ```
+/// use reaper_regions::formats::avid::{self, AvidOptions};
+/// use reaper_regions::{Marker, WavData};
+///
+/// let data = WavData {
+///     sample_rate: 48000,
+///     markers: vec![Marker::new(1, "Song".to_string(), 48000, Some(144000), 48000)],
+///     ..WavData::default()
+/// };
+/// let list = avid::export(&data, &AvidOptions::default());
+/// assert_eq!(list, "reaper-regions\t01:00:01:00\tA1\tgreen\tSong\t50\n");
+/// ```
+pub fn export(data: &WavData, options: &AvidOptions) -> String {
+    let start = options.start_timecode;
+    let rate = start.rate;
+
+    let mut output = String::new();
+    for marker in &data.markers {
+        let start_frame = rate.samples_to_frames(marker.start as u64, data.sample_rate);
+        let (color, duration) = match (marker.r#type, marker.end) {
+            (MarkerType::Region, Some(end)) => {
+                let end_frame = rate.samples_to_frames(end as u64, data.sample_rate);
+                (
+                    options.region_color,
+                    end_frame.saturating_sub(start_frame).max(1),
+                )
+            }
+            _ => (options.marker_color, 1),
+        };
+
+        output.push_str(&format!(
+            "{}\t{}\t{}\t{}\t{}\t{}\n",
+            sanitize(&options.user),
+            start.add_frames(start_frame),
+            sanitize(&options.track),
+            color,
+            sanitize(&marker.name),
+            duration
+        ));
+    }
+    output
+}
+
+/// Replaces tabs and line breaks, which would break the tab-delimited layout, with spaces.
+fn sanitize(value: &str) -> String {
+    value.replace(['\t', '\r', '\n'], " ")
+}
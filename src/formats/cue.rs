@@ -0,0 +1,742 @@
+//! CUE sheet import and export for CD and DDP mastering.
+//!
+//! ## Export
+//!
+//! Regions become tracks (`TRACK nn AUDIO`) titled after the region name, and markers
+//! inside a region become additional index points (`INDEX 02`, `INDEX 03`, ...) of
+//! that track. Silence between two regions is written as the pregap (`INDEX 00`) of
+//! the following track. If the file has no regions, each marker starts a track instead.
+//!
+//! CUE sheet positions are `mm:ss:ff` with 75 frames per second, so every position is
+//! rounded to a CD frame using [`CdFrameRounding`]. Each rounded position is reported
+//! as a [`Diagnostic`].
+//!
+//! ## Import
+//!
+//! [`CueSheet::parse`] reads the `FILE`, `TRACK`, `INDEX`, `TITLE`, `PERFORMER` and `REM`
+//! commands of a CUE sheet. Each track becomes a region from its `INDEX 01` to the next
+//! track's `INDEX 01` (or the end of the audio), and index points after `INDEX 01` become
+//! markers. Multiple `FILE`s are laid end to end.
+
+use super::Export;
+use crate::diagnostic::Diagnostic;
+use crate::{Marker, MarkerType, ParseError, WavData, parse_markers_from_file};
+use log::{debug, warn};
+use std::collections::BTreeMap;
+use std::path::Path;
+
+/// CD frames (sectors) per second.
+pub const CD_FRAMES_PER_SECOND: u64 = 75;
+
+/// Maximum number of tracks (and of index points per track) on a CD.
+const MAX_CD_NUMBER: usize = 99;
+
+/// How sample positions are rounded to CD frames.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, strum::Display, strum::EnumString)]
+#[strum(serialize_all = "lowercase", ascii_case_insensitive)]
+pub enum CdFrameRounding {
+    /// Round to the nearest frame
+    #[default]
+    Nearest,
+    /// Round down to the frame containing the position
+    Down,
+    /// Round up to the next frame boundary
+    Up,
+}
+
+impl CdFrameRounding {
+    /// Converts a sample position to CD frames.
+    ///
+    /// # Example
+    /// ```
+    /// use reaper_regions::formats::cue::CdFrameRounding;
+    ///
+    /// // One CD frame is 588 samples at 44.1 kHz
+    /// assert_eq!(CdFrameRounding::Nearest.samples_to_frames(300, 44100), 1);
+    /// assert_eq!(CdFrameRounding::Down.samples_to_frames(300, 44100), 0);
+    /// assert_eq!(CdFrameRounding::Up.samples_to_frames(1, 44100), 1);
+    /// ```
+    pub fn samples_to_frames(self, samples: u32, sample_rate: u32) -> u64 {
+        let scaled = samples as u64 * CD_FRAMES_PER_SECOND;
+        let sample_rate = sample_rate as u64;
+        match self {
+            CdFrameRounding::Nearest => (scaled * 2 + sample_rate) / (sample_rate * 2),
+            CdFrameRounding::Down => scaled / sample_rate,
+            CdFrameRounding::Up => scaled.div_ceil(sample_rate),
+        }
+    }
+}
+
+/// Options for [`export`].
+#[derive(Debug, Clone)]
+pub struct CueOptions {
+    /// Audio file name for the `FILE` line (defaults to the file name of the WAV)
+    pub file: Option<String>,
+    /// Album title (defaults to the `IPRD` or `INAM` INFO tag)
+    pub title: Option<String>,
+    /// Album performer (defaults to the `IART` INFO tag)
+    pub performer: Option<String>,
+    /// Write markers inside regions as index points
+    pub index_markers: bool,
+    /// How positions are rounded to CD frames
+    pub rounding: CdFrameRounding,
+}
+
+impl Default for CueOptions {
+    fn default() -> Self {
+        CueOptions {
+            file: None,
+            title: None,
+            performer: None,
+            index_markers: true,
+            rounding: CdFrameRounding::Nearest,
+        }
+    }
+}
+
+/// A track to be written, before rounding.
+struct Track<'a> {
+    marker: &'a Marker,
+    indexes: Vec<&'a Marker>,
+}
+
+/// Exports regions (or markers) as a CUE sheet.
+///
+/// # Arguments
+/// * `data` - Parsed WAV data containing the markers
+/// * `options` - File name, album metadata, index points and rounding
+///
+/// # Returns
+/// * [`Export`] - The CUE sheet, with diagnostics for rounded positions,
+///   overlapping regions and markers that couldn't be placed
+///
+/// # Example
+/// ```
+/// use reaper_regions::formats::cue::{self, CueOptions};
+/// use reaper_regions::{Marker, WavData};
+///
+/// let data = WavData {
+///     path: "album.wav".to_string(),
+///     sample_rate: 44100,
+///     markers: vec![
+///         Marker::new(1, "Opener".to_string(), 88200, Some(441000), 44100),
+///         Marker::new(2, "Closer".to_string(), 529200, Some(882000), 44100),
+///     ],
+///     ..WavData::default()
+/// };
+/// let export = cue::export(&data, &CueOptions::default());
+/// assert!(export.output.contains("FILE \"album.wav\" WAVE"));
+/// assert!(export.output.contains("  TRACK 02 AUDIO\n    TITLE \"Closer\"\n    INDEX 00 00:10:00\n    INDEX 01 00:12:00\n"));
+/// assert!(export.diagnostics.is_empty());
+/// ```
+pub fn export(data: &WavData, options: &CueOptions) -> Export {
+    let mut diagnostics = Vec::new();
+    let tracks = plan_tracks(data, options, &mut diagnostics);
+
+    let mut output = String::new();
+    let title = options
+        .title
+        .as_deref()
+        .or(data.info.get("IPRD").map(String::as_str))
+        .or(data.info.get("INAM").map(String::as_str));
+    let performer = options
+        .performer
+        .as_deref()
+        .or(data.info.get("IART").map(String::as_str));
+    if let Some(performer) = performer {
+        output.push_str(&format!("PERFORMER {}\n", quote(performer)));
+    }
+    if let Some(title) = title {
+        output.push_str(&format!("TITLE {}\n", quote(title)));
+    }
+    let file = options.file.clone().unwrap_or_else(|| {
+        Path::new(&data.path)
+            .file_name()
+            .map(|name| name.to_string_lossy().to_string())
+            .unwrap_or_else(|| "audio.wav".to_string())
+    });
+    output.push_str(&format!("FILE {} WAVE\n", quote(&file)));
+
+    // End of the previous region, in samples; the start of the file for the first track
+    let mut previous_end: Option<(&Marker, u32)> = tracks.first().map(|track| (track.marker, 0));
+    for (number, track) in tracks.iter().enumerate() {
+        let start = round(
+            data,
+            options,
+            track.marker,
+            track.marker.start,
+            "start",
+            &mut diagnostics,
+        );
+        output.push_str(&format!("  TRACK {:02} AUDIO\n", number + 1));
+        output.push_str(&format!("    TITLE {}\n", quote(&track.marker.name)));
+        if let Some(performer) = performer {
+            output.push_str(&format!("    PERFORMER {}\n", quote(performer)));
+        }
+        let pregap = previous_end
+            .map(|(marker, end)| round(data, options, marker, end, "end", &mut diagnostics))
+            .filter(|&end| end < start);
+        if let Some(pregap) = pregap {
+            output.push_str(&format!("    INDEX 00 {}\n", format_frames(pregap)));
+        }
+        output.push_str(&format!("    INDEX 01 {}\n", format_frames(start)));
+
+        let mut last = start;
+        // INDEX 01 is the start of the track, INDEX 00 its pregap
+        let mut next_index = 2;
+        for marker in &track.indexes {
+            let position = round(
+                data,
+                options,
+                marker,
+                marker.start,
+                "index point",
+                &mut diagnostics,
+            );
+            if position <= last {
+                diagnostics.push(Diagnostic::warning(
+                    Some(marker.id),
+                    format!(
+                        "'{}' falls on the same CD frame as the previous index point of track {:02}, skipping",
+                        marker.name,
+                        number + 1
+                    ),
+                ));
+                continue;
+            }
+            if next_index > MAX_CD_NUMBER {
+                diagnostics.push(Diagnostic::warning(
+                    Some(marker.id),
+                    format!(
+                        "track {:02} has more than 99 index points, skipping '{}'",
+                        number + 1,
+                        marker.name
+                    ),
+                ));
+                continue;
+            }
+            output.push_str(&format!(
+                "    INDEX {:02} {}\n",
+                next_index,
+                format_frames(position)
+            ));
+            next_index += 1;
+            last = position;
+        }
+
+        previous_end = track.marker.end.map(|end| (track.marker, end));
+    }
+
+    if tracks.len() > MAX_CD_NUMBER {
+        diagnostics.push(Diagnostic::warning(
+            None,
+            format!("{} tracks exceed the CD limit of 99", tracks.len()),
+        ));
+    }
+
+    Export {
+        output,
+        diagnostics,
+    }
+}
+
+/// Decides which markers become tracks and which become index points.
+fn plan_tracks<'a>(
+    data: &'a WavData,
+    options: &CueOptions,
+    diagnostics: &mut Vec<Diagnostic>,
+) -> Vec<Track<'a>> {
+    let mut sorted: Vec<&Marker> = data.markers.iter().collect();
+    sorted.sort_by_key(|m| (m.start, m.id));
+
+    let regions: Vec<&Marker> = sorted
+        .iter()
+        .copied()
+        .filter(|m| m.r#type == MarkerType::Region)
+        .collect();
+
+    if regions.is_empty() {
+        if !sorted.is_empty() {
+            diagnostics.push(Diagnostic::info(
+                None,
+                "no regions found, using markers as track starts",
+            ));
+        }
+        return sorted
+            .into_iter()
+            .map(|marker| Track {
+                marker,
+                indexes: Vec::new(),
+            })
+            .collect();
+    }
+
+    let mut tracks: Vec<Track> = regions
+        .into_iter()
+        .map(|marker| Track {
+            marker,
+            indexes: Vec::new(),
+        })
+        .collect();
+
+    for pair in tracks.windows(2) {
+        let (previous, next) = (pair[0].marker, pair[1].marker);
+        if previous.end.is_some_and(|end| next.start < end) {
+            diagnostics.push(Diagnostic::warning(
+                Some(next.id),
+                format!(
+                    "'{}' overlaps '{}', which is cut short at the start of '{}'",
+                    next.name, previous.name, next.name
+                ),
+            ));
+        }
+    }
+
+    for marker in sorted
+        .into_iter()
+        .filter(|m| m.r#type == MarkerType::Marker)
+    {
+        if !options.index_markers {
+            continue;
+        }
+        // The track playing at the marker position is the last one started before it
+        let track = tracks
+            .iter_mut()
+            .rev()
+            .find(|track| track.marker.start <= marker.start);
+        match track {
+            Some(track) if track.marker.end.is_some_and(|end| marker.start < end) => {
+                track.indexes.push(marker)
+            }
+            _ => diagnostics.push(Diagnostic::warning(
+                Some(marker.id),
+                format!("'{}' is not inside a region, skipping", marker.name),
+            )),
+        }
+    }
+
+    tracks
+}
+
+/// Rounds a sample position to CD frames, reporting the rounding if the position isn't frame-aligned.
+fn round(
+    data: &WavData,
+    options: &CueOptions,
+    marker: &Marker,
+    samples: u32,
+    what: &str,
+    diagnostics: &mut Vec<Diagnostic>,
+) -> u64 {
+    let sample_rate = data.sample_rate as u64;
+    let frames = options
+        .rounding
+        .samples_to_frames(samples, data.sample_rate);
+    if frames * sample_rate != samples as u64 * CD_FRAMES_PER_SECOND {
+        let delta = (frames * sample_rate / CD_FRAMES_PER_SECOND) as i64 - samples as i64;
+        diagnostics.push(Diagnostic::info(
+            Some(marker.id),
+            format!(
+                "'{}' {what} at {samples} samples rounded to CD frame {} ({delta:+} samples)",
+                marker.name,
+                format_frames(frames),
+            ),
+        ));
+    }
+    frames
+}
+
+/// Formats CD frames as `mm:ss:ff`.
+fn format_frames(frames: u64) -> String {
+    format!(
+        "{:02}:{:02}:{:02}",
+        frames / CD_FRAMES_PER_SECOND / 60,
+        (frames / CD_FRAMES_PER_SECOND) % 60,
+        frames % CD_FRAMES_PER_SECOND
+    )
+}
+
+/// Quotes a CUE sheet string. The format has no escapes, so double quotes are replaced.
+fn quote(value: &str) -> String {
+    format!("\"{}\"", value.replace('"', "'").replace(['\r', '\n'], " "))
+}
+
+/// A parsed CUE sheet.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct CueSheet {
+    /// Album title (`TITLE` before the first track)
+    pub title: Option<String>,
+    /// Album performer (`PERFORMER` before the first track)
+    pub performer: Option<String>,
+    /// Album comments (`REM` before the first track), without the `REM` keyword
+    pub rems: Vec<String>,
+    /// Audio files in the order they are played
+    pub files: Vec<CueFile>,
+}
+
+/// A `FILE` entry of a CUE sheet and its tracks.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct CueFile {
+    /// File name as written in the sheet
+    pub name: String,
+    /// File type (e.g. `WAVE`, `BINARY`)
+    pub file_type: String,
+    /// Tracks in this file
+    pub tracks: Vec<CueTrack>,
+}
+
+/// A `TRACK` entry of a CUE sheet.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct CueTrack {
+    /// Track number
+    pub number: u32,
+    /// Track type (e.g. `AUDIO`)
+    pub track_type: String,
+    /// Track title
+    pub title: Option<String>,
+    /// Track performer
+    pub performer: Option<String>,
+    /// Track comments, without the `REM` keyword
+    pub rems: Vec<String>,
+    /// Index points as `(index number, position in CD frames from the start of the file)`
+    pub indexes: Vec<(u32, u64)>,
+}
+
+impl CueTrack {
+    /// Position of `INDEX 01` (or the first index, if there is none) in CD frames.
+    pub fn start(&self) -> Option<u64> {
+        self.indexes
+            .iter()
+            .find(|(number, _)| *number == 1)
+            .or(self.indexes.first())
+            .map(|&(_, frames)| frames)
+    }
+}
+
+impl CueSheet {
+    /// Parses a CUE sheet.
+    ///
+    /// Commands that don't affect markers (`FLAGS`, `ISRC`, `CATALOG`, `PREGAP`, ...) are ignored.
+    ///
+    /// # Errors
+    /// * [`ParseError::InvalidLine`] - If a `TRACK` appears before any `FILE`, an `INDEX`
+    ///   before any `TRACK`, or a track number, index number or `mm:ss:ff` time is invalid
+    ///
+    /// # Example
+    /// ```
+    /// use reaper_regions::formats::cue::CueSheet;
+    ///
+    /// let sheet = CueSheet::parse(
+    ///     "PERFORMER \"The Band\"\nFILE \"live.wav\" WAVE\n  TRACK 01 AUDIO\n    TITLE \"Opener\"\n    INDEX 01 00:02:00\n",
+    /// )
+    /// .unwrap();
+    /// assert_eq!(sheet.performer.as_deref(), Some("The Band"));
+    /// assert_eq!(sheet.files[0].tracks[0].title.as_deref(), Some("Opener"));
+    /// assert_eq!(sheet.files[0].tracks[0].start(), Some(150));
+    /// ```
+    pub fn parse(text: &str) -> Result<CueSheet, ParseError> {
+        let mut sheet = CueSheet::default();
+
+        for (index, line) in text.lines().enumerate() {
+            let invalid = || ParseError::InvalidLine(index + 1, line.to_string());
+            let line = line.trim_start_matches('\u{feff}').trim();
+            let Some((command, rest)) = split_command(line) else {
+                continue;
+            };
+            let track = sheet
+                .files
+                .last_mut()
+                .and_then(|file| file.tracks.last_mut());
+
+            match command.to_ascii_uppercase().as_str() {
+                "REM" => match track {
+                    Some(track) => track.rems.push(rest.to_string()),
+                    None => sheet.rems.push(rest.to_string()),
+                },
+                "TITLE" => match track {
+                    Some(track) => track.title = Some(unquote(rest)),
+                    None => sheet.title = Some(unquote(rest)),
+                },
+                "PERFORMER" => match track {
+                    Some(track) => track.performer = Some(unquote(rest)),
+                    None => sheet.performer = Some(unquote(rest)),
+                },
+                "FILE" => {
+                    let (name, file_type) = split_quoted(rest);
+                    sheet.files.push(CueFile {
+                        name,
+                        file_type: file_type.to_string(),
+                        tracks: Vec::new(),
+                    });
+                }
+                "TRACK" => {
+                    let file = sheet.files.last_mut().ok_or_else(invalid)?;
+                    let (number, track_type) =
+                        rest.split_once(char::is_whitespace).unwrap_or((rest, ""));
+                    file.tracks.push(CueTrack {
+                        number: number.parse().map_err(|_| invalid())?,
+                        track_type: track_type.trim().to_string(),
+                        ..CueTrack::default()
+                    });
+                }
+                "INDEX" => {
+                    let track = track.ok_or_else(invalid)?;
+                    let (number, time) =
+                        rest.split_once(char::is_whitespace).ok_or_else(invalid)?;
+                    let number = number.parse().map_err(|_| invalid())?;
+                    let frames = parse_frames(time.trim()).ok_or_else(invalid)?;
+                    track.indexes.push((number, frames));
+                }
+                _ => debug!("Ignoring CUE sheet command: {line}"),
+            }
+        }
+
+        Ok(sheet)
+    }
+
+    /// Converts the tracks into regions and extra index points into markers.
+    ///
+    /// # Arguments
+    /// * `sample_rate` - Sample rate used to convert CD frames into samples
+    /// * `file_lengths` - Length in samples of each `FILE`, in order. Every file but the
+    ///   last needs a length to place the following files; a missing length for the last
+    ///   file turns its last track into a marker.
+    ///
+    /// # Errors
+    /// * [`ParseError::Other`] - If the length of a file followed by another file is
+    ///   unknown, or a position doesn't fit in 32-bit samples
+    ///
+    /// # Example
+    /// ```
+    /// use reaper_regions::formats::cue::CueSheet;
+    /// use reaper_regions::MarkerType;
+    ///
+    /// let sheet = CueSheet::parse(
+    ///     "FILE a.wav WAVE\nTRACK 01 AUDIO\nINDEX 01 00:00:00\nFILE b.wav WAVE\nTRACK 02 AUDIO\nINDEX 01 00:00:00\n",
+    /// )
+    /// .unwrap();
+    /// let markers = sheet.to_markers(44100, &[Some(441000), Some(882000)]).unwrap();
+    /// assert_eq!(markers[0].name, "Track 01");
+    /// assert_eq!(markers[0].end, Some(441000));
+    /// assert_eq!(markers[1].start, 441000);
+    /// assert_eq!(markers[1].end, Some(1323000));
+    /// ```
+    pub fn to_markers(
+        &self,
+        sample_rate: u32,
+        file_lengths: &[Option<u32>],
+    ) -> Result<Vec<Marker>, ParseError> {
+        // WAV files count samples in 32 bits, so later positions are rejected
+        let position = |offset: u32, frames: u64| {
+            u32::try_from(frames * sample_rate as u64 / CD_FRAMES_PER_SECOND)
+                .ok()
+                .and_then(|samples| offset.checked_add(samples))
+                .ok_or_else(|| {
+                    ParseError::Other(format!(
+                        "CUE sheet position {} is too far into the audio",
+                        format_frames(frames)
+                    ))
+                })
+        };
+
+        // Start positions of all tracks (and end positions of files), in samples
+        let mut offset: u32 = 0;
+        let mut tracks = Vec::new();
+        for (index, file) in self.files.iter().enumerate() {
+            let length = file_lengths.get(index).copied().flatten();
+            for track in &file.tracks {
+                let Some(start) = track.start() else {
+                    warn!("CUE track {:02} has no INDEX, skipping", track.number);
+                    continue;
+                };
+                tracks.push((track, position(offset, start)?, offset));
+            }
+            match length {
+                Some(length) => {
+                    offset = offset.checked_add(length).ok_or_else(|| {
+                        ParseError::Other("CUE sheet files are too long together".to_string())
+                    })?;
+                }
+                None if index + 1 < self.files.len() => {
+                    return Err(ParseError::Other(format!(
+                        "length of CUE sheet FILE '{}' is unknown",
+                        file.name
+                    )));
+                }
+                None => {}
+            }
+        }
+        // The last track ends with the last file, if its length is known
+        let total_length = self
+            .files
+            .len()
+            .checked_sub(1)
+            .and_then(|last| file_lengths.get(last).copied().flatten())
+            .map(|_| offset);
+
+        let mut markers = Vec::new();
+        for (index, &(track, start, file_offset)) in tracks.iter().enumerate() {
+            let end = match tracks.get(index + 1) {
+                Some(&(_, next_start, _)) => Some(next_start),
+                None => total_length,
+            };
+            let end = end.filter(|&end| end > start);
+            if end.is_none() {
+                warn!(
+                    "CUE track {:02} has no known end, importing it as a marker",
+                    track.number
+                );
+            }
+            let name = track
+                .title
+                .clone()
+                .unwrap_or_else(|| format!("Track {:02}", track.number));
+
+            let id = markers.len() as u32 + 1;
+            markers.push(Marker::new(id, name.clone(), start, end, sample_rate));
+
+            for &(number, frames) in track.indexes.iter().filter(|(number, _)| *number > 1) {
+                let id = markers.len() as u32 + 1;
+                markers.push(Marker::new(
+                    id,
+                    format!("{name} (index {number:02})"),
+                    position(file_offset, frames)?,
+                    None,
+                    sample_rate,
+                ));
+            }
+        }
+
+        Ok(markers)
+    }
+}
+
+/// Parses a single-file CUE sheet into markers.
+///
+/// # Arguments
+/// * `text` - Contents of the CUE sheet
+/// * `sample_rate` - Sample rate used to convert CD frames into samples
+/// * `length` - Length of the audio in samples, which ends the last track
+///
+/// # Errors
+/// * [`ParseError::InvalidLine`] - If the sheet cannot be parsed
+/// * [`ParseError::Other`] - If the sheet has several files (use [`CueSheet::to_markers`]),
+///   or a position doesn't fit in 32-bit samples
+pub fn import(
+    text: &str,
+    sample_rate: u32,
+    length: Option<u32>,
+) -> Result<Vec<Marker>, ParseError> {
+    let sheet = CueSheet::parse(text)?;
+    if sheet.files.len() > 1 {
+        return Err(ParseError::Other(format!(
+            "CUE sheet has {} files, but only one length is known",
+            sheet.files.len()
+        )));
+    }
+    sheet.to_markers(sample_rate, &[length])
+}
+
+/// Reads a CUE sheet into [`WavData`].
+///
+/// The sample rate and length of each `FILE` are read from the referenced WAV files,
+/// resolved relative to the CUE sheet. If the first file can't be read, `sample_rate`
+/// is used instead. The album title and performer are stored as the `IPRD` and `IART`
+/// INFO tags.
+///
+/// # Arguments
+/// * `file_path` - Path to the CUE sheet
+/// * `sample_rate` - Fallback sample rate in Hz if the audio files can't be read
+///
+/// # Errors
+/// * [`ParseError::Io`] - If the CUE sheet cannot be read
+/// * [`ParseError::InvalidLine`] - If the CUE sheet cannot be parsed
+/// * [`ParseError::Other`] - If the length of a file followed by another file is
+///   unknown, or a position doesn't fit in 32-bit samples
+pub fn import_file(file_path: &str, sample_rate: u32) -> Result<WavData, ParseError> {
+    let text = std::fs::read_to_string(file_path)?;
+    let sheet = CueSheet::parse(&text)?;
+
+    let directory = Path::new(file_path).parent().unwrap_or(Path::new(""));
+    let audio: Vec<Option<WavData>> = sheet
+        .files
+        .iter()
+        .map(|file| {
+            let path = directory.join(&file.name);
+            parse_markers_from_file(&path.to_string_lossy())
+                .inspect_err(|err| warn!("Cannot read CUE sheet FILE '{}': {err}", file.name))
+                .ok()
+        })
+        .collect();
+
+    let sample_rate = audio
+        .iter()
+        .flatten()
+        .next()
+        .map(|data| data.sample_rate)
+        .unwrap_or(sample_rate);
+    let lengths: Vec<Option<u32>> = audio
+        .iter()
+        .map(|data| data.as_ref().and_then(|data| data.length))
+        .collect();
+
+    let mut info = BTreeMap::new();
+    if let Some(title) = &sheet.title {
+        info.insert("IPRD".to_string(), title.clone());
+    }
+    if let Some(performer) = &sheet.performer {
+        info.insert("IART".to_string(), performer.clone());
+    }
+
+    Ok(WavData {
+        path: file_path.to_string(),
+        sample_rate,
+        length: lengths
+            .iter()
+            .try_fold(0u32, |total, &length| total.checked_add(length?)),
+        markers: sheet.to_markers(sample_rate, &lengths)?,
+        info,
+        ..WavData::default()
+    })
+}
+
+/// Splits a line into its command keyword and the rest.
+fn split_command(line: &str) -> Option<(&str, &str)> {
+    if line.is_empty() {
+        return None;
+    }
+    match line.split_once(char::is_whitespace) {
+        Some((command, rest)) => Some((command, rest.trim())),
+        None => Some((line, "")),
+    }
+}
+
+/// Splits a possibly quoted string from the words following it.
+fn split_quoted(value: &str) -> (String, &str) {
+    if let Some(rest) = value.strip_prefix('"')
+        && let Some(end) = rest.find('"')
+    {
+        return (rest[..end].to_string(), rest[end + 1..].trim());
+    }
+    match value.rsplit_once(char::is_whitespace) {
+        Some((name, rest)) => (name.trim().to_string(), rest.trim()),
+        None => (value.to_string(), ""),
+    }
+}
+
+/// Removes the quotes around a CUE sheet string, if any.
+fn unquote(value: &str) -> String {
+    match value.strip_prefix('"') {
+        Some(rest) => rest.split('"').next().unwrap_or_default().to_string(),
+        None => value.to_string(),
+    }
+}
+
+/// Parses a `mm:ss:ff` CUE sheet time into CD frames.
+fn parse_frames(time: &str) -> Option<u64> {
+    let mut fields = time.split(':').map(|field| field.parse::<u64>().ok());
+    let (minutes, seconds, frames) = (fields.next()??, fields.next()??, fields.next()??);
+    if fields.next().is_some() || seconds >= 60 || frames >= CD_FRAMES_PER_SECOND {
+        return None;
+    }
+    Some((minutes * 60 + seconds) * CD_FRAMES_PER_SECOND + frames)
+}
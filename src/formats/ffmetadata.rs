@@ -0,0 +1,183 @@
+//! FFmpeg metadata (`FFMETADATA1`) chapter export.
+//!
+//! The output can be applied to MP4/MKV/M4A deliverables with:
+//!
+//! ```bash
+//! ffmpeg -i input.wav -i metadata.txt -map_metadata 1 -codec copy output.mkv
+//! ```
+//!
+//! Regions become `[CHAPTER]` blocks with `TIMEBASE=1/<sample_rate>`, so `START` and `END`
+//! are exact sample positions. Global tags are taken from the LIST-INFO chunk.
+
+use super::Export;
+use crate::diagnostic::Diagnostic;
+use crate::{MarkerType, WavData};
+
+/// LIST-INFO tags written as global metadata, with their FFmpeg keys.
+const INFO_KEYS: &[(&str, &str)] = &[
+    ("INAM", "title"),
+    ("IART", "artist"),
+    ("IPRD", "album"),
+    ("IGNR", "genre"),
+    ("ICRD", "date"),
+    ("ICMT", "comment"),
+    ("ICOP", "copyright"),
+];
+
+/// What to do with simple (point) markers.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, strum::Display, strum::EnumString)]
+#[strum(serialize_all = "lowercase", ascii_case_insensitive)]
+pub enum PointMarkers {
+    /// Start a chapter at each marker, lasting until the next chapter starts or the
+    /// enclosing region (or the audio) ends. The chapter of the enclosing region ends
+    /// at its first marker, so that chapters don't overlap.
+    #[default]
+    Chapter,
+    /// Leave markers out
+    Ignore,
+}
+
+/// Options for [`export`].
+#[derive(Debug, Clone, Default)]
+pub struct FfmetadataOptions {
+    /// How simple markers are handled
+    pub markers: PointMarkers,
+}
+
+/// A chapter to be written, in samples.
+struct Chapter<'a> {
+    id: u32,
+    start: u32,
+    end: u32,
+    title: &'a str,
+}
+
+/// Exports regions (and optionally markers) as an FFmpeg metadata file with chapters.
+///
+/// # Arguments
+/// * `data` - Parsed WAV data containing the markers
+/// * `options` - Handling of simple markers
+///
+/// # Returns
+/// * [`Export`] - The metadata file, with diagnostics for overlapping chapters
+///   and markers whose chapter end is unknown
+///
+/// # Example
+/// ```
+/// use reaper_regions::formats::ffmetadata::{self, FfmetadataOptions};
+/// use reaper_regions::{Marker, WavData};
+///
+/// let mut data = WavData {
+///     sample_rate: 48000,
+///     markers: vec![Marker::new(1, "Song".to_string(), 48000, Some(96000), 48000)],
+///     ..WavData::default()
+/// };
+/// data.info.insert("IART".to_string(), "The Band".to_string());
+///
+/// let export = ffmetadata::export(&data, &FfmetadataOptions::default());
+/// assert_eq!(
+///     export.output,
+///     ";FFMETADATA1\nartist=The Band\n\n[CHAPTER]\nTIMEBASE=1/48000\nSTART=48000\nEND=96000\ntitle=Song\n"
+/// );
+/// ```
+pub fn export(data: &WavData, options: &FfmetadataOptions) -> Export {
+    let mut diagnostics = Vec::new();
+
+    let mut sorted: Vec<_> = data.markers.iter().collect();
+    sorted.sort_by_key(|m| (m.start, m.id));
+
+    let with_markers = options.markers == PointMarkers::Chapter;
+    let mut chapters = Vec::new();
+    for marker in &sorted {
+        let end = match (marker.r#type, marker.end) {
+            (MarkerType::Region, Some(end)) => {
+                // Markers inside the region start chapters of their own
+                let first_marker = sorted
+                    .iter()
+                    .filter(|m| with_markers && m.r#type == MarkerType::Marker)
+                    .map(|m| m.start)
+                    .find(|&start| start > marker.start && start < end);
+                first_marker.unwrap_or(end)
+            }
+            _ if !with_markers => continue,
+            _ => {
+                // Until the next chapter, the end of the enclosing region, or the end of the audio
+                let next = sorted
+                    .iter()
+                    .map(|m| m.start)
+                    .find(|&start| start > marker.start);
+                let enclosing = sorted
+                    .iter()
+                    .filter(|m| m.r#type == MarkerType::Region && m.start <= marker.start)
+                    .filter_map(|m| m.end)
+                    .rfind(|&end| end > marker.start);
+                let end = match (next, enclosing) {
+                    (Some(next), Some(enclosing)) => Some(next.min(enclosing)),
+                    (next, enclosing) => next.or(enclosing),
+                };
+                end.or(data.length.filter(|&length| length > marker.start))
+                    .unwrap_or_else(|| {
+                        diagnostics.push(Diagnostic::warning(
+                            Some(marker.id),
+                            format!(
+                                "'{}' is the last marker and the audio length is unknown, writing an empty chapter",
+                                marker.name
+                            ),
+                        ));
+                        marker.start
+                    })
+            }
+        };
+        chapters.push(Chapter {
+            id: marker.id,
+            start: marker.start,
+            end,
+            title: &marker.name,
+        });
+    }
+
+    for pair in chapters.windows(2) {
+        if pair[1].start < pair[0].end {
+            diagnostics.push(Diagnostic::warning(
+                Some(pair[1].id),
+                format!(
+                    "chapter '{}' overlaps chapter '{}'",
+                    pair[1].title, pair[0].title
+                ),
+            ));
+        }
+    }
+
+    let mut output = String::from(";FFMETADATA1\n");
+    for (tag, key) in INFO_KEYS {
+        if let Some(value) = data.info.get(*tag) {
+            output.push_str(&format!("{key}={}\n", escape(value)));
+        }
+    }
+    for chapter in &chapters {
+        output.push_str(&format!(
+            "\n[CHAPTER]\nTIMEBASE=1/{}\nSTART={}\nEND={}\ntitle={}\n",
+            data.sample_rate,
+            chapter.start,
+            chapter.end,
+            escape(chapter.title)
+        ));
+    }
+
+    Export {
+        output,
+        diagnostics,
+    }
+}
+
+/// Escapes the characters with special meaning in FFmpeg metadata files.
+fn escape(value: &str) -> String {
+    let mut escaped = String::with_capacity(value.len());
+    for c in value.chars() {
+        if matches!(c, '=' | ';' | '#' | '\\' | '\n') {
+            escaped.push('\\');
+        }
+        escaped.push(c);
+    }
+    escaped
+}
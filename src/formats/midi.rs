@@ -0,0 +1,257 @@
+//! Standard MIDI File (SMF) export.
+//!
+//! Writes a type-1 SMF with a single conductor track holding the tempo, the track name
+//! and the markers, for notation software, lighting desks and other DAWs that import
+//! markers from MIDI files. Markers become Marker (`FF 06`) meta-events; regions become
+//! a pair of Cue Point (`FF 07`) or Marker meta-events at their start and end.
+//!
+//! Positions are converted to ticks using either a metrical division (pulses per quarter
+//! note at a constant tempo) or an SMPTE division (ticks per frame), for frame-accurate
+//! placement independent of the tempo.
+
+use crate::{MarkerType, WavData};
+use log::warn;
+
+/// Default pulses per quarter note.
+pub const DEFAULT_PPQ: u16 = 960;
+
+/// Default tempo in beats per minute.
+pub const DEFAULT_TEMPO: f64 = 120.0;
+
+/// SMPTE frame rates that can be used as a MIDI file division.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, strum::Display, strum::EnumString)]
+pub enum SmpteFormat {
+    /// 24 frames per second
+    #[strum(serialize = "24")]
+    Fps24,
+    /// 25 frames per second
+    #[strum(serialize = "25")]
+    Fps25,
+    /// 30000/1001 frames per second, drop-frame
+    #[strum(serialize = "29.97df")]
+    Fps29_97Df,
+    /// 30 frames per second
+    #[strum(serialize = "30")]
+    Fps30,
+}
+
+impl SmpteFormat {
+    /// Returns the exact frame rate as a `(numerator, denominator)` ratio.
+    fn ratio(self) -> (u64, u64) {
+        match self {
+            SmpteFormat::Fps24 => (24, 1),
+            SmpteFormat::Fps25 => (25, 1),
+            SmpteFormat::Fps29_97Df => (30000, 1001),
+            SmpteFormat::Fps30 => (30, 1),
+        }
+    }
+
+    /// The (negative) frame rate stored in the upper byte of the header division.
+    fn header_byte(self) -> i8 {
+        match self {
+            SmpteFormat::Fps24 => -24,
+            SmpteFormat::Fps25 => -25,
+            SmpteFormat::Fps29_97Df => -29,
+            SmpteFormat::Fps30 => -30,
+        }
+    }
+}
+
+/// Time division of the MIDI file.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Division {
+    /// Pulses per quarter note (1 to 32767), at the tempo in [`MidiOptions::tempo`]
+    Ppq(u16),
+    /// Ticks per SMPTE frame
+    Smpte {
+        /// Frame rate
+        format: SmpteFormat,
+        /// Ticks per frame
+        ticks_per_frame: u8,
+    },
+}
+
+/// How regions are written.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, strum::Display, strum::EnumString)]
+#[strum(serialize_all = "lowercase", ascii_case_insensitive)]
+pub enum MidiRegions {
+    /// A pair of Cue Point meta-events named `<name> start` and `<name> end`
+    #[default]
+    Cue,
+    /// A pair of Marker meta-events named `<name> start` and `<name> end`
+    Marker,
+}
+
+/// Options for [`export`].
+#[derive(Debug, Clone)]
+pub struct MidiOptions {
+    /// Time division
+    pub division: Division,
+    /// Tempo in beats per minute, written as a Set Tempo meta-event. Tempos that aren't
+    /// finite and positive fall back to [`DEFAULT_TEMPO`].
+    pub tempo: f64,
+    /// How regions are written
+    pub regions: MidiRegions,
+}
+
+impl Default for MidiOptions {
+    fn default() -> Self {
+        MidiOptions {
+            division: Division::Ppq(DEFAULT_PPQ),
+            tempo: DEFAULT_TEMPO,
+            regions: MidiRegions::Cue,
+        }
+    }
+}
+
+const META_TRACK_NAME: u8 = 0x03;
+const META_MARKER: u8 = 0x06;
+const META_CUE_POINT: u8 = 0x07;
+const META_END_OF_TRACK: u8 = 0x2F;
+const META_SET_TEMPO: u8 = 0x51;
+
+/// Exports markers and regions as a type-1 Standard MIDI File.
+///
+/// The track is named after the `INAM` INFO tag, if present. Events at the same tick
+/// are ordered with region ends first, then by marker ID.
+///
+/// # Arguments
+/// * `data` - Parsed WAV data containing the markers
+/// * `options` - Division, tempo and region options
+///
+/// # Returns
+/// * `Vec<u8>` - The MIDI file
+///
+/// # Example
+/// ```
+/// use reaper_regions::formats::midi::{self, MidiOptions};
+/// use reaper_regions::{Marker, WavData};
+///
+/// let data = WavData {
+///     sample_rate: 48000,
+///     markers: vec![Marker::new(1, "Drop".to_string(), 24000, None, 48000)],
+///     ..WavData::default()
+/// };
+/// let smf = midi::export(&data, &MidiOptions::default());
+/// assert_eq!(&smf[..4], b"MThd");
+/// // Half a second at 120 BPM and 960 PPQ is 960 ticks
+/// assert!(smf.windows(7).any(|w| w == [0x87, 0x40, 0xFF, 0x06, 4, b'D', b'r']));
+/// ```
+pub fn export(data: &WavData, options: &MidiOptions) -> Vec<u8> {
+    let tempo = if is_valid_tempo(options.tempo) {
+        options.tempo
+    } else {
+        warn!(
+            "Invalid MIDI tempo {} BPM, using {DEFAULT_TEMPO} BPM",
+            options.tempo
+        );
+        DEFAULT_TEMPO
+    };
+    let micros_per_quarter = (60_000_000.0 / tempo).round().clamp(1.0, 0xFF_FFFF as f64) as u32;
+
+    // (tick, is start, marker id, meta type, text)
+    let mut events: Vec<(u64, bool, u32, u8, String)> = Vec::new();
+    for marker in &data.markers {
+        let start = to_ticks(marker.start, data.sample_rate, options, micros_per_quarter);
+        match (marker.r#type, marker.end) {
+            (MarkerType::Region, Some(end)) => {
+                let meta = match options.regions {
+                    MidiRegions::Cue => META_CUE_POINT,
+                    MidiRegions::Marker => META_MARKER,
+                };
+                let end = to_ticks(end, data.sample_rate, options, micros_per_quarter);
+                events.push((
+                    start,
+                    true,
+                    marker.id,
+                    meta,
+                    format!("{} start", marker.name),
+                ));
+                events.push((end, false, marker.id, meta, format!("{} end", marker.name)));
+            }
+            _ => events.push((start, true, marker.id, META_MARKER, marker.name.clone())),
+        }
+    }
+    events.sort_by_key(|(tick, is_start, id, _, _)| (*tick, *is_start, *id));
+
+    let mut track = Vec::new();
+    if let Some(name) = data.info.get("INAM") {
+        write_meta(&mut track, 0, META_TRACK_NAME, name.as_bytes());
+    }
+    write_meta(
+        &mut track,
+        0,
+        META_SET_TEMPO,
+        &micros_per_quarter.to_be_bytes()[1..],
+    );
+    let mut previous = 0;
+    for (tick, _, _, meta, text) in &events {
+        write_meta(&mut track, tick - previous, *meta, text.as_bytes());
+        previous = *tick;
+    }
+    write_meta(&mut track, 0, META_END_OF_TRACK, &[]);
+
+    let division = match options.division {
+        Division::Ppq(ppq) => (ppq & 0x7FFF).to_be_bytes(),
+        Division::Smpte {
+            format,
+            ticks_per_frame,
+        } => [format.header_byte() as u8, ticks_per_frame],
+    };
+
+    let mut smf = Vec::with_capacity(22 + track.len());
+    smf.extend_from_slice(b"MThd");
+    smf.extend_from_slice(&6u32.to_be_bytes());
+    smf.extend_from_slice(&1u16.to_be_bytes()); // format 1
+    smf.extend_from_slice(&1u16.to_be_bytes()); // one track
+    smf.extend_from_slice(&division);
+    smf.extend_from_slice(b"MTrk");
+    smf.extend_from_slice(&(track.len() as u32).to_be_bytes());
+    smf.extend_from_slice(&track);
+    smf
+}
+
+/// Whether a tempo in beats per minute can be written: finite and positive.
+pub fn is_valid_tempo(tempo: f64) -> bool {
+    tempo.is_finite() && tempo > 0.0
+}
+
+/// Converts a sample position to the nearest tick.
+fn to_ticks(samples: u32, sample_rate: u32, options: &MidiOptions, micros_per_quarter: u32) -> u64 {
+    // ticks = samples * numerator / denominator
+    let (numerator, denominator) = match options.division {
+        Division::Ppq(ppq) => (
+            ppq as u128 * 1_000_000,
+            sample_rate as u128 * micros_per_quarter as u128,
+        ),
+        Division::Smpte {
+            format,
+            ticks_per_frame,
+        } => {
+            let (num, den) = format.ratio();
+            (
+                num as u128 * ticks_per_frame as u128,
+                sample_rate as u128 * den as u128,
+            )
+        }
+    };
+    ((samples as u128 * numerator * 2 + denominator) / (denominator * 2)) as u64
+}
+
+fn write_meta(track: &mut Vec<u8>, delta: u64, meta: u8, data: &[u8]) {
+    write_variable_length(track, delta);
+    track.extend_from_slice(&[0xFF, meta]);
+    write_variable_length(track, data.len() as u64);
+    track.extend_from_slice(data);
+}
+
+/// Writes a MIDI variable-length quantity: 7 bits per byte, most significant first.
+fn write_variable_length(output: &mut Vec<u8>, value: u64) {
+    let mut bytes = vec![(value & 0x7F) as u8];
+    let mut rest = value >> 7;
+    while rest > 0 {
+        bytes.push((rest & 0x7F) as u8 | 0x80);
+        rest >>= 7;
+    }
+    output.extend(bytes.iter().rev());
+}
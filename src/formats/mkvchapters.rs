@@ -0,0 +1,144 @@
+//! Matroska chapters XML export.
+//!
+//! Produces the XML chapter format understood by `mkvmerge --chapters` and MKVToolNix,
+//! for MKV/MKA/WebM deliverables. Chapter times are computed exactly from the sample
+//! positions and the sample rate, in nanoseconds.
+
+use crate::{Marker, MarkerType, WavData};
+
+/// Options for [`export`].
+#[derive(Debug, Clone)]
+pub struct MkvChaptersOptions {
+    /// Nest markers as sub-chapters of the region containing them
+    pub nest_markers: bool,
+    /// ISO 639-2 language code of the chapter names
+    pub language: String,
+}
+
+impl Default for MkvChaptersOptions {
+    fn default() -> Self {
+        MkvChaptersOptions {
+            nest_markers: false,
+            language: "eng".to_string(),
+        }
+    }
+}
+
+/// Exports markers and regions as Matroska chapters XML.
+///
+/// Regions become chapters with a start and end time, markers chapters with only
+/// a start time. Each chapter's `ChapterUID` is the marker ID.
+///
+/// # Arguments
+/// * `data` - Parsed WAV data containing the markers
+/// * `options` - Nesting and language options
+///
+/// # Example
+/// ```
+/// use reaper_regions::formats::mkvchapters::{self, MkvChaptersOptions};
+/// use reaper_regions::{Marker, WavData};
+///
+/// let data = WavData {
+///     sample_rate: 44100,
+///     markers: vec![Marker::new(1, "Song".to_string(), 1, Some(44100), 44100)],
+///     ..WavData::default()
+/// };
+/// let xml = mkvchapters::export(&data, &MkvChaptersOptions::default());
+/// assert!(xml.contains("<ChapterTimeStart>00:00:00.000022676</ChapterTimeStart>"));
+/// assert!(xml.contains("<ChapterTimeEnd>00:00:01.000000000</ChapterTimeEnd>"));
+/// ```
+pub fn export(data: &WavData, options: &MkvChaptersOptions) -> String {
+    let mut sorted: Vec<&Marker> = data.markers.iter().collect();
+    sorted.sort_by_key(|m| (m.start, m.id));
+
+    // Top-level chapters, each with the markers nested inside it
+    let mut chapters: Vec<(&Marker, Vec<&Marker>)> = Vec::new();
+    for marker in sorted {
+        if options.nest_markers && marker.r#type == MarkerType::Marker {
+            // The innermost region is the last one started before the marker
+            let parent = chapters.iter_mut().rev().find(|(chapter, _)| {
+                chapter.r#type == MarkerType::Region
+                    && chapter.start <= marker.start
+                    && chapter.end.is_some_and(|end| marker.start < end)
+            });
+            if let Some((_, children)) = parent {
+                children.push(marker);
+                continue;
+            }
+        }
+        chapters.push((marker, Vec::new()));
+    }
+
+    let mut output = String::from(
+        "<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n<!DOCTYPE Chapters SYSTEM \"matroskachapters.dtd\">\n<Chapters>\n  <EditionEntry>\n",
+    );
+    for (chapter, children) in &chapters {
+        write_atom(&mut output, chapter, children, data.sample_rate, options, 2);
+    }
+    output.push_str("  </EditionEntry>\n</Chapters>\n");
+    output
+}
+
+/// Writes a `ChapterAtom` element and its nested atoms.
+fn write_atom(
+    output: &mut String,
+    marker: &Marker,
+    children: &[&Marker],
+    sample_rate: u32,
+    options: &MkvChaptersOptions,
+    depth: usize,
+) {
+    let indent = "  ".repeat(depth);
+    output.push_str(&format!("{indent}<ChapterAtom>\n"));
+    output.push_str(&format!(
+        "{indent}  <ChapterUID>{}</ChapterUID>\n",
+        marker.id
+    ));
+    output.push_str(&format!(
+        "{indent}  <ChapterTimeStart>{}</ChapterTimeStart>\n",
+        format_time(marker.start, sample_rate)
+    ));
+    if let Some(end) = marker.end {
+        output.push_str(&format!(
+            "{indent}  <ChapterTimeEnd>{}</ChapterTimeEnd>\n",
+            format_time(end, sample_rate)
+        ));
+    }
+    output.push_str(&format!("{indent}  <ChapterDisplay>\n"));
+    output.push_str(&format!(
+        "{indent}    <ChapterString>{}</ChapterString>\n",
+        escape(&marker.name)
+    ));
+    output.push_str(&format!(
+        "{indent}    <ChapterLanguage>{}</ChapterLanguage>\n",
+        escape(&options.language)
+    ));
+    output.push_str(&format!("{indent}  </ChapterDisplay>\n"));
+    for child in children {
+        write_atom(output, child, &[], sample_rate, options, depth + 1);
+    }
+    output.push_str(&format!("{indent}</ChapterAtom>\n"));
+}
+
+/// Formats a sample position as `HH:MM:SS.nnnnnnnnn`, rounded to the nearest nanosecond.
+fn format_time(samples: u32, sample_rate: u32) -> String {
+    let rate = sample_rate as u128;
+    let nanos = (samples as u128 * 1_000_000_000 * 2 + rate) / (rate * 2);
+    let seconds = nanos / 1_000_000_000;
+    format!(
+        "{:02}:{:02}:{:02}.{:09}",
+        seconds / 3600,
+        (seconds / 60) % 60,
+        seconds % 60,
+        nanos % 1_000_000_000
+    )
+}
+
+/// Escapes XML special characters.
+fn escape(value: &str) -> String {
+    value
+        .replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
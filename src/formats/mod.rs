@@ -0,0 +1,37 @@
+//! Marker list formats used by other applications.
+//!
+//! Each submodule converts the markers of a [`WavData`](crate::WavData) into
+//! a format understood by another application, and, where the format can be
+//! read back, parses it into [`Marker`](crate::Marker)s.
+
+use crate::diagnostic::Diagnostic;
+
+pub mod audacity;
+pub mod avid;
+pub mod cue;
+pub mod ffmetadata;
+pub mod midi;
+pub mod mkvchapters;
+pub mod podcast;
+pub mod reaper;
+pub mod rpp;
+pub mod subtitles;
+pub mod youtube;
+
+/// Output of an exporter that may have to adapt the markers to the target format.
+#[derive(Debug, Clone, Default)]
+pub struct Export {
+    /// The exported text
+    pub output: String,
+    /// Issues encountered while exporting (rounding, skipped markers, ...)
+    pub diagnostics: Vec<Diagnostic>,
+}
+
+impl From<String> for Export {
+    fn from(output: String) -> Self {
+        Export {
+            output,
+            diagnostics: Vec::new(),
+        }
+    }
+}
@@ -0,0 +1,115 @@
+//! Podcasting 2.0 chapters (`chapters.json`) export.
+//!
+//! Builds the JSON chapters document referenced by the `<podcast:chapters>` RSS tag.
+//! The returned [`PodcastChapters`] implements [`Serialize`], so it can be written
+//! with any serde serializer (e.g. `serde_json::to_string_pretty`).
+
+use crate::{MarkerType, WavData, serialize_f64, serialize_opt_f64};
+use serde::Serialize;
+
+/// Version of the Podcasting 2.0 chapters format that is produced.
+pub const CHAPTERS_VERSION: &str = "1.2.0";
+
+/// What to do with simple (point) markers.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, strum::Display, strum::EnumString)]
+#[strum(serialize_all = "lowercase", ascii_case_insensitive)]
+pub enum PodcastMarkers {
+    /// Include markers as chapters without an end time
+    #[default]
+    Chapter,
+    /// Include markers as chapters hidden from the table of contents (`"toc": false`)
+    Hidden,
+    /// Leave markers out
+    Ignore,
+}
+
+/// Options for [`chapters`].
+#[derive(Debug, Clone, Default)]
+pub struct PodcastOptions {
+    /// How simple markers are handled
+    pub markers: PodcastMarkers,
+}
+
+/// A Podcasting 2.0 chapters document.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct PodcastChapters {
+    /// Format version
+    pub version: String,
+    /// Episode title (from the `INAM` INFO tag)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub title: Option<String>,
+    /// Author (from the `IART` INFO tag)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub author: Option<String>,
+    /// Chapters, in order of start time
+    pub chapters: Vec<PodcastChapter>,
+}
+
+/// A single chapter.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PodcastChapter {
+    /// Start time in seconds
+    #[serde(serialize_with = "serialize_f64")]
+    pub start_time: f64,
+    /// End time in seconds (regions only)
+    #[serde(
+        serialize_with = "serialize_opt_f64",
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub end_time: Option<f64>,
+    /// Chapter title
+    pub title: String,
+    /// `false` to hide the chapter from the table of contents
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub toc: Option<bool>,
+}
+
+/// Builds a Podcasting 2.0 chapters document from the markers and regions.
+///
+/// # Example
+/// ```
+/// use reaper_regions::formats::podcast::{self, PodcastMarkers, PodcastOptions};
+/// use reaper_regions::{Marker, WavData};
+///
+/// let data = WavData {
+///     sample_rate: 48000,
+///     markers: vec![
+///         Marker::new(1, "Song".to_string(), 0, Some(96000), 48000),
+///         Marker::new(2, "Solo".to_string(), 48000, None, 48000),
+///     ],
+///     ..WavData::default()
+/// };
+/// let options = PodcastOptions { markers: PodcastMarkers::Hidden };
+/// let document = podcast::chapters(&data, &options);
+/// assert_eq!(document.chapters[0].end_time, Some(2.0));
+/// assert_eq!(document.chapters[1].toc, Some(false));
+/// ```
+pub fn chapters(data: &WavData, options: &PodcastOptions) -> PodcastChapters {
+    let mut sorted: Vec<_> = data.markers.iter().collect();
+    sorted.sort_by_key(|m| (m.start, m.id));
+
+    let chapters = sorted
+        .into_iter()
+        .filter_map(|marker| {
+            let toc = match (marker.r#type, options.markers) {
+                (MarkerType::Region, _) | (_, PodcastMarkers::Chapter) => None,
+                (MarkerType::Marker, PodcastMarkers::Hidden) => Some(false),
+                (MarkerType::Marker, PodcastMarkers::Ignore) => return None,
+            };
+            Some(PodcastChapter {
+                start_time: marker.start_time,
+                end_time: marker.end_time,
+                title: marker.name.clone(),
+                toc,
+            })
+        })
+        .collect();
+
+    PodcastChapters {
+        version: CHAPTERS_VERSION.to_string(),
+        title: data.info.get("INAM").cloned(),
+        author: data.info.get("IART").cloned(),
+        chapters,
+    }
+}
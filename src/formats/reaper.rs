@@ -0,0 +1,162 @@
+//! REAPER Region/Marker Manager CSV and ReaScript export.
+//!
+//! Two ways to bring the markers of a render back into a REAPER project:
+//!
+//! - [`export_csv`]: the CSV layout of REAPER's Region/Marker Manager
+//!   (`#,Name,Start,End,Length`), importable from the manager's context menu.
+//! - [`export_reascript`]: a Lua ReaScript calling `reaper.AddProjectMarker2` for every
+//!   marker and region, run as a single undoable action.
+
+use crate::{Marker, MarkerType, WavData};
+
+/// Options for [`export_reascript`].
+#[derive(Debug, Clone, Default)]
+pub struct ReascriptOptions {
+    /// Seconds added to every position, e.g. where the mixdown starts in the project
+    pub offset: f64,
+}
+
+/// Exports markers and regions in the Region/Marker Manager CSV layout.
+///
+/// Rows are numbered `R<id>` for regions and `M<id>` for markers, sorted by position.
+/// Times are `M:SS.mmm`; markers have no end or length.
+///
+/// # Example
+/// ```
+/// use reaper_regions::formats::reaper;
+/// use reaper_regions::{Marker, WavData};
+///
+/// let data = WavData {
+///     sample_rate: 48000,
+///     markers: vec![
+///         Marker::new(1, "Verse, part 1".to_string(), 48000 * 65, Some(48000 * 95), 48000),
+///         Marker::new(2, "Drop".to_string(), 24000, None, 48000),
+///     ],
+///     ..WavData::default()
+/// };
+/// assert_eq!(
+///     reaper::export_csv(&data),
+///     "#,Name,Start,End,Length\nM2,Drop,0:00.500,,\nR1,\"Verse, part 1\",1:05.000,1:35.000,0:30.000\n"
+/// );
+/// ```
+pub fn export_csv(data: &WavData) -> String {
+    let mut output = String::from("#,Name,Start,End,Length\n");
+    for marker in sorted(data) {
+        let (prefix, end, length) = match (marker.r#type, marker.end) {
+            (MarkerType::Region, Some(end)) => (
+                'R',
+                format_time(end, data.sample_rate),
+                format_time(end.saturating_sub(marker.start), data.sample_rate),
+            ),
+            _ => ('M', String::new(), String::new()),
+        };
+        output.push_str(&format!(
+            "{prefix}{},{},{},{end},{length}\n",
+            marker.id,
+            quote_csv(&marker.name),
+            format_time(marker.start, data.sample_rate),
+        ));
+    }
+    output
+}
+
+/// Exports markers and regions as a Lua ReaScript that adds them to the current project.
+///
+/// Each marker keeps its ID as the marker/region number. Positions are written at full
+/// precision, relative to `local offset` at the top of the script, which can also be
+/// edited before running it.
+///
+/// # Example
+/// ```
+/// use reaper_regions::formats::reaper::{self, ReascriptOptions};
+/// use reaper_regions::{Marker, WavData};
+///
+/// let data = WavData {
+///     sample_rate: 48000,
+///     markers: vec![Marker::new(3, "Chorus".to_string(), 24000, Some(72000), 48000)],
+///     ..WavData::default()
+/// };
+/// let script = reaper::export_reascript(&data, &ReascriptOptions { offset: 10.0 });
+/// assert!(script.contains("local offset = 10\n"));
+/// assert!(script.contains("reaper.AddProjectMarker2(0, true, offset + 0.5, offset + 1.5, \"Chorus\", 3, 0)"));
+/// ```
+pub fn export_reascript(data: &WavData, options: &ReascriptOptions) -> String {
+    let mut output = String::from(
+        "-- Recreates the markers and regions of a rendered file in the current project.\n",
+    );
+    output.push_str(&format!(
+        "-- Positions are in seconds, relative to offset.\nlocal offset = {}\n\n",
+        options.offset
+    ));
+    output.push_str("reaper.Undo_BeginBlock()\n");
+    for marker in sorted(data) {
+        let (is_region, end) = match (marker.r#type, marker.end_time) {
+            (MarkerType::Region, Some(end)) => (true, end),
+            _ => (false, 0.0),
+        };
+        let end = if is_region {
+            format!("offset + {end}")
+        } else {
+            "0".to_string()
+        };
+        output.push_str(&format!(
+            "reaper.AddProjectMarker2(0, {is_region}, offset + {}, {end}, {}, {}, 0)\n",
+            marker.start_time,
+            quote_lua(&marker.name),
+            marker.id
+        ));
+    }
+    output.push_str(
+        "reaper.UpdateArrange()\nreaper.Undo_EndBlock(\"Add markers from rendered file\", -1)\n",
+    );
+    output
+}
+
+fn sorted(data: &WavData) -> Vec<&Marker> {
+    let mut sorted: Vec<&Marker> = data.markers.iter().collect();
+    sorted.sort_by_key(|m| (m.start, m.id));
+    sorted
+}
+
+/// Formats a sample position as `M:SS.mmm`, rounded to the nearest millisecond.
+fn format_time(samples: u32, sample_rate: u32) -> String {
+    let sample_rate = sample_rate as u64;
+    let millis = (samples as u64 * 2000 + sample_rate) / (sample_rate * 2);
+    format!(
+        "{}:{:02}.{:03}",
+        millis / 60_000,
+        (millis / 1000) % 60,
+        millis % 1000
+    )
+}
+
+/// Quotes a CSV field if needed, doubling embedded quotes.
+fn quote_csv(value: &str) -> String {
+    if value.contains([',', '"', '\r', '\n']) {
+        format!("\"{}\"", value.replace('"', "\"\""))
+    } else {
+        value.to_string()
+    }
+}
+
+/// Quotes a Lua string literal.
+fn quote_lua(value: &str) -> String {
+    let mut quoted = String::from("\"");
+    for c in value.chars() {
+        match c {
+            '\\' => quoted.push_str("\\\\"),
+            '"' => quoted.push_str("\\\""),
+            '\n' => quoted.push_str("\\n"),
+            '\r' => quoted.push_str("\\r"),
+            // Escape each byte of the UTF-8 encoding, so C1 controls stay valid UTF-8
+            c if c.is_control() => {
+                for byte in c.encode_utf8(&mut [0; 4]).bytes() {
+                    quoted.push_str(&format!("\\{byte:03}"));
+                }
+            }
+            c => quoted.push(c),
+        }
+    }
+    quoted.push('"');
+    quoted
+}
@@ -0,0 +1,480 @@
+//! REAPER project (`.RPP`) import and comparison with a render.
+//!
+//! A project holds the full marker and region list, while a rendered WAV only holds what
+//! existed at render time. [`RppProject::parse`] reads:
+//!
+//! - `MARKER` lines: index, position, name, flags, color and GUID. A region is written as
+//!   two lines with the same index, the second one holding the end position.
+//! - `TEMPO` and the `<TEMPOENVEX` envelope points into a [`TempoMap`].
+//! - `RENDER_RANGE` and `SELECTION`, for the time range a render starts at.
+//! - `SAMPLERATE`, `TITLE` and `AUTHOR`.
+//!
+//! [`compare`] then reports regions and markers that are missing from a render, or were
+//! renamed or moved since it was made.
+
+use crate::diff::{MatchBy, match_markers, same_position};
+use crate::tempo::{TempoChange, TempoMap};
+use crate::{Marker, ParseError, WavData};
+use std::collections::BTreeMap;
+use std::fmt;
+
+/// A marker or region of a REAPER project.
+#[derive(Debug, Clone, PartialEq)]
+pub struct RppMarker {
+    /// Marker or region number shown in REAPER (markers and regions are numbered separately)
+    pub index: u32,
+    /// Start position in seconds
+    pub position: f64,
+    /// End position in seconds (regions only)
+    pub end: Option<f64>,
+    /// Name
+    pub name: String,
+    /// Flags as stored in the project (bit 0 set for regions)
+    pub flags: u32,
+    /// Custom color as stored in the project (`0x1000000 | RGB`), if any
+    pub color: Option<u32>,
+    /// GUID, with braces
+    pub guid: Option<String>,
+}
+
+/// Time range rendered by the project's render settings.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct RenderRange {
+    /// Bounds mode: 0 custom time range, 1 entire project, 2 time selection,
+    /// 3 project regions, 4 selected media items, 5 selected regions
+    pub bounds: u32,
+    /// Start of the custom time range in seconds
+    pub start: f64,
+    /// End of the custom time range in seconds
+    pub end: f64,
+}
+
+/// Markers, tempo and render settings read from a REAPER project.
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct RppProject {
+    /// Markers and regions, in file order
+    pub markers: Vec<RppMarker>,
+    /// Tempo and time signature changes
+    pub tempo: TempoMap,
+    /// Render bounds, from `RENDER_RANGE`
+    pub render_range: Option<RenderRange>,
+    /// Time selection `(start, end)` in seconds
+    pub selection: Option<(f64, f64)>,
+    /// Project sample rate, if set in the project settings
+    pub sample_rate: Option<u32>,
+    /// Project title
+    pub title: Option<String>,
+    /// Project author
+    pub author: Option<String>,
+}
+
+impl RppProject {
+    /// Parses the contents of a REAPER project file.
+    ///
+    /// Unknown lines and blocks are ignored; only the top level of the project and its
+    /// `<TEMPOENVEX` block are read.
+    ///
+    /// # Errors
+    /// * [`ParseError::InvalidLine`] - If a `MARKER`, `TEMPO` or envelope point line has
+    ///   missing or non-numeric fields
+    /// * [`ParseError::Other`] - If the text is not a REAPER project
+    ///
+    /// # Example
+    /// ```
+    /// use reaper_regions::formats::rpp::RppProject;
+    ///
+    /// let project = RppProject::parse(
+    ///     "<REAPER_PROJECT 0.1 \"7.0\" 1700000000\n  TEMPO 96 3 4\n  MARKER 1 2.5 \"Verse 1\" 1 0 1 R {6F4E} 0\n  MARKER 1 10 \"\" 1\n>\n",
+    /// )
+    /// .unwrap();
+    /// assert_eq!(project.markers[0].name, "Verse 1");
+    /// assert_eq!(project.markers[0].end, Some(10.0));
+    /// assert_eq!(project.tempo.changes[0].bpm, 96.0);
+    /// assert_eq!(project.tempo.changes[0].numerator, 3);
+    /// ```
+    pub fn parse(text: &str) -> Result<Self, ParseError> {
+        let mut project = RppProject::default();
+        let mut tempo: Option<TempoChange> = None;
+        let mut envelope: Vec<TempoChange> = Vec::new();
+        let mut found_project = false;
+        // Names of the open blocks
+        let mut blocks: Vec<String> = Vec::new();
+
+        for (index, line) in text.lines().enumerate() {
+            let number = index + 1;
+            let invalid = || ParseError::InvalidLine(number, line.trim().to_string());
+            let tokens = tokenize(line);
+            let Some(first) = tokens.first() else {
+                continue;
+            };
+
+            if let Some(name) = first.strip_prefix('<') {
+                if blocks.is_empty() && name == "REAPER_PROJECT" {
+                    found_project = true;
+                }
+                blocks.push(name.to_string());
+                continue;
+            }
+            if first == ">" {
+                blocks.pop();
+                continue;
+            }
+
+            let path: Vec<&str> = blocks.iter().map(String::as_str).collect();
+            match (path.as_slice(), first.as_str()) {
+                (["REAPER_PROJECT"], "MARKER") => {
+                    let index = field(&tokens, 1).ok_or_else(invalid)?;
+                    let position = field(&tokens, 2).ok_or_else(invalid)?;
+                    let name = tokens.get(3).cloned().unwrap_or_default();
+                    let flags: u32 = field(&tokens, 4).unwrap_or(0);
+                    let is_region = flags & 1 != 0;
+                    // The second line of a region holds its end
+                    if is_region
+                        && let Some(open) = project
+                            .markers
+                            .iter_mut()
+                            .rev()
+                            .find(|m| m.index == index && m.flags & 1 != 0 && m.end.is_none())
+                    {
+                        open.end = Some(position);
+                        continue;
+                    }
+                    project.markers.push(RppMarker {
+                        index,
+                        position,
+                        end: None,
+                        name,
+                        flags,
+                        color: field(&tokens, 5).filter(|&color: &u32| color != 0),
+                        guid: tokens.iter().skip(5).find(|t| t.starts_with('{')).cloned(),
+                    });
+                }
+                (["REAPER_PROJECT"], "TEMPO") => {
+                    tempo = Some(TempoChange {
+                        position: 0.0,
+                        bpm: field(&tokens, 1).ok_or_else(invalid)?,
+                        numerator: field(&tokens, 2).unwrap_or(4),
+                        denominator: field(&tokens, 3).unwrap_or(4),
+                        linear: false,
+                    });
+                }
+                (["REAPER_PROJECT"], "RENDER_RANGE") => {
+                    project.render_range = Some(RenderRange {
+                        bounds: field(&tokens, 1).ok_or_else(invalid)?,
+                        start: field(&tokens, 2).unwrap_or(0.0),
+                        end: field(&tokens, 3).unwrap_or(0.0),
+                    });
+                }
+                (["REAPER_PROJECT"], "SELECTION") => {
+                    let start = field(&tokens, 1).ok_or_else(invalid)?;
+                    let end = field(&tokens, 2).ok_or_else(invalid)?;
+                    project.selection = Some((start, end));
+                }
+                (["REAPER_PROJECT"], "SAMPLERATE") => {
+                    project.sample_rate = field(&tokens, 1).filter(|&rate: &u32| rate > 0);
+                }
+                (["REAPER_PROJECT"], "TITLE") => project.title = tokens.get(1).cloned(),
+                (["REAPER_PROJECT"], "AUTHOR") => project.author = tokens.get(1).cloned(),
+                (["REAPER_PROJECT", "TEMPOENVEX"], "PT") => {
+                    let position = field(&tokens, 1).ok_or_else(invalid)?;
+                    let bpm = field(&tokens, 2).ok_or_else(invalid)?;
+                    let shape: u32 = field(&tokens, 3).unwrap_or(1);
+                    // Time signature packed as numerator | denominator << 16, only on changes
+                    let (numerator, denominator) = match field::<u32>(&tokens, 4) {
+                        Some(packed) if packed != 0 => (packed & 0xFFFF, packed >> 16),
+                        _ => envelope
+                            .last()
+                            .or(tempo.as_ref())
+                            .map_or((4, 4), |previous| {
+                                (previous.numerator, previous.denominator)
+                            }),
+                    };
+                    envelope.push(TempoChange {
+                        position,
+                        bpm,
+                        numerator,
+                        denominator,
+                        linear: shape == 0,
+                    });
+                }
+                _ => {}
+            }
+        }
+
+        if !found_project {
+            return Err(ParseError::Other("not a REAPER project".to_string()));
+        }
+
+        envelope.sort_by(|a, b| a.position.total_cmp(&b.position));
+        project.tempo = match (envelope.is_empty(), tempo) {
+            (false, _) => TempoMap { changes: envelope },
+            (true, Some(tempo)) => TempoMap {
+                changes: vec![tempo],
+            },
+            (true, None) => TempoMap::default(),
+        };
+        Ok(project)
+    }
+
+    /// Returns the start and end in seconds of the time range a render of the project
+    /// covers, if it is a single range (custom time range, entire project or time selection).
+    ///
+    /// The end is infinite when rendering the entire project.
+    pub fn render_bounds(&self) -> Option<(f64, f64)> {
+        match self.render_range?.bounds {
+            0 => self.render_range.map(|range| (range.start, range.end)),
+            1 => Some((0.0, f64::INFINITY)),
+            2 => self.selection,
+            _ => None,
+        }
+    }
+
+    /// Converts the project markers to [`Marker`]s, relative to `offset` seconds.
+    ///
+    /// Markers before the offset are left out, and regions starting before it are clipped.
+    /// IDs are assigned from 1 in order of position, like REAPER does when rendering.
+    ///
+    /// # Arguments
+    /// * `sample_rate` - Sample rate used to convert seconds into sample positions
+    /// * `offset` - Position in seconds that becomes sample 0
+    pub fn to_markers(&self, sample_rate: u32, offset: f64) -> Vec<Marker> {
+        let to_samples = |seconds: f64| ((seconds - offset) * sample_rate as f64).round();
+
+        let mut sorted: Vec<&RppMarker> = self.markers.iter().collect();
+        sorted.sort_by(|a, b| a.position.total_cmp(&b.position));
+
+        let mut markers = Vec::new();
+        for marker in sorted {
+            let end = marker.end.map(to_samples);
+            let start = to_samples(marker.position);
+            if end.unwrap_or(start) < 0.0 {
+                continue;
+            }
+            let start = start.clamp(0.0, u32::MAX as f64) as u32;
+            let end = end.map(|end| end.min(u32::MAX as f64) as u32);
+            let id = markers.len() as u32 + 1;
+            markers.push(Marker::new(
+                id,
+                marker.name.clone(),
+                start,
+                end,
+                sample_rate,
+            ));
+        }
+        markers
+    }
+}
+
+/// Reads a REAPER project file into [`WavData`], with positions on the project timeline.
+///
+/// The sample rate is taken from the project settings, falling back to `sample_rate`.
+/// `TITLE` and `AUTHOR` become the `INAM` and `IART` INFO tags, and the project's tempo
+/// map becomes [`WavData::tempo`].
+///
+/// # Errors
+/// * [`ParseError::Io`] - If the file cannot be read
+/// * [`ParseError::InvalidLine`] - If a line cannot be parsed (see [`RppProject::parse`])
+pub fn import_file(file_path: &str, sample_rate: u32) -> Result<WavData, ParseError> {
+    let project = RppProject::parse(&std::fs::read_to_string(file_path)?)?;
+    let sample_rate = project.sample_rate.unwrap_or(sample_rate);
+
+    let mut info = BTreeMap::new();
+    if let Some(title) = &project.title {
+        info.insert("INAM".to_string(), title.clone());
+    }
+    if let Some(author) = &project.author {
+        info.insert("IART".to_string(), author.clone());
+    }
+
+    Ok(WavData {
+        path: file_path.to_string(),
+        sample_rate,
+        markers: project.to_markers(sample_rate, 0.0),
+        info,
+        tempo: Some(project.tempo),
+        ..WavData::default()
+    })
+}
+
+/// A difference between a project and its render, found by [`compare`].
+#[derive(Debug, Clone, PartialEq)]
+pub enum RenderChange {
+    /// In the project, but not in the render
+    Missing(Marker),
+    /// In the render, but no longer in the project
+    Removed(Marker),
+    /// At the same position, with a different name
+    Renamed {
+        /// Marker in the project
+        project: Marker,
+        /// Marker in the render
+        render: Marker,
+    },
+    /// Same name, at a different position (or with a different end)
+    Moved {
+        /// Marker in the project
+        project: Marker,
+        /// Marker in the render
+        render: Marker,
+    },
+}
+
+impl fmt::Display for RenderChange {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            RenderChange::Missing(marker) => write!(
+                f,
+                "missing: {} '{}' at {:.3} s is not in the render",
+                marker.r#type, marker.name, marker.start_time
+            ),
+            RenderChange::Removed(marker) => write!(
+                f,
+                "removed: {} '{}' at {:.3} s is no longer in the project",
+                marker.r#type, marker.name, marker.start_time
+            ),
+            RenderChange::Renamed { project, render } => write!(
+                f,
+                "renamed: {} '{}' at {:.3} s is now '{}'",
+                project.r#type, render.name, render.start_time, project.name
+            ),
+            RenderChange::Moved { project, render } => {
+                write!(
+                    f,
+                    "moved: {} '{}' from {:.3} s to {:.3} s ({:+.3} s)",
+                    project.r#type,
+                    project.name,
+                    render.start_time,
+                    project.start_time,
+                    project.start_time - render.start_time
+                )?;
+                if let (Some(old), Some(new)) = (render.end_time, project.end_time)
+                    && old != new
+                {
+                    write!(
+                        f,
+                        ", end from {old:.3} s to {new:.3} s ({:+.3} s)",
+                        new - old
+                    )?;
+                }
+                Ok(())
+            }
+        }
+    }
+}
+
+/// Compares the markers of a project with those of its render.
+///
+/// Both lists must be on the render's timeline and at the same sample rate, e.g. from
+/// [`RppProject::to_markers`] with the [`render bounds`](RppProject::render_bounds) start.
+/// Markers are matched by type and name first (nearest position wins), then the remaining
+/// ones by type and position, which are reported as renamed. Positions within `tolerance`
+/// samples are considered equal.
+///
+/// Project markers after the end of the render (`length`, if known) are not reported as missing.
+///
+/// # Example
+/// ```
+/// use reaper_regions::Marker;
+/// use reaper_regions::formats::rpp::{self, RenderChange};
+///
+/// let project = vec![
+///     Marker::new(1, "Intro".to_string(), 0, Some(4800), 48000),
+///     Marker::new(2, "Verse 1".to_string(), 4800, Some(9600), 48000),
+///     Marker::new(3, "Chorus".to_string(), 9800, None, 48000),
+/// ];
+/// let render = vec![
+///     Marker::new(1, "Intro".to_string(), 0, Some(4800), 48000),
+///     Marker::new(2, "Verse".to_string(), 4800, Some(9600), 48000),
+///     Marker::new(3, "Chorus".to_string(), 9600, None, 48000),
+/// ];
+/// let changes = rpp::compare(&project, &render, None, 0);
+/// assert!(matches!(&changes[0], RenderChange::Moved { project, .. } if project.name == "Chorus"));
+/// assert!(matches!(&changes[1], RenderChange::Renamed { render, .. } if render.name == "Verse"));
+/// ```
+pub fn compare(
+    project: &[Marker],
+    render: &[Marker],
+    length: Option<u32>,
+    tolerance: u32,
+) -> Vec<RenderChange> {
+    let matches = match_markers(
+        project,
+        render,
+        &[MatchBy::Name, MatchBy::Position],
+        tolerance,
+    );
+    let mut changes = Vec::new();
+
+    // Same type and name
+    for (marker, found) in project.iter().zip(&matches) {
+        if let Some((r, MatchBy::Name)) = *found
+            && !same_position(marker, &render[r], tolerance)
+        {
+            changes.push(RenderChange::Moved {
+                project: marker.clone(),
+                render: render[r].clone(),
+            });
+        }
+    }
+
+    // Same type and position
+    for (marker, found) in project.iter().zip(&matches) {
+        match *found {
+            Some((r, MatchBy::Position)) => changes.push(RenderChange::Renamed {
+                project: marker.clone(),
+                render: render[r].clone(),
+            }),
+            None if length.is_none_or(|length| marker.start < length) => {
+                changes.push(RenderChange::Missing(marker.clone()))
+            }
+            _ => {}
+        }
+    }
+
+    for (r, marker) in render.iter().enumerate() {
+        if !matches.iter().flatten().any(|&(matched, _)| matched == r) {
+            changes.push(RenderChange::Removed(marker.clone()));
+        }
+    }
+    changes
+}
+
+/// Parses field `index` of a tokenized line.
+fn field<T: std::str::FromStr>(tokens: &[String], index: usize) -> Option<T> {
+    tokens.get(index)?.parse().ok()
+}
+
+/// Splits an RPP line into tokens.
+///
+/// Tokens are separated by whitespace. A token may be quoted with `"`, `'` or `` ` ``
+/// (REAPER picks a quote character that doesn't occur in the value); there is no escaping.
+fn tokenize(line: &str) -> Vec<String> {
+    let mut tokens = Vec::new();
+    let mut chars = line.trim().chars().peekable();
+    while let Some(&c) = chars.peek() {
+        if c.is_whitespace() {
+            chars.next();
+            continue;
+        }
+        let mut token = String::new();
+        if matches!(c, '"' | '\'' | '`') {
+            chars.next();
+            for next in chars.by_ref() {
+                if next == c {
+                    break;
+                }
+                token.push(next);
+            }
+        } else {
+            while let Some(&c) = chars.peek() {
+                if c.is_whitespace() {
+                    break;
+                }
+                token.push(c);
+                chars.next();
+            }
+        }
+        tokens.push(token);
+    }
+    tokens
+}
@@ -0,0 +1,326 @@
+//! Subtitle and synced-lyrics export: SubRip (`.srt`), WebVTT (`.vtt`) and LRC (`.lrc`).
+//!
+//! Region names become captions spanning the region. Simple markers become captions
+//! lasting until the next marker, optionally capped at a fixed duration. Times are
+//! computed from the sample positions and rounded to the nearest millisecond
+//! (centisecond for LRC).
+//!
+//! Overlapping regions can either be kept as separate, simultaneously displayed cues
+//! ([`Overlap::Stack`]), or merged into consecutive non-overlapping cues listing every
+//! active caption ([`Overlap::Merge`]). LRC has no end times, so it is always merged.
+
+use super::Export;
+use crate::diagnostic::Diagnostic;
+use crate::{Marker, MarkerType, WavData};
+
+/// How long the last simple marker lasts if the audio length is unknown, in seconds.
+pub const DEFAULT_LAST_MARKER_SECONDS: f64 = 5.0;
+
+/// How overlapping captions are handled.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, strum::Display, strum::EnumString)]
+#[strum(serialize_all = "lowercase", ascii_case_insensitive)]
+pub enum Overlap {
+    /// Keep overlapping cues; players show them on top of each other
+    #[default]
+    Stack,
+    /// Split the timeline at every cue boundary, listing all active captions in each cue
+    Merge,
+}
+
+/// Options for [`srt`], [`vtt`] and [`lrc`].
+#[derive(Debug, Clone)]
+pub struct SubtitleOptions {
+    /// Include simple markers as captions
+    pub include_markers: bool,
+    /// Maximum caption duration of simple markers in seconds.
+    ///
+    /// Markers always end at the next marker. Without a maximum, the last marker lasts until
+    /// the end of the audio, or [`DEFAULT_LAST_MARKER_SECONDS`] if the length is unknown.
+    pub marker_duration: Option<f64>,
+    /// How overlapping captions are handled (ignored by [`lrc`], which always merges)
+    pub overlap: Overlap,
+}
+
+impl Default for SubtitleOptions {
+    fn default() -> Self {
+        SubtitleOptions {
+            include_markers: true,
+            marker_duration: None,
+            overlap: Overlap::Stack,
+        }
+    }
+}
+
+/// A caption, with its start and end in samples.
+#[derive(Debug, Clone, PartialEq)]
+struct Cue {
+    start: u64,
+    end: u64,
+    lines: Vec<String>,
+}
+
+/// Exports markers and regions as SubRip (`.srt`) subtitles.
+///
+/// # Example
+/// ```
+/// use reaper_regions::formats::subtitles::{self, SubtitleOptions};
+/// use reaper_regions::{Marker, WavData};
+///
+/// let data = WavData {
+///     sample_rate: 48000,
+///     markers: vec![Marker::new(1, "Verse".to_string(), 24000, Some(120000), 48000)],
+///     ..WavData::default()
+/// };
+/// let export = subtitles::srt(&data, &SubtitleOptions::default());
+/// assert_eq!(export.output, "1\n00:00:00,500 --> 00:00:02,500\nVerse\n\n");
+/// ```
+pub fn srt(data: &WavData, options: &SubtitleOptions) -> Export {
+    let (cues, diagnostics) = cues(data, options, options.overlap);
+    let output = cues
+        .iter()
+        .enumerate()
+        .map(|(index, cue)| {
+            format!(
+                "{}\n{} --> {}\n{}\n\n",
+                index + 1,
+                format_time(cue.start, data.sample_rate, ','),
+                format_time(cue.end, data.sample_rate, ','),
+                cue.lines.join("\n")
+            )
+        })
+        .collect();
+    Export {
+        output,
+        diagnostics,
+    }
+}
+
+/// Exports markers and regions as WebVTT (`.vtt`) subtitles.
+///
+/// Cues are numbered like SRT, and `&`, `<` and `>` in names are escaped.
+///
+/// # Example
+/// ```
+/// use reaper_regions::formats::subtitles::{self, SubtitleOptions};
+/// use reaper_regions::{Marker, WavData};
+///
+/// let data = WavData {
+///     sample_rate: 48000,
+///     markers: vec![Marker::new(1, "Verse".to_string(), 24000, Some(120000), 48000)],
+///     ..WavData::default()
+/// };
+/// let export = subtitles::vtt(&data, &SubtitleOptions::default());
+/// assert_eq!(export.output, "WEBVTT\n\n1\n00:00:00.500 --> 00:00:02.500\nVerse\n\n");
+/// ```
+pub fn vtt(data: &WavData, options: &SubtitleOptions) -> Export {
+    let (cues, diagnostics) = cues(data, options, options.overlap);
+    let mut output = String::from("WEBVTT\n\n");
+    for (index, cue) in cues.iter().enumerate() {
+        output.push_str(&format!(
+            "{}\n{} --> {}\n{}\n\n",
+            index + 1,
+            format_time(cue.start, data.sample_rate, '.'),
+            format_time(cue.end, data.sample_rate, '.'),
+            cue.lines
+                .iter()
+                .map(|line| escape_vtt(line))
+                .collect::<Vec<_>>()
+                .join("\n")
+        ));
+    }
+    Export {
+        output,
+        diagnostics,
+    }
+}
+
+/// Exports markers and regions as LRC synced lyrics.
+///
+/// Each caption is a `[mm:ss.xx]` line. A line with only a timestamp clears the caption
+/// at the end of a cue when nothing follows immediately. Title, artist and album tags are
+/// taken from the `INAM`, `IART` and `IPRD` INFO tags.
+///
+/// # Example
+/// ```
+/// use reaper_regions::formats::subtitles::{self, SubtitleOptions};
+/// use reaper_regions::{Marker, WavData};
+///
+/// let data = WavData {
+///     sample_rate: 48000,
+///     markers: vec![Marker::new(1, "Verse".to_string(), 24000, Some(120000), 48000)],
+///     ..WavData::default()
+/// };
+/// let export = subtitles::lrc(&data, &SubtitleOptions::default());
+/// assert_eq!(export.output, "[00:00.50]Verse\n[00:02.50]\n");
+/// ```
+pub fn lrc(data: &WavData, options: &SubtitleOptions) -> Export {
+    let (cues, diagnostics) = cues(data, options, Overlap::Merge);
+    let mut output = String::new();
+    for (tag, key) in [("ti", "INAM"), ("ar", "IART"), ("al", "IPRD")] {
+        if let Some(value) = data.info.get(key) {
+            output.push_str(&format!("[{tag}:{}]\n", single_line(value)));
+        }
+    }
+    for (index, cue) in cues.iter().enumerate() {
+        output.push_str(&format!(
+            "{}{}\n",
+            format_lrc_time(cue.start, data.sample_rate),
+            cue.lines.join(" / ")
+        ));
+        if cues.get(index + 1).is_none_or(|next| next.start > cue.end) {
+            output.push_str(&format!("{}\n", format_lrc_time(cue.end, data.sample_rate)));
+        }
+    }
+    Export {
+        output,
+        diagnostics,
+    }
+}
+
+/// Builds the cues, sorted by start, along with diagnostics about skipped and overlapping captions.
+fn cues(
+    data: &WavData,
+    options: &SubtitleOptions,
+    overlap: Overlap,
+) -> (Vec<Cue>, Vec<Diagnostic>) {
+    let mut diagnostics = Vec::new();
+    let sample_rate = data.sample_rate as u64;
+
+    let mut sorted: Vec<&Marker> = data.markers.iter().collect();
+    sorted.sort_by_key(|m| (m.start, m.id));
+
+    let point_starts: Vec<u64> = sorted
+        .iter()
+        .filter(|m| m.r#type == MarkerType::Marker)
+        .map(|m| m.start as u64)
+        .collect();
+
+    let mut cues = Vec::new();
+    for marker in sorted {
+        let start = marker.start as u64;
+        let end = match marker.r#type {
+            MarkerType::Region => marker.end.map_or(start, u64::from),
+            MarkerType::Marker if !options.include_markers => continue,
+            MarkerType::Marker => {
+                let next = point_starts.iter().copied().find(|&s| s > start);
+                let cap = options
+                    .marker_duration
+                    .map(|seconds| start + (seconds * sample_rate as f64).round() as u64);
+                match (next, cap) {
+                    (Some(next), Some(cap)) => next.min(cap),
+                    (Some(end), None) | (None, Some(end)) => end,
+                    (None, None) => match data.length {
+                        Some(length) if length as u64 > start => length as u64,
+                        _ => {
+                            start
+                                + (DEFAULT_LAST_MARKER_SECONDS * sample_rate as f64).round() as u64
+                        }
+                    },
+                }
+            }
+        };
+        if end <= start {
+            diagnostics.push(Diagnostic::warning(
+                Some(marker.id),
+                format!("'{}' has no duration, skipping", marker.name),
+            ));
+            continue;
+        }
+        cues.push((
+            marker.id,
+            Cue {
+                start,
+                end,
+                lines: vec![single_line(&marker.name)],
+            },
+        ));
+    }
+
+    for (index, (id, cue)) in cues.iter().enumerate() {
+        for (other_id, other) in &cues[index + 1..] {
+            if other.start >= cue.end {
+                break;
+            }
+            let action = match overlap {
+                Overlap::Stack => "stacked",
+                Overlap::Merge => "merged",
+            };
+            diagnostics.push(Diagnostic::info(
+                Some(*other_id),
+                format!("overlaps marker {id}, captions are {action}"),
+            ));
+        }
+    }
+
+    let cues = cues.into_iter().map(|(_, cue)| cue).collect();
+    let cues = match overlap {
+        Overlap::Stack => cues,
+        Overlap::Merge => merge(cues),
+    };
+    (cues, diagnostics)
+}
+
+/// Splits overlapping cues at every boundary, so that each resulting cue lists
+/// the captions active during it. Adjacent cues with the same captions are joined.
+fn merge(cues: Vec<Cue>) -> Vec<Cue> {
+    let mut boundaries: Vec<u64> = cues.iter().flat_map(|cue| [cue.start, cue.end]).collect();
+    boundaries.sort_unstable();
+    boundaries.dedup();
+
+    let mut merged: Vec<Cue> = Vec::new();
+    for window in boundaries.windows(2) {
+        let (start, end) = (window[0], window[1]);
+        let lines: Vec<String> = cues
+            .iter()
+            .filter(|cue| cue.start <= start && end <= cue.end)
+            .flat_map(|cue| cue.lines.iter().cloned())
+            .collect();
+        if lines.is_empty() {
+            continue;
+        }
+        match merged.last_mut() {
+            Some(last) if last.end == start && last.lines == lines => last.end = end,
+            _ => merged.push(Cue { start, end, lines }),
+        }
+    }
+    merged
+}
+
+/// Formats a sample position as `HH:MM:SS,mmm` (with the given decimal separator).
+fn format_time(samples: u64, sample_rate: u32, separator: char) -> String {
+    let millis = rounded_division(samples * 1000, sample_rate as u64);
+    format!(
+        "{:02}:{:02}:{:02}{separator}{:03}",
+        millis / 3_600_000,
+        (millis / 60_000) % 60,
+        (millis / 1000) % 60,
+        millis % 1000
+    )
+}
+
+/// Formats a sample position as an LRC `[mm:ss.xx]` timestamp.
+fn format_lrc_time(samples: u64, sample_rate: u32) -> String {
+    let centis = rounded_division(samples * 100, sample_rate as u64);
+    format!(
+        "[{:02}:{:02}.{:02}]",
+        centis / 6000,
+        (centis / 100) % 60,
+        centis % 100
+    )
+}
+
+fn rounded_division(value: u64, divisor: u64) -> u64 {
+    (value * 2 + divisor) / (divisor * 2)
+}
+
+/// Captions are one line per name.
+fn single_line(value: &str) -> String {
+    value.replace(['\r', '\n'], " ")
+}
+
+fn escape_vtt(value: &str) -> String {
+    value
+        .replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+}
@@ -0,0 +1,238 @@
+//! YouTube chapter timestamp list export.
+//!
+//! YouTube turns `0:00 Title` lines in a video description into chapters, provided that:
+//!
+//! - the first timestamp is `0:00`,
+//! - there are at least three chapters,
+//! - each chapter is at least ten seconds long.
+//!
+//! [`export`] enforces the first and last rules and reports every adjustment, as well as
+//! regions that don't map cleanly onto chapters, as [`Diagnostic`]s.
+
+use super::Export;
+use crate::diagnostic::Diagnostic;
+use crate::{Marker, MarkerType, WavData};
+
+/// Minimum number of chapters YouTube requires.
+pub const MIN_CHAPTERS: usize = 3;
+
+/// Minimum chapter length in seconds YouTube requires.
+pub const MIN_CHAPTER_SECONDS: u64 = 10;
+
+/// Options for [`export`].
+#[derive(Debug, Clone)]
+pub struct YoutubeOptions {
+    /// Title of a chapter inserted at `0:00` when the first marker starts ten seconds or
+    /// more later. Without it, or if the first marker starts earlier, the first chapter is
+    /// moved to `0:00`.
+    pub intro: Option<String>,
+    /// Include simple markers as chapters (regions are always included)
+    pub include_markers: bool,
+}
+
+impl Default for YoutubeOptions {
+    fn default() -> Self {
+        YoutubeOptions {
+            intro: None,
+            include_markers: true,
+        }
+    }
+}
+
+/// Exports markers and regions as a YouTube chapter list.
+///
+/// Chapters that would start less than ten seconds after the previous chapter are dropped,
+/// as is a last chapter ending less than ten seconds after it starts, if the length of the
+/// audio is known. An intro chapter is only added if the first marker starts at least ten
+/// seconds in; otherwise the first marker is moved to `0:00`.
+///
+/// # Arguments
+/// * `data` - Parsed WAV data containing the markers
+/// * `options` - Intro chapter and marker handling
+///
+/// # Returns
+/// * [`Export`] - The chapter list, with diagnostics for every rule violation
+///
+/// # Example
+/// ```
+/// use reaper_regions::formats::youtube::{self, YoutubeOptions};
+/// use reaper_regions::{Marker, WavData};
+///
+/// let data = WavData {
+///     sample_rate: 48000,
+///     markers: vec![
+///         Marker::new(1, "Opener".to_string(), 48000 * 15, None, 48000),
+///         Marker::new(2, "Ballad".to_string(), 48000 * 200, None, 48000),
+///         Marker::new(3, "Encore".to_string(), 48000 * 3700, None, 48000),
+///     ],
+///     ..WavData::default()
+/// };
+/// let options = YoutubeOptions { intro: Some("Intro".to_string()), ..YoutubeOptions::default() };
+/// let export = youtube::export(&data, &options);
+/// assert_eq!(export.output, "0:00 Intro\n0:15 Opener\n3:20 Ballad\n1:01:40 Encore\n");
+/// ```
+pub fn export(data: &WavData, options: &YoutubeOptions) -> Export {
+    let mut diagnostics = Vec::new();
+
+    let mut sorted: Vec<&Marker> = data
+        .markers
+        .iter()
+        .filter(|m| options.include_markers || m.r#type == MarkerType::Region)
+        .collect();
+    sorted.sort_by_key(|m| (m.start, m.id));
+
+    check_regions(&sorted, &mut diagnostics);
+
+    // (start in whole seconds, marker ID, title)
+    let mut chapters: Vec<(u64, Option<u32>, &str)> = Vec::new();
+    if let Some(first) = sorted.first()
+        && first.start_time >= 1.0
+    {
+        let moved = |reason: &str| {
+            Diagnostic::warning(
+                Some(first.id),
+                format!(
+                    "'{}' starts at {}, moved to 0:00 because {reason}",
+                    first.name,
+                    format_timestamp(first.start_time as u64)
+                ),
+            )
+        };
+        match &options.intro {
+            Some(intro) if first.start_time >= MIN_CHAPTER_SECONDS as f64 => {
+                chapters.push((0, None, intro))
+            }
+            Some(intro) => diagnostics.push(moved(&format!(
+                "an intro '{intro}' would be shorter than {MIN_CHAPTER_SECONDS} seconds"
+            ))),
+            None => diagnostics.push(moved("the first chapter must start at 0:00")),
+        }
+    }
+
+    for marker in sorted {
+        let start = if chapters.is_empty() {
+            0
+        } else {
+            marker.start_time as u64
+        };
+        if let Some(&(previous, _, title)) = chapters.last()
+            && start < previous + MIN_CHAPTER_SECONDS
+        {
+            diagnostics.push(Diagnostic::warning(
+                Some(marker.id),
+                format!(
+                    "'{}' at {} starts less than {MIN_CHAPTER_SECONDS} seconds after '{title}', skipping",
+                    marker.name,
+                    format_timestamp(marker.start_time as u64)
+                ),
+            ));
+            continue;
+        }
+        chapters.push((start, Some(marker.id), &marker.name));
+    }
+
+    // The last chapter runs until the end of the audio
+    if let Some(length) = data
+        .length
+        .filter(|&length| length > 0 && data.sample_rate > 0)
+        && chapters.len() > 1
+        && let Some(&(start, id, title)) = chapters.last()
+        && (length as f64 / data.sample_rate as f64) < (start + MIN_CHAPTER_SECONDS) as f64
+    {
+        diagnostics.push(Diagnostic::warning(
+            id,
+            format!(
+                "'{title}' at {} ends less than {MIN_CHAPTER_SECONDS} seconds later with the audio, skipping",
+                format_timestamp(start)
+            ),
+        ));
+        chapters.pop();
+    }
+
+    if !chapters.is_empty() && chapters.len() < MIN_CHAPTERS {
+        diagnostics.push(Diagnostic::warning(
+            None,
+            format!(
+                "YouTube needs at least {MIN_CHAPTERS} chapters, found {}",
+                chapters.len()
+            ),
+        ));
+    }
+
+    let output = chapters
+        .iter()
+        .map(|(start, _, title)| {
+            format!(
+                "{} {}\n",
+                format_timestamp(*start),
+                title.replace(['\r', '\n'], " ")
+            )
+        })
+        .collect();
+
+    Export {
+        output,
+        diagnostics,
+    }
+}
+
+/// Reports regions that can't be represented faithfully by YouTube chapters,
+/// which only have a start time and run until the next chapter.
+fn check_regions(sorted: &[&Marker], diagnostics: &mut Vec<Diagnostic>) {
+    let regions: Vec<&Marker> = sorted
+        .iter()
+        .copied()
+        .filter(|m| m.r#type == MarkerType::Region)
+        .collect();
+
+    for region in &regions {
+        if region
+            .duration
+            .is_some_and(|duration| duration < MIN_CHAPTER_SECONDS as f64)
+        {
+            diagnostics.push(Diagnostic::warning(
+                Some(region.id),
+                format!(
+                    "'{}' is shorter than {MIN_CHAPTER_SECONDS} seconds",
+                    region.name
+                ),
+            ));
+        }
+    }
+
+    for pair in regions.windows(2) {
+        let (previous, next) = (pair[0], pair[1]);
+        let Some(end) = previous.end else { continue };
+        if next.start < end {
+            diagnostics.push(Diagnostic::warning(
+                Some(next.id),
+                format!(
+                    "'{}' overlaps '{}', which ends early as a chapter",
+                    next.name, previous.name
+                ),
+            ));
+        } else if next.start > end {
+            diagnostics.push(Diagnostic::info(
+                Some(previous.id),
+                format!(
+                    "the gap after '{}' becomes part of its chapter",
+                    previous.name
+                ),
+            ));
+        }
+    }
+}
+
+/// Formats whole seconds as `M:SS`, or `H:MM:SS` from one hour on.
+fn format_timestamp(seconds: u64) -> String {
+    if seconds >= 3600 {
+        format!(
+            "{}:{:02}:{:02}",
+            seconds / 3600,
+            (seconds / 60) % 60,
+            seconds % 60
+        )
+    } else {
+        format!("{}:{:02}", seconds / 60, seconds % 60)
+    }
+}
@@ -7,10 +7,10 @@
 //! In order for this to work properly, two conditions must be met:
 //!
 //! 1. The project **must** have at least one marker or region defined in the track view:
-//! <img alt="Track showing a marker and two regions" src="https://raw.githubusercontent.com/dra11y/reaper-regions/main/images/track.png" width="511">
+//!    <img alt="Track showing a marker and two regions" src="https://raw.githubusercontent.com/dra11y/reaper-regions/main/images/track.png" width="511">
 //!
 //! 2. The WAV file **must** be rendered with Regions or Regions + Markers, and there must be at least one marker or region in the time range of the rendered output.
-//! <img alt="Render with markers or markers + regions" src="https://raw.githubusercontent.com/dra11y/reaper-regions/main/images/render.png" width="610">
+//!    <img alt="Render with markers or markers + regions" src="https://raw.githubusercontent.com/dra11y/reaper-regions/main/images/render.png" width="610">
 //!    - The "Write BWF ('bext') chunk" checkbox is **optional** and has no effect on the regions/markers:
 //!
 //! This library **might** work with WAV files exported from other DAWs with markers/regions,
@@ -22,12 +22,24 @@
 //! - Extracts region names, start/end sample offsets, and start/end times and durations (in seconds)
 //! - Supports both markers (single points) and regions (start/end ranges)
 //! - Provides human-readable and machine-readable output formats
+//! - Writes regions as `smpl` loops for samplers and game audio engines ([`loops`])
+//! - Splits WAV files into one file per region ([`split`])
+//! - Crops, pads and trims WAV files while keeping markers in place ([`edit`])
+//! - Concatenates WAV files, shifting their markers into place ([`mod@concat`])
+//! - Copies markers between WAV files, converting the sample rate ([`transplant`])
+//! - Adds, renames, deletes and moves markers in place ([`markers`])
+//! - Compares the markers of two files ([`diff`])
+//! - Selects markers by type, name, ID, position and duration ([`select`])
+//! - Looks up markers and regions by position ([`query`])
+//! - Analyses how regions nest, overlap and cover the audio ([`structure`])
 //!
 //! ## Supported WAV Chunks
 //! - `cue ` - Cue points with unique IDs and positions
 //! - `labl` - Labels associated with cue points
 //! - `smpl` - Sampler data including loop points
-//! - `LIST` - List chunks containing additional metadata
+//! - `inst` - Instrument data (key and velocity ranges, fine tuning and gain)
+//! - `acid` - ACID loop information (tempo, meter, beats and root note)
+//! - `LIST` - List chunks containing labels (`adtl`) and text tags (`INFO`)
 //!
 //! ## Example
 //! ```rust,no_run
@@ -97,6 +109,8 @@
 //! cargo add reaper-regions --no-default-features
 //! ```
 //!
+//! Add `--features regex` to select and rename markers by regular expression.
+//!
 //! ## Motivation
 //! I was motivated to create this tool because I needed to sync song regions from my
 //! master mixdown created in REAPER with my video projects in [DaVinci Resolve](https://www.blackmagicdesign.com/products/davinciresolve)
@@ -112,12 +126,31 @@
 //! This library is free, open source, and MIT-licensed.
 //! DaVinci Resolve is a trademark and the copyright property of [Blackmagic Design Pty. Ltd.](https://www.blackmagicdesign.com/)
 
+pub mod concat;
+pub mod diagnostic;
+pub mod diff;
+pub mod edit;
+pub mod formats;
+pub mod loops;
+pub mod markers;
+pub mod query;
+pub mod select;
+pub mod split;
+pub mod structure;
+pub mod tempo;
+pub mod timecode;
+pub mod transplant;
 pub mod wavtag;
+pub mod write;
 
 use log::{debug, warn};
 use serde::{Deserialize, Serialize};
-use std::{collections::HashMap, error::Error};
+use std::{
+    collections::{BTreeMap, HashMap},
+    error::Error,
+};
 use strum::EnumMessage;
+use tempo::{MusicalTime, TempoMap};
 use wavtag::{ChunkType, RiffFile};
 
 /// Reason for missing or incomplete markers in a WAV file.
@@ -159,7 +192,14 @@ pub enum ParseError {
     /// Failed to convert bytes to little-endian integer
     #[error("bytes to little endian at step: {0}")]
     BytesToLe(String),
+    /// Timecode string could not be parsed
+    #[error("invalid timecode: {0}")]
+    InvalidTimecode(String),
+    /// Line of a text marker file (labels, cue sheet, ...) could not be parsed
+    #[error("line {0}: invalid entry: {1}")]
+    InvalidLine(usize, String),
     /// Other parsing errors
+    #[error("{0}")]
     Other(String),
 }
 
@@ -175,8 +215,23 @@ pub struct WavData {
     pub path: String,
     /// Sample rate in Hz
     pub sample_rate: u32,
+    /// Length of the audio in samples (per channel), from the 'data' chunk, if known
+    #[serde(skip)]
+    pub length: Option<u32>,
     /// Vector of parsed markers and regions
     pub markers: Vec<Marker>,
+    /// Text tags from the LIST-INFO chunk (e.g. `INAM` title, `IART` artist)
+    #[serde(skip_serializing_if = "BTreeMap::is_empty")]
+    pub info: BTreeMap<String, String>,
+    /// Sampler and instrument data from the 'smpl' and 'inst' chunks, if present
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub sampler: Option<Sampler>,
+    /// ACID loop information from the 'acid' chunk, if present
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub acid: Option<wavtag::AcidChunk>,
+    /// Tempo map of the source, if it has one (e.g. a REAPER project or an 'acid' chunk)
+    #[serde(skip)]
+    pub tempo: Option<TempoMap>,
     /// Reason for incomplete parsing, if any
     #[serde(skip_serializing_if = "Option::is_none")]
     pub reason: Option<Reason>,
@@ -197,6 +252,15 @@ impl WavData {
         self.reason_text = reason.get_documentation().map(ToString::to_string);
     }
 
+    /// Derives the musical time of every marker from a tempo map.
+    ///
+    /// See [`Marker::set_musical_time`].
+    pub fn set_musical_time(&mut self, tempo: &TempoMap) {
+        for marker in &mut self.markers {
+            marker.set_musical_time(tempo);
+        }
+    }
+
     /// Clears any previously set parsing reason.
     ///
     /// Used when markers are successfully parsed or when resetting the state.
@@ -206,10 +270,91 @@ impl WavData {
     }
 }
 
+/// Sampler and instrument metadata of a WAV file.
+///
+/// Combines the 'smpl' chunk (unity note, tuning and SMPTE offset) with the key and
+/// velocity ranges, fine tuning and gain of the 'inst' chunk. Fields only found in the
+/// 'inst' chunk are None without one.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct Sampler {
+    /// MIDI note played back at the original sample rate (60 is middle C)
+    pub unity_note: u32,
+    /// Name of the unity note, e.g. `C3`
+    pub unity_note_name: String,
+    /// Tuning above the unity note in cents, from the MIDI pitch fraction
+    #[serde(serialize_with = "serialize_f64")]
+    pub pitch_fraction: f64,
+    /// MMA manufacturer code of the intended sampler (0 for any)
+    pub manufacturer: u32,
+    /// Model ID of the intended sampler (0 for any)
+    pub product: u32,
+    /// Duration of one sample in nanoseconds (0 if unset)
+    pub sample_period: u32,
+    /// SMPTE frame rate of the offset (24, 25, 29 or 30; 0 for none)
+    pub smpte_format: u32,
+    /// SMPTE offset as `hh:mm:ss:ff` (None without an SMPTE format)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub smpte_offset: Option<String>,
+    /// Fine tuning in cents (-50 to +50)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub fine_tune: Option<i8>,
+    /// Playback gain in decibels (-64 to +64)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub gain: Option<i8>,
+    /// Lowest MIDI note of the key range
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub low_note: Option<u8>,
+    /// Highest MIDI note of the key range
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub high_note: Option<u8>,
+    /// Lowest velocity of the velocity range
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub low_velocity: Option<u8>,
+    /// Highest velocity of the velocity range
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub high_velocity: Option<u8>,
+}
+
+impl Sampler {
+    /// Combines the sampler and instrument chunks, if there is either.
+    ///
+    /// Without a 'smpl' chunk, the unity note is the unshifted note of the 'inst' chunk.
+    pub fn new(
+        sampler: Option<&wavtag::SamplerChunk>,
+        instrument: Option<&wavtag::InstrumentChunk>,
+    ) -> Option<Self> {
+        if sampler.is_none() && instrument.is_none() {
+            return None;
+        }
+        let smpl = sampler.cloned().unwrap_or_else(|| wavtag::SamplerChunk {
+            midi_unity_note: instrument.map_or(60, |inst| inst.unshifted_note as u32),
+            ..Default::default()
+        });
+        Some(Sampler {
+            unity_note: smpl.midi_unity_note,
+            unity_note_name: wavtag::utils::note_num_to_name(smpl.midi_unity_note),
+            pitch_fraction: smpl.midi_pitch_fraction as f64 / 2f64.powi(32) * 100.0,
+            manufacturer: smpl.manufacturer,
+            product: smpl.product,
+            sample_period: smpl.sample_period,
+            smpte_format: smpl.smpte_format,
+            smpte_offset: (smpl.smpte_format != 0).then(|| format_smpte_offset(smpl.smpte_offset)),
+            fine_tune: instrument.map(|inst| inst.fine_tune),
+            gain: instrument.map(|inst| inst.gain),
+            low_note: instrument.map(|inst| inst.low_note),
+            high_note: instrument.map(|inst| inst.high_note),
+            low_velocity: instrument.map(|inst| inst.low_vel),
+            high_velocity: instrument.map(|inst| inst.high_vel),
+        })
+    }
+}
+
 /// Type of marker in the WAV file.
 ///
 /// Distinguishes between simple markers (single points) and regions (ranges).
-#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+/// Displayed in lowercase, as `marker` or `region`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, strum::Display)]
+#[strum(serialize_all = "lowercase")]
 pub enum MarkerType {
     /// A simple marker representing a single point in time
     Marker,
@@ -249,6 +394,15 @@ pub struct Marker {
         skip_serializing_if = "Option::is_none"
     )]
     pub duration: Option<f64>,
+    /// DERIVED: Start as bars.beats.ticks (see [`Marker::set_musical_time`])
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub musical_start: Option<MusicalTime>,
+    /// DERIVED: End as bars.beats.ticks (None for simple markers)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub musical_end: Option<MusicalTime>,
+    /// DERIVED: Length as bars.beats.ticks (None for simple markers)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub musical_length: Option<MusicalTime>,
 }
 
 /// Rounds a floating-point value to 3 decimal places.
@@ -272,7 +426,7 @@ pub fn round3(value: f64) -> f64 {
 /// Custom serializer for f64 values.
 ///
 /// Automatically rounds values to 3 decimal places during serialization.
-fn serialize_f64<S>(value: &f64, serializer: S) -> Result<S::Ok, S::Error>
+pub(crate) fn serialize_f64<S>(value: &f64, serializer: S) -> Result<S::Ok, S::Error>
 where
     S: serde::Serializer,
 {
@@ -282,7 +436,7 @@ where
 /// Custom serializer for optional f64 values.
 ///
 /// Automatically rounds values to 3 decimal places during serialization.
-fn serialize_opt_f64<S>(value: &Option<f64>, serializer: S) -> Result<S::Ok, S::Error>
+pub(crate) fn serialize_opt_f64<S>(value: &Option<f64>, serializer: S) -> Result<S::Ok, S::Error>
 where
     S: serde::Serializer,
 {
@@ -346,9 +500,31 @@ impl Marker {
             start_time,
             end_time,
             duration,
+            musical_start: None,
+            musical_end: None,
+            musical_length: None,
         }
     }
 
+    /// Derives the musical start, end and length from a tempo map.
+    ///
+    /// # Example
+    /// ```
+    /// use reaper_regions::Marker;
+    /// use reaper_regions::tempo::TempoMap;
+    ///
+    /// let mut chorus = Marker::new(1, "Chorus".to_string(), 48000 * 64, Some(48000 * 80), 48000);
+    /// chorus.set_musical_time(&TempoMap::constant(120.0, 4, 4));
+    /// assert_eq!(chorus.musical_start.unwrap().to_string(), "33.1.000");
+    /// assert_eq!(chorus.musical_end.unwrap().to_string(), "41.1.000");
+    /// assert_eq!(chorus.musical_length.unwrap().to_string(), "8.0.000");
+    /// ```
+    pub fn set_musical_time(&mut self, tempo: &TempoMap) {
+        self.musical_start = Some(tempo.position(self.start_time));
+        self.musical_end = self.end_time.map(|end| tempo.position(end));
+        self.musical_length = self.end_time.map(|end| tempo.length(self.start_time, end));
+    }
+
     /// Formats the marker as a human-readable string.
     ///
     /// # Returns
@@ -416,41 +592,90 @@ impl Marker {
 /// }
 /// ```
 pub fn parse_markers_from_file(file_path: &str) -> Result<WavData, ParseError> {
+    // Only the metadata is read; the audio stays in the file
     let file = std::fs::File::open(file_path)?;
-    let riff_file = RiffFile::read(file, file_path.to_string()).map_err(|err| {
-        let string = err.to_string();
-        if string.contains("no RIFF tag found") {
-            return ParseError::NoRiffTag;
-        }
-        if string.contains("no WAVE tag found") {
-            return ParseError::NoWaveTag;
-        }
-        err.into()
-    })?;
+    let (riff_file, data_location) =
+        RiffFile::read_metadata(file, file_path.to_string()).map_err(riff_error)?;
+
+    let length = data_location.and_then(|location| get_audio_length(&riff_file, location.size));
+    debug!("Audio length: {:?} samples", length);
+
+    parse_markers_from_riff(&riff_file, file_path, length)
+}
+
+/// Maps an error reading a RIFF file to a [`ParseError`].
+pub(crate) fn riff_error(err: std::io::Error) -> ParseError {
+    let string = err.to_string();
+    if string.contains("no RIFF tag found") {
+        return ParseError::NoRiffTag;
+    }
+    if string.contains("no WAVE tag found") {
+        return ParseError::NoWaveTag;
+    }
+    err.into()
+}
 
+/// Parses all markers from the chunks of a RIFF file.
+///
+/// # Arguments
+/// * `riff_file` - Reference to the parsed RIFF file
+/// * `file_path` - Path of the file, for [`WavData::path`]
+/// * `length` - Length of the audio in samples, if known
+///
+/// # Returns
+/// * [`ParseResult`] - Result containing parsed markers or an error
+pub(crate) fn parse_markers_from_riff(
+    riff_file: &RiffFile,
+    file_path: &str,
+    length: Option<u32>,
+) -> ParseResult {
     // Get sample rate from format chunk
-    let sample_rate = get_sample_rate(&riff_file)?;
+    let sample_rate = get_sample_rate(riff_file)?;
     debug!("Sample rate: {} Hz", sample_rate);
 
     let mut result = WavData {
         path: file_path.to_string(),
         sample_rate,
+        length,
         ..WavData::default()
     };
 
+    // Parse text tags
+    result.info = parse_info(riff_file);
+
+    // Parse ACID loop information, the default tempo source for musical time
+    match riff_file.get_acid_chunk() {
+        Some(Ok(acid)) => {
+            debug!("ACID chunk: {acid:?}");
+            result.tempo = acid.tempo_map();
+            result.acid = Some(acid);
+        }
+        Some(Err(err)) => warn!("Invalid 'acid' chunk, ignoring it: {err}"),
+        None => {}
+    }
+
     // Parse labels
-    let labels = parse_labels(&riff_file);
+    let labels = parse_labels(riff_file);
     debug!("Found {} label(s)", labels.len());
 
-    // Parse sampler loops
-    let sampler_data = parse_sampler_data(&riff_file)?;
+    // Parse sampler loops and instrument data
+    let sampler_chunk = parse_sampler_data(riff_file)?;
+    let instrument_chunk = riff_file
+        .find_chunk_by_type(ChunkType::Instrument)
+        .and_then(|chunk| {
+            wavtag::InstrumentChunk::from_chunk(chunk)
+                .inspect_err(|err| warn!("Invalid 'inst' chunk, ignoring it: {err}"))
+                .ok()
+        });
+    result.sampler = Sampler::new(sampler_chunk.as_ref(), instrument_chunk.as_ref());
+    let sampler_data = sampler_chunk.map(|chunk| chunk.sample_loops);
     if sampler_data.is_none() {
         debug!("No sample loops found.");
         result.set_reason(Reason::NoSamplerData);
     }
 
     // Parse cue points for start positions
-    let Some(cue_points) = parse_cue_points(&riff_file)? else {
+    let Some(cue_points) = parse_cue_points(riff_file)? else {
         debug!("No cue points found.");
         result.set_reason(Reason::NoCuePoints);
         return Ok(result);
@@ -481,7 +706,7 @@ struct Label {
 /// * [`ParseError::MissingFormatChunk`] - If format chunk is not found
 /// * [`ParseError::InvalidFormatChunk`] - If format chunk is too short (< 8 bytes)
 /// * [`ParseError::BytesToLe`] - If bytes cannot be converted to little-endian
-fn get_sample_rate(riff_file: &RiffFile) -> Result<u32, ParseError> {
+pub(crate) fn get_sample_rate(riff_file: &RiffFile) -> Result<u32, ParseError> {
     let format_chunk = riff_file
         .find_chunk_by_type(ChunkType::Format)
         .ok_or(ParseError::MissingFormatChunk)?;
@@ -503,6 +728,25 @@ fn get_sample_rate(riff_file: &RiffFile) -> Result<u32, ParseError> {
     Ok(sample_rate)
 }
 
+/// Calculates the audio length from the 'data' chunk size and the format's block alignment.
+///
+/// # Arguments
+/// * `riff_file` - Reference to the parsed RIFF file
+/// * `data_size` - Size of the 'data' chunk in bytes
+///
+/// # Returns
+/// * `Option<u32>` - Length in samples (per channel), or None if the format chunk is
+///   missing or has no block alignment
+pub(crate) fn get_audio_length(riff_file: &RiffFile, data_size: u32) -> Option<u32> {
+    let format_chunk = riff_file.find_chunk_by_type(ChunkType::Format)?;
+    // Offset 12-13: Block align (bytes per sample frame, all channels)
+    let block_align = u16::from_le_bytes(format_chunk.data.get(12..14)?.try_into().ok()?);
+    if block_align == 0 {
+        return None;
+    }
+    Some(data_size / block_align as u32)
+}
+
 /// Parses all labels from the file (standalone or LIST chunks).
 ///
 /// # Arguments
@@ -553,7 +797,8 @@ fn parse_labels(riff_file: &RiffFile) -> Vec<Label> {
     // If no standalone labels, parse the LIST-adtl chunk
     if !found_standalone_labels {
         debug!("=== PARSING LIST CHUNK ===");
-        if let Some(list_chunk) = riff_file.find_chunk_by_type(ChunkType::List) {
+        // A file may hold several LIST chunks (e.g. LIST-INFO before LIST-adtl)
+        for list_chunk in riff_file.find_chunks_by_type(ChunkType::List) {
             debug!("  LIST chunk size: {} bytes", list_chunk.data.len());
 
             if let Ok(list_labels) = parse_list_chunk_for_labels(list_chunk) {
@@ -566,27 +811,38 @@ fn parse_labels(riff_file: &RiffFile) -> Vec<Label> {
     labels
 }
 
-/// Parses sampler chunk data to extract sample loops.
+/// Parses the sampler chunk.
 ///
 /// # Arguments
 /// * `riff_file` - Reference to the parsed RIFF file
 ///
 /// # Returns
-/// * `Result<Option<Vec<wavtag::SampleLoop>>, ParseError>` - Sample loops or None if not found
+/// * `Result<Option<wavtag::SamplerChunk>, ParseError>` - Sampler chunk or None if not found
 ///
 /// # Errors
 /// * [`ParseError::BytesToLe`] - If sampler chunk data cannot be parsed
-fn parse_sampler_data(riff_file: &RiffFile) -> Result<Option<Vec<wavtag::SampleLoop>>, ParseError> {
+fn parse_sampler_data(riff_file: &RiffFile) -> Result<Option<wavtag::SamplerChunk>, ParseError> {
     if let Some(smpl_chunk) = riff_file.find_chunk_by_type(ChunkType::Sampler) {
         let sampler_data = wavtag::SamplerChunk::from_chunk(smpl_chunk)?;
         debug!("Found {} sample loop(s)", sampler_data.sample_loops.len());
-        Ok(Some(sampler_data.sample_loops))
+        Ok(Some(sampler_data))
     } else {
         warn!("No 'smpl' chunk found!");
         Ok(None)
     }
 }
 
+/// Formats a packed SMPTE offset (`0xhhmmssff`, signed hours) as `hh:mm:ss:ff`.
+fn format_smpte_offset(offset: u32) -> String {
+    let [frames, seconds, minutes, hours] = offset.to_le_bytes();
+    let hours = hours as i8;
+    let sign = if hours < 0 { "-" } else { "" };
+    format!(
+        "{sign}{:02}:{minutes:02}:{seconds:02}:{frames:02}",
+        hours.unsigned_abs()
+    )
+}
+
 /// Parses 'labl' subchunks from a LIST-adtl chunk.
 ///
 /// # Arguments
@@ -646,6 +902,50 @@ fn parse_list_chunk_for_labels(
     Ok(labels)
 }
 
+/// Parses text tags (e.g. `INAM`, `IART`) from LIST-INFO chunks.
+///
+/// # Arguments
+/// * `riff_file` - Reference to the parsed RIFF file
+///
+/// # Returns
+/// * `BTreeMap<String, String>` - Map of tag IDs to their values
+///
+/// # Note
+/// Each INFO subchunk holds a NUL-terminated string. Malformed subchunks
+/// end the parsing of their LIST chunk.
+fn parse_info(riff_file: &RiffFile) -> BTreeMap<String, String> {
+    let mut info = BTreeMap::new();
+
+    for list_chunk in riff_file.find_chunks_by_type(ChunkType::List) {
+        let data = &list_chunk.data;
+        if data.len() < 4 || &data[0..4] != b"INFO" {
+            continue;
+        }
+
+        let mut pos = 4;
+        while pos + 8 <= data.len() {
+            let tag = String::from_utf8_lossy(&data[pos..pos + 4]).to_string();
+            let size =
+                u32::from_le_bytes([data[pos + 4], data[pos + 5], data[pos + 6], data[pos + 7]])
+                    as usize;
+            if pos + 8 + size > data.len() {
+                warn!("INFO subchunk '{tag}' exceeds its LIST chunk, skipping");
+                break;
+            }
+
+            let value = String::from_utf8_lossy(&data[pos + 8..pos + 8 + size])
+                .trim_end_matches('\0')
+                .to_string();
+            debug!("  Found INFO tag: {tag}='{value}'");
+            info.insert(tag, value);
+
+            pos += 8 + ((size + 1) & !1);
+        }
+    }
+
+    info
+}
+
 /// Matches labels with sampler loops to create complete markers/regions.
 ///
 /// # Arguments
@@ -689,8 +989,8 @@ fn match_markers(
         markers.push(Marker::new(cue_id, name, start, end, sample_rate));
     }
 
-    // Sort markers by their start time for cleaner output
-    markers.sort_by_key(|m| m.start);
+    // Sort markers by their start time for cleaner output, and by ID for a stable order
+    markers.sort_by_key(|m| (m.start, m.id));
 
     markers
 }
@@ -0,0 +1,163 @@
+//! Regions as `smpl` loops for samplers and game audio engines.
+//!
+//! FMOD, Wwise, Unity and hardware samplers loop the sample loops of the `smpl` chunk,
+//! and most of them only use the first one. REAPER writes a loop for every region, which
+//! is also how it tells regions from markers. [`write_loops`] moves the selected regions
+//! to the front with the chosen loop type and play count, and keeps a plain forward loop
+//! for every other region, so the file still reads back with the same markers and regions.
+//! The cue points and labels are left untouched.
+//!
+//! ```rust,no_run
+//! use reaper_regions::loops::{self, LoopOptions};
+//! use reaper_regions::select::Filter;
+//!
+//! let filter = Filter::Name("Loop*".to_string());
+//! let ids = loops::write_loops_to_file("in.wav", "out.wav", &filter, &LoopOptions::default())
+//!     .unwrap();
+//! println!("Looped regions {ids:?}");
+//! ```
+
+use crate::select::Filter;
+use crate::wavtag::{ChunkType, LoopType, RiffFile, SampleLoop, SamplerChunk};
+use crate::write::copy_with_metadata;
+use crate::{Marker, MarkerType, ParseError, WavData, parse_markers_from_file};
+
+/// Loop settings of the selected regions.
+#[derive(Debug, Clone, Copy)]
+pub struct LoopOptions {
+    /// How the selected loops play
+    pub loop_type: LoopType,
+    /// Number of times to play the selected loops (0 loops until released)
+    pub play_count: u32,
+    /// MIDI note played back at the original sample rate (60 is middle C)
+    pub unity_note: u32,
+}
+
+impl Default for LoopOptions {
+    fn default() -> Self {
+        LoopOptions {
+            loop_type: LoopType::Forward,
+            play_count: 0,
+            unity_note: 60,
+        }
+    }
+}
+
+/// Matches a name against a pattern with `*` and `?` wildcards.
+///
+/// ```
+/// use reaper_regions::loops::glob_match;
+///
+/// assert!(glob_match("Loop ?*", "Loop A (dry)"));
+/// assert!(!glob_match("Loop ?*", "Loop "));
+/// ```
+pub fn glob_match(pattern: &str, name: &str) -> bool {
+    let pattern: Vec<char> = pattern.chars().collect();
+    let name: Vec<char> = name.chars().collect();
+    let (mut p, mut n) = (0, 0);
+    // Position after the last `*` and the name position it currently matches up to
+    let mut backtrack = None;
+    while n < name.len() {
+        match pattern.get(p) {
+            Some('*') => {
+                p += 1;
+                backtrack = Some((p, n));
+            }
+            Some(&c) if c == '?' || c == name[n] => {
+                p += 1;
+                n += 1;
+            }
+            _ => match backtrack {
+                Some((star_p, star_n)) => {
+                    p = star_p;
+                    n = star_n + 1;
+                    backtrack = Some((star_p, star_n + 1));
+                }
+                None => return false,
+            },
+        }
+    }
+    pattern[p..].iter().all(|&c| c == '*')
+}
+
+/// Rewrites the `smpl` chunk of a RIFF file with the selected regions as its first loops.
+///
+/// # Arguments
+/// * `riff_file` - The RIFF file to change
+/// * `data` - The markers of the file, from [`parse_markers_from_file`]
+/// * `filter` - The regions to loop; markers never are
+/// * `options` - Loop type, play count and unity note
+///
+/// # Returns
+/// * `Result<Vec<u32>, ParseError>` - IDs of the selected regions, in loop order
+///
+/// # Errors
+/// * [`ParseError::Other`] - If no region is selected
+/// * [`ParseError::Io`] - If the existing `smpl` chunk is invalid
+///
+/// # Note
+/// The manufacturer, product, pitch fraction, SMPTE offset and sampler-specific data of
+/// an existing `smpl` chunk are kept. The sample period is derived from the sample rate.
+pub fn write_loops(
+    riff_file: &mut RiffFile,
+    data: &WavData,
+    filter: &Filter,
+    options: &LoopOptions,
+) -> Result<Vec<u32>, ParseError> {
+    let (selected, others): (Vec<&Marker>, Vec<&Marker>) = data
+        .markers
+        .iter()
+        .filter(|marker| marker.r#type == MarkerType::Region)
+        .partition(|marker| filter.matches(marker));
+    if selected.is_empty() {
+        return Err(ParseError::Other("no regions match the selection".into()));
+    }
+
+    let sample_loop = |marker: &Marker, loop_type, play_count| SampleLoop {
+        id: marker.id,
+        loop_type,
+        start: marker.start,
+        end: marker.end.unwrap_or(marker.start),
+        fraction: 0,
+        play_count,
+    };
+
+    let mut chunk = match riff_file.find_chunk_by_type(ChunkType::Sampler) {
+        Some(chunk) => SamplerChunk::from_chunk(chunk)?,
+        None => SamplerChunk::default(),
+    };
+    chunk.sample_period = (1e9 / data.sample_rate as f64).round() as u32;
+    chunk.midi_unity_note = options.unity_note;
+    chunk.sample_loops = selected
+        .iter()
+        .map(|marker| sample_loop(marker, options.loop_type, options.play_count))
+        .chain(
+            others
+                .iter()
+                .map(|marker| sample_loop(marker, LoopType::Forward, 0)),
+        )
+        .collect();
+    riff_file.set_sampler_chunk(chunk);
+
+    Ok(selected.iter().map(|marker| marker.id).collect())
+}
+
+/// Writes a copy of a WAV file with the selected regions as its first `smpl` loops.
+///
+/// See [`write_loops`]. The audio is copied without loading it, through a temporary file
+/// next to the output. The input and output may be the same file, which is then updated
+/// with [`update_metadata`](crate::write::update_metadata).
+///
+/// # Returns
+/// * `Result<Vec<u32>, ParseError>` - IDs of the selected regions, in loop order
+pub fn write_loops_to_file(
+    input: &str,
+    output: &str,
+    filter: &Filter,
+    options: &LoopOptions,
+) -> Result<Vec<u32>, ParseError> {
+    let data = parse_markers_from_file(input)?;
+    copy_with_metadata(input, output, |riff_file| {
+        write_loops(riff_file, &data, filter, options)
+    })
+}
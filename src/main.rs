@@ -10,15 +10,99 @@
 //! reaper-regions audio.wav --format json
 //! reaper-regions audio.wav --format csv --no-header
 //! reaper-regions audio.wav --debug
+//! reaper-regions labels.txt --sample-rate 48000 --format json
+//! reaper-regions compare project.rpp render.wav
 //! ```
 //!
+//! ## Input Formats
+//! - WAV (default): Markers and regions embedded by REAPER
+//! - Audacity label track (`.txt`, or `--input-format audacity`), converted at `--sample-rate`
+//! - CUE sheet (`.cue`, or `--input-format cue`), with tracks as regions; the sample rate
+//!   and length come from the referenced WAV files, falling back to `--sample-rate`
+//! - REAPER project (`.rpp`, or `--input-format rpp`), with positions on the project
+//!   timeline; the sample rate comes from the project settings, falling back to `--sample-rate`
+//!
+//! ## Musical Time
+//! `--time-format bars` shows positions as `bar.beat.ticks` in the human-readable and
+//! delimited outputs, and adds them to the JSON output. The tempo comes from a constant
+//! `--bpm` and `--meter`, a `--tempo-map` CSV of `position,bpm[,meter[,shape]]` lines,
+//! or the input file (REAPER projects, or the ACID chunk of a WAV file), defaulting to
+//! 120 BPM in 4/4.
+//!
+//! ## Selecting Markers
+//! The output formats and the `compare`, `loops`, `split`, `concat`, `transplant`, `diff`
+//! and `structure` commands read only the markers and regions matching every selection option
+//! given: `--type marker|region`, `--name-glob` patterns (any may match), `--name-regex`,
+//! `--ids`, a `--from`/`--to` window (markers inside it, regions overlapping it), and
+//! `--min-duration`/`--max-duration` for regions. Positions and durations are samples or
+//! times, as for `add` below. `--where` takes an expression combining the same
+//! conditions with `and`, `or`, `not` and parentheses, for example
+//! `--where 'type:region and (name:Verse* or time:1:00..2:00) and not max-duration:5s'`.
+//! `split` only splits the selected regions but carries over all markers inside them,
+//! and `rename`, `delete` and `move` edit the selected markers and keep the others.
+//!
+//! ## Commands
+//! - `compare PROJECT RENDER`: compares the markers and regions of a REAPER project with
+//!   those of a WAV file rendered from it, and lists the ones missing from the render,
+//!   renamed or moved since it was made (`--render-start`, `--tolerance`). Exits with
+//!   status 1 if there are differences.
+//! - `loops INPUT -o OUTPUT`: writes the selected regions (all by default) as `smpl`
+//!   loops for samplers and game audio engines, with `--loop-type`, `--play-count` and
+//!   `--unity-note`. Cue points and labels are kept.
+//! - `split INPUT`: writes every region to its own WAV file in `--output-dir`, named from
+//!   `--template` (default `{index:02} - {name}.wav`) and tagged with the region name and
+//!   track number. Markers inside a region are carried over unless `--no-markers`.
+//! - `concat INPUT... -o OUTPUT`: appends WAV files of the same format, shifting their
+//!   markers to their new positions, with an optional region per file
+//!   (`--source-regions`) and `--title`.
+//! - `transplant SOURCE DESTINATION -o OUTPUT`: replaces the markers of a WAV file with
+//!   those of another, converted to its sample rate and shifted by `--offset` seconds.
+//!   Markers outside its audio are dropped or clamped (`--out-of-range`).
+//! - `add FILE NAME --start POS [--end POS]`, `rename FILE NEW_NAME`, `delete FILE` and
+//!   `move FILE --start POS [--end POS]`: edit the markers and regions of a WAV file in
+//!   place. `rename`, `delete` and `move` edit the markers matching the selection options,
+//!   of which at least one is required; with `--name-regex`, `rename` replaces the
+//!   matched text and `NEW_NAME` may use `$1` for capture groups.
+//!   Positions are samples (`48000`) or times (`1.5`, `12s`, `1:23.456`). The marker
+//!   chunks are updated in place without copying the audio when possible, and the file
+//!   is otherwise replaced atomically. `--backup` keeps a copy as `FILE.bak`, and
+//!   `--dry-run` prints the resulting markers instead.
+//! - `diff OLD NEW`: lists the markers and regions added, removed, renamed or moved
+//!   between two WAV files, matched by `--match` (`id`, `name` and `position`, in that
+//!   order by default) within `--tolerance` seconds, as text or `--json`. Exits with
+//!   status 1 if there are differences and 2 on errors.
+//! - `structure FILE`: prints the markers and regions as a tree, with regions nested in
+//!   the regions they are inside and markers under their region, followed by the
+//!   overlapping regions (nested or partial), the gaps between regions and how much of
+//!   the audio the regions cover, as text or `--json`.
+//!
 //! ## Output Formats
 //! - Human-readable (default): Easy to read in terminal
-//! - JSON: JavaScript Object Notation
+//! - JSON: JavaScript Object Notation; `--nest` puts markers and regions in a `children`
+//!   list of the region they are inside
 //! - Delimited, with or without headers, for piping into other programs.
 //!   - CSV: Comma-separated
 //!   - TSV: Tab-separated
 //!   - PSV: Pipe-separated
+//! - Marker lists for other applications:
+//!   - Audacity: Audacity label track at full precision
+//!   - CUE: CUE sheet for CD/DDP mastering, regions as tracks (`--cue-file`,
+//!     `--cue-title`, `--cue-performer`, `--cue-no-index-markers`, `--cd-rounding`)
+//!   - FFmetadata: FFmpeg `;FFMETADATA1` chapters for MP4/MKV/M4A (`--ffmetadata-markers`)
+//!   - MKV chapters: Matroska chapters XML for `mkvmerge` (`--mkv-nest-markers`, `--mkv-language`)
+//!   - Podcast chapters: Podcasting 2.0 `chapters.json` (`--podcast-markers`)
+//!   - YouTube: `0:00 Title` chapter list for video descriptions (`--youtube-intro`,
+//!     `--youtube-no-markers`)
+//!   - SRT, WebVTT, LRC: region names as subtitles or synced lyrics
+//!     (`--subtitle-no-markers`, `--subtitle-marker-duration`, `--subtitle-overlap`)
+//!   - REAPER CSV: Region/Marker Manager CSV, for importing into another project
+//!   - ReaScript: Lua script re-creating the markers in the current REAPER project
+//!     (`--reascript-offset`)
+//!   - MIDI: type-1 Standard MIDI File with marker and cue point meta-events, written
+//!     to stdout (`--midi-ppq`, `--midi-tempo`, `--midi-smpte`, `--midi-ticks-per-frame`,
+//!     `--midi-regions`)
+//!   - Avid: Avid Media Composer marker list (`--avid-user`, `--avid-track`,
+//!     `--frame-rate`, `--start-timecode`, `--avid-marker-color`, `--avid-region-color`)
 //!
 //! ## Installation
 //! ```bash
@@ -59,7 +143,16 @@
 //!     }, ...
 //!   ],
 //!   "path": "tests/fixtures/3-markers-3-regions-overlapping_stripped.wav",
-//!   "sample_rate": 48000
+//!   "sample_rate": 48000,
+//!   "sampler": {
+//!     "manufacturer": 0,
+//!     "pitch_fraction": 0.0,
+//!     "product": 0,
+//!     "sample_period": 0,
+//!     "smpte_format": 0,
+//!     "unity_note": 0,
+//!     "unity_note_name": "C-2"
+//!   }
 //! }
 //! ```
 //!
@@ -97,23 +190,73 @@
 //! REAPER is a trademark and the copyright property of [Cockos, Incorporated](https://www.cockos.com/).
 //! This library is free, open source, and MIT-licensed.
 
-use clap::{Parser, ValueEnum};
+use clap::{Args, Parser, Subcommand, ValueEnum};
 use env_logger::Builder;
-use log::{debug, error};
-use reaper_regions::{ParseResult, parse_markers_from_file, round3};
-use serde_json;
-use std::io;
+use log::{debug, error, info, warn};
+use reaper_regions::concat::{self, ConcatOptions};
+use reaper_regions::diagnostic::Severity;
+use reaper_regions::diff::{DiffOptions, MatchBy};
+use reaper_regions::edit::OutOfRange;
+use reaper_regions::formats::avid::{self, AvidColor, AvidOptions};
+use reaper_regions::formats::cue::{self, CdFrameRounding, CueOptions};
+use reaper_regions::formats::ffmetadata::{self, FfmetadataOptions, PointMarkers};
+use reaper_regions::formats::midi::{self, Division, MidiOptions, MidiRegions, SmpteFormat};
+use reaper_regions::formats::mkvchapters::{self, MkvChaptersOptions};
+use reaper_regions::formats::podcast::{self, PodcastMarkers, PodcastOptions};
+use reaper_regions::formats::reaper::{self, ReascriptOptions};
+use reaper_regions::formats::rpp::{self, RppProject};
+use reaper_regions::formats::subtitles::{self, Overlap, SubtitleOptions};
+use reaper_regions::formats::youtube::{self, YoutubeOptions};
+use reaper_regions::formats::{Export, audacity};
+use reaper_regions::loops::{self, LoopOptions};
+use reaper_regions::markers::{self, MarkerEdit, Position};
+use reaper_regions::select::Filter;
+use reaper_regions::split::{self, SplitOptions};
+use reaper_regions::tempo::{self, TempoMap};
+use reaper_regions::timecode::{FrameRate, Timecode};
+use reaper_regions::transplant::{self, TransplantOptions};
+use reaper_regions::wavtag::utils::note_num_to_name;
+use reaper_regions::wavtag::{AcidChunk, LoopType};
+use reaper_regions::write::Update;
+use reaper_regions::{MarkerType, ParseResult, Sampler, WavData, parse_markers_from_file, round3};
+use regex::Regex;
+use std::io::{self, IsTerminal, Write};
 use strum::EnumMessage;
 
 /// Extract Reaper region markers from WAV files.
 #[derive(Parser)]
-#[command(version, about, arg_required_else_help = true)]
+#[command(
+    version,
+    about,
+    arg_required_else_help = true,
+    subcommand_negates_reqs = true,
+    override_usage = "reaper-regions [OPTIONS] <FILE>\n       reaper-regions [OPTIONS] <COMMAND>"
+)]
 struct Cli {
+    #[command(subcommand)]
+    command: Option<Command>,
+
     /// Path to the input WAV file containing Reaper markers.
     ///
     /// The file must be a valid WAV file with RIFF structure and
-    /// may contain Reaper-specific chunks for markers and regions.
-    file: String,
+    /// may contain Reaper-specific chunks for markers and regions,
+    /// or a marker file in one of the other input formats.
+    #[arg(required = true)]
+    file: Option<String>,
+
+    /// Format of the input file.
+    ///
+    /// Detected from the file extension when omitted (`.txt` is read as an
+    /// Audacity label track, `.cue` as a CUE sheet, `.rpp` as a REAPER project,
+    /// anything else as WAV).
+    #[arg(short, long, value_enum)]
+    input_format: Option<InputFormat>,
+
+    /// Sample rate in Hz for input formats that store times in seconds.
+    ///
+    /// Ignored for WAV input, which carries its own sample rate.
+    #[arg(short, long, default_value_t = 48000, value_parser = clap::value_parser!(u32).range(1..))]
+    sample_rate: u32,
 
     /// Output format for displaying parsed markers.
     ///
@@ -125,14 +268,701 @@ struct Cli {
     ///
     /// When enabled, shows detailed information about chunk parsing,
     /// label matching, and any warnings encountered.
-    #[arg(short, long)]
+    #[arg(short, long, global = true)]
     debug: bool,
 
+    /// Show informational diagnostics, such as positions rounded by an export format.
+    ///
+    /// Warnings are always shown.
+    #[arg(short, long, global = true)]
+    verbose: bool,
+
     /// Omit header row in CSV/TSV/PSV output formats.
     ///
     /// Useful when piping output to other tools that don't expect headers.
     #[arg(short, long)]
     no_header: bool,
+
+    /// Nest markers and regions under the region they are inside in the JSON output.
+    ///
+    /// Each region gets a `children` list; see the `structure` command.
+    #[arg(long)]
+    nest: bool,
+
+    #[command(flatten)]
+    musical: MusicalTimeArgs,
+
+    #[command(flatten)]
+    timecode: TimecodeArgs,
+
+    #[command(flatten)]
+    avid: AvidArgs,
+
+    #[command(flatten)]
+    cue: CueArgs,
+
+    #[command(flatten)]
+    ffmetadata: FfmetadataArgs,
+
+    #[command(flatten)]
+    mkv: MkvChaptersArgs,
+
+    #[command(flatten)]
+    podcast: PodcastArgs,
+
+    #[command(flatten)]
+    youtube: YoutubeArgs,
+
+    #[command(flatten)]
+    subtitles: SubtitleArgs,
+
+    #[command(flatten)]
+    midi: MidiArgs,
+
+    #[command(flatten)]
+    reascript: ReascriptArgs,
+
+    #[command(flatten)]
+    selection: SelectionArgs,
+}
+
+/// Subcommands working on more than one file.
+#[derive(Subcommand)]
+enum Command {
+    /// Compare a REAPER project with its rendered WAV file.
+    ///
+    /// Reports markers and regions missing from the render, and those renamed or moved
+    /// since it was made. Exits with status 1 if there are differences, 2 on errors.
+    Compare(CompareArgs),
+
+    /// Write regions as 'smpl' loops for samplers and game audio engines.
+    ///
+    /// The selected regions become the first loops, with the chosen loop type and play
+    /// count. The other regions keep a plain loop, so REAPER still reads them as regions.
+    Loops(LoopsArgs),
+
+    /// Split a WAV file into one file per region.
+    ///
+    /// Each file is tagged with the region name and track number, and gets the markers
+    /// and regions inside the region.
+    Split(SplitArgs),
+
+    /// Concatenate WAV files into one, keeping their markers.
+    ///
+    /// The files must have the same format. Their markers and regions are shifted to
+    /// their new positions and renumbered.
+    Concat(ConcatArgs),
+
+    /// Copy the markers of a WAV file to another, e.g. a new render of the same mix.
+    ///
+    /// Positions are converted to the sample rate of the destination and shifted by the
+    /// offset. The markers of the destination are replaced.
+    Transplant(TransplantArgs),
+
+    /// Add a marker, or a region if it has an end, to a WAV file.
+    Add(AddArgs),
+
+    /// Rename markers and regions of a WAV file.
+    Rename(RenameArgs),
+
+    /// Delete markers and regions from a WAV file.
+    Delete(DeleteArgs),
+
+    /// Move a marker or region of a WAV file.
+    ///
+    /// A region keeps its length unless a new end is given.
+    Move(MoveArgs),
+
+    /// Compare the markers and regions of two WAV files, e.g. two renders of a mix.
+    ///
+    /// Exits with status 1 if there are differences, 2 on errors.
+    Diff(DiffArgs),
+
+    /// Show how the regions of a WAV file nest, overlap and cover the audio.
+    ///
+    /// Prints the markers and regions as a tree, with each region holding those inside
+    /// it, followed by the overlapping regions, the gaps between regions and the
+    /// fraction of the audio inside a region.
+    Structure(StructureArgs),
+}
+
+/// Arguments of the `compare` subcommand.
+#[derive(Args)]
+struct CompareArgs {
+    /// REAPER project file (`.rpp`).
+    project: String,
+
+    /// WAV file rendered from the project.
+    render: String,
+
+    /// Project position in seconds where the render starts.
+    ///
+    /// Defaults to the start of the project's render bounds (custom time range or
+    /// time selection), or 0.
+    #[arg(long)]
+    render_start: Option<f64>,
+
+    /// Positions closer than this many seconds are considered equal.
+    #[arg(long, default_value_t = 0.001)]
+    tolerance: f64,
+
+    #[command(flatten)]
+    selection: SelectionArgs,
+}
+
+/// Arguments of the `loops` subcommand.
+#[derive(Args)]
+struct LoopsArgs {
+    /// WAV file to read.
+    input: String,
+
+    /// WAV file to write; may be the input file.
+    #[arg(short, long)]
+    output: String,
+
+    /// Loop type: forward, ping-pong or reverse.
+    #[arg(long, default_value_t = LoopType::Forward)]
+    loop_type: LoopType,
+
+    /// Number of times to play the loops (0 loops until the key is released).
+    #[arg(long, default_value_t = 0)]
+    play_count: u32,
+
+    /// MIDI note played back at the original pitch (60 is middle C).
+    #[arg(long, default_value_t = 60, value_parser = clap::value_parser!(u32).range(0..=127))]
+    unity_note: u32,
+
+    #[command(flatten)]
+    selection: SelectionArgs,
+}
+
+/// Arguments of the `split` subcommand.
+#[derive(Args)]
+struct SplitArgs {
+    /// WAV file to split.
+    input: String,
+
+    /// Directory for the files.
+    #[arg(short, long, default_value = ".")]
+    output_dir: String,
+
+    /// File name template with `{index}`, `{id}` and `{name}` placeholders; numbers may
+    /// be zero-padded, e.g. `{index:02}`. Nothing is written if two regions get the same
+    /// name.
+    #[arg(long, default_value = split::DEFAULT_TEMPLATE)]
+    template: String,
+
+    /// Don't carry over the markers and regions inside each region.
+    #[arg(long)]
+    no_markers: bool,
+
+    #[command(flatten)]
+    selection: SelectionArgs,
+}
+
+/// Arguments of the `concat` subcommand.
+#[derive(Args)]
+struct ConcatArgs {
+    /// WAV files to concatenate, in order.
+    #[arg(required = true)]
+    inputs: Vec<String>,
+
+    /// WAV file to write.
+    #[arg(short, long)]
+    output: String,
+
+    /// Add a region spanning each input file, named from its title or file stem.
+    #[arg(long)]
+    source_regions: bool,
+
+    /// Title (INAM) of the output file.
+    #[arg(long)]
+    title: Option<String>,
+
+    #[command(flatten)]
+    selection: SelectionArgs,
+}
+
+/// Arguments of the `transplant` subcommand.
+#[derive(Args)]
+struct TransplantArgs {
+    /// WAV file to copy the markers from.
+    source: String,
+
+    /// WAV file to copy the markers to.
+    destination: String,
+
+    /// WAV file to write; may be the destination file.
+    #[arg(short, long)]
+    output: String,
+
+    /// Seconds added to every position, negative to move markers earlier.
+    #[arg(
+        long,
+        value_name = "SECONDS",
+        default_value_t = 0.0,
+        allow_hyphen_values = true
+    )]
+    offset: f64,
+
+    /// What happens to markers outside the destination's audio: drop or clamp.
+    #[arg(long, default_value_t = OutOfRange::Drop)]
+    out_of_range: OutOfRange,
+
+    #[command(flatten)]
+    selection: SelectionArgs,
+}
+
+/// How the marker editing subcommands write the file.
+#[derive(Args)]
+struct WriteArgs {
+    /// Keep a copy of the original file as FILE.bak.
+    #[arg(long)]
+    backup: bool,
+
+    /// Print the resulting markers instead of writing the file.
+    #[arg(long)]
+    dry_run: bool,
+}
+
+/// Arguments of the `add` subcommand.
+#[derive(Args)]
+struct AddArgs {
+    /// WAV file to edit.
+    file: String,
+
+    /// Name of the marker or region.
+    name: String,
+
+    /// Position of the marker or start of the region: samples (48000) or a time (1.5,
+    /// 12s, 1:23.456).
+    #[arg(long)]
+    start: Position,
+
+    /// End of the region; without it, a marker is added.
+    #[arg(long)]
+    end: Option<Position>,
+
+    #[command(flatten)]
+    write: WriteArgs,
+}
+
+/// Arguments of the `rename` subcommand.
+#[derive(Args)]
+struct RenameArgs {
+    /// WAV file to edit.
+    file: String,
+
+    /// New name; with `--name-regex`, replaces the matched text and may use `$1` for
+    /// groups.
+    name: String,
+
+    #[command(flatten)]
+    selection: SelectionArgs,
+
+    #[command(flatten)]
+    write: WriteArgs,
+}
+
+/// Arguments of the `delete` subcommand.
+#[derive(Args)]
+struct DeleteArgs {
+    /// WAV file to edit.
+    file: String,
+
+    #[command(flatten)]
+    selection: SelectionArgs,
+
+    #[command(flatten)]
+    write: WriteArgs,
+}
+
+/// Arguments of the `move` subcommand.
+#[derive(Args)]
+struct MoveArgs {
+    /// WAV file to edit.
+    file: String,
+
+    /// New position of the marker or start of the region: samples (48000) or a time
+    /// (1.5, 12s, 1:23.456).
+    #[arg(long)]
+    start: Position,
+
+    /// New end of the region; a marker with an end becomes a region.
+    #[arg(long)]
+    end: Option<Position>,
+
+    #[command(flatten)]
+    selection: SelectionArgs,
+
+    #[command(flatten)]
+    write: WriteArgs,
+}
+
+/// Arguments of the `diff` subcommand.
+#[derive(Args)]
+struct DiffArgs {
+    /// WAV file with the old markers.
+    old: String,
+
+    /// WAV file with the new markers.
+    new: String,
+
+    /// How markers are matched, in order: id, name and position.
+    #[arg(
+        long = "match",
+        value_name = "BY",
+        value_delimiter = ',',
+        default_value = "id,name,position"
+    )]
+    match_by: Vec<MatchBy>,
+
+    /// Positions closer than this many seconds are considered equal.
+    #[arg(long, default_value_t = 0.001)]
+    tolerance: f64,
+
+    /// Print the changes as JSON.
+    #[arg(long)]
+    json: bool,
+
+    #[command(flatten)]
+    selection: SelectionArgs,
+}
+
+/// Arguments of the `structure` subcommand.
+#[derive(Args)]
+struct StructureArgs {
+    /// WAV file to analyse.
+    file: String,
+
+    /// Print the structure as JSON.
+    #[arg(long)]
+    json: bool,
+
+    #[command(flatten)]
+    selection: SelectionArgs,
+}
+
+/// Markers and regions to read, for the output formats and the subcommands that read
+/// markers; a marker must match every option given.
+#[derive(Args)]
+#[command(next_help_heading = "Selection")]
+struct SelectionArgs {
+    /// Only markers or only regions.
+    #[arg(long = "type", value_enum, value_name = "TYPE")]
+    marker_type: Option<SelectType>,
+
+    /// Names matching this pattern, with `*` and `?` wildcards (repeatable, any may
+    /// match).
+    #[arg(long, value_name = "PATTERN")]
+    name_glob: Vec<String>,
+
+    /// Names matching this regular expression.
+    #[arg(long, value_name = "REGEX", value_parser = Regex::new)]
+    name_regex: Option<Regex>,
+
+    /// Cue point IDs, comma-separated.
+    #[arg(long = "ids", value_name = "ID", value_delimiter = ',')]
+    select_ids: Vec<u32>,
+
+    /// Markers from this position, and regions ending after it; in samples, seconds
+    /// (`12.5`, `12s`) or `[h:]m:s`.
+    #[arg(long, value_name = "POSITION")]
+    from: Option<Position>,
+
+    /// Markers before this position, and regions starting before it.
+    #[arg(long, value_name = "POSITION")]
+    to: Option<Position>,
+
+    /// Regions at least this long; excludes markers.
+    #[arg(long, value_name = "DURATION")]
+    min_duration: Option<Position>,
+
+    /// Regions at most this long; excludes markers.
+    #[arg(long, value_name = "DURATION")]
+    max_duration: Option<Position>,
+
+    /// A selection expression, e.g. `type:region and not name:"Take*"`; see the `select`
+    /// module for the syntax.
+    #[arg(long = "where", value_name = "EXPR")]
+    expression: Option<Filter>,
+}
+
+/// Marker types for `--type`.
+#[derive(Copy, Clone, PartialEq, Eq, ValueEnum)]
+enum SelectType {
+    Marker,
+    Region,
+}
+
+impl SelectionArgs {
+    /// Combines the options into one filter.
+    fn filter(&self) -> Filter {
+        let mut filter = Filter::All;
+        if let Some(marker_type) = self.marker_type {
+            filter = filter
+                & Filter::Type(match marker_type {
+                    SelectType::Marker => MarkerType::Marker,
+                    SelectType::Region => MarkerType::Region,
+                });
+        }
+        if !self.name_glob.is_empty() {
+            let names = self.name_glob.iter().cloned().map(Filter::Name);
+            filter = filter & Filter::Or(names.collect());
+        }
+        if let Some(regex) = &self.name_regex {
+            filter = filter & Filter::Regex(regex.clone());
+        }
+        if !self.select_ids.is_empty() {
+            filter = filter & Filter::Ids(self.select_ids.clone());
+        }
+        if self.from.is_some() || self.to.is_some() {
+            filter = filter
+                & Filter::Time {
+                    from: self.from,
+                    to: self.to,
+                };
+        }
+        if let Some(min) = self.min_duration {
+            filter = filter & Filter::MinDuration(min);
+        }
+        if let Some(max) = self.max_duration {
+            filter = filter & Filter::MaxDuration(max);
+        }
+        if let Some(expression) = &self.expression {
+            filter = filter & expression.clone();
+        }
+        filter
+    }
+}
+
+/// How positions are shown in the human-readable and delimited outputs.
+#[derive(Copy, Clone, PartialEq, Eq, ValueEnum)]
+enum TimeFormat {
+    /// Seconds and samples
+    Seconds,
+    /// Bars.beats.ticks, followed by seconds
+    Bars,
+}
+
+/// Options for musical (bars:beats) time.
+#[derive(Args)]
+#[command(next_help_heading = "Musical time options")]
+struct MusicalTimeArgs {
+    /// Show positions as seconds or as bars.beats.ticks.
+    ///
+    /// Musical positions are also added to the JSON output whenever a tempo is known
+    /// from the options below.
+    #[arg(long, value_enum, default_value_t = TimeFormat::Seconds)]
+    time_format: TimeFormat,
+
+    /// Constant tempo in beats per minute.
+    ///
+    /// Defaults to the tempo of the input (e.g. a REAPER project), or 120.
+    #[arg(long, value_name = "BPM", value_parser = parse_bpm)]
+    bpm: Option<f64>,
+
+    /// Constant time signature, such as `4/4` or `6/8`.
+    #[arg(long, value_parser = parse_meter)]
+    meter: Option<(u32, u32)>,
+
+    /// Tempo map CSV with `position,bpm[,meter[,shape]]` lines, positions in seconds.
+    #[arg(long, value_name = "FILE", conflicts_with_all = ["bpm", "meter"])]
+    tempo_map: Option<String>,
+}
+
+/// Timecode options for formats placed on a video timeline.
+#[derive(Args)]
+#[command(next_help_heading = "Timecode options")]
+struct TimecodeArgs {
+    /// Video frame rate: 23.976, 24, 25, 29.97, 29.97df, 30, 50, 59.94, 59.94df or 60.
+    #[arg(long, default_value_t = FrameRate::Fps25)]
+    frame_rate: FrameRate,
+
+    /// Timecode at the start of the WAV file (sample 0).
+    #[arg(long, default_value = "01:00:00:00")]
+    start_timecode: String,
+}
+
+/// Options for the Avid Media Composer marker list.
+#[derive(Args)]
+#[command(next_help_heading = "Avid options")]
+struct AvidArgs {
+    /// User name written in the first column of the marker list.
+    #[arg(long = "avid-user", default_value = "reaper-regions")]
+    user: String,
+
+    /// Track the markers are placed on (e.g. A1, V1, TC).
+    #[arg(long = "avid-track", default_value = "A1")]
+    track: String,
+
+    /// Color for simple markers: red, green, blue, cyan, magenta, yellow, black or white.
+    #[arg(long = "avid-marker-color", default_value_t = AvidColor::Red)]
+    marker_color: AvidColor,
+
+    /// Color for regions: red, green, blue, cyan, magenta, yellow, black or white.
+    #[arg(long = "avid-region-color", default_value_t = AvidColor::Green)]
+    region_color: AvidColor,
+}
+
+/// Options for the CUE sheet.
+#[derive(Args)]
+#[command(next_help_heading = "CUE sheet options")]
+struct CueArgs {
+    /// Audio file name for the FILE line (default: the input file name).
+    #[arg(long = "cue-file", id = "cue_file")]
+    file: Option<String>,
+
+    /// Album title (default: the IPRD or INAM INFO tag).
+    #[arg(long = "cue-title", id = "cue_title")]
+    title: Option<String>,
+
+    /// Album performer (default: the IART INFO tag).
+    #[arg(long = "cue-performer", id = "cue_performer")]
+    performer: Option<String>,
+
+    /// Don't write markers inside regions as index points.
+    #[arg(long = "cue-no-index-markers")]
+    no_index_markers: bool,
+
+    /// Rounding of positions to CD frames (1/75 s): nearest, down or up.
+    #[arg(long, default_value_t = CdFrameRounding::Nearest)]
+    cd_rounding: CdFrameRounding,
+}
+
+/// Options for FFmpeg metadata chapters.
+#[derive(Args)]
+#[command(next_help_heading = "FFmpeg metadata options")]
+struct FfmetadataArgs {
+    /// Simple markers: `chapter` starts a chapter lasting until the next one, `ignore` leaves them out.
+    #[arg(long = "ffmetadata-markers", default_value_t = PointMarkers::Chapter)]
+    markers: PointMarkers,
+}
+
+/// Options for Matroska chapters XML.
+#[derive(Args)]
+#[command(next_help_heading = "Matroska chapters options")]
+struct MkvChaptersArgs {
+    /// Nest markers as sub-chapters of the region containing them.
+    #[arg(long = "mkv-nest-markers")]
+    nest_markers: bool,
+
+    /// ISO 639-2 language code of the chapter names.
+    #[arg(long = "mkv-language", default_value = "eng")]
+    language: String,
+}
+
+/// Options for Podcasting 2.0 chapters.
+#[derive(Args)]
+#[command(next_help_heading = "Podcast chapters options")]
+struct PodcastArgs {
+    /// Simple markers: `chapter`, `hidden` (not in the table of contents) or `ignore`.
+    #[arg(long = "podcast-markers", id = "podcast_markers", default_value_t = PodcastMarkers::Chapter)]
+    markers: PodcastMarkers,
+}
+
+/// Options for the YouTube chapter list.
+#[derive(Args)]
+#[command(next_help_heading = "YouTube options")]
+struct YoutubeArgs {
+    /// Insert a chapter with this title at 0:00 if the first marker starts 10 s or later.
+    ///
+    /// Without it, or if the first marker starts earlier, the first chapter is moved to 0:00.
+    #[arg(long = "youtube-intro")]
+    intro: Option<String>,
+
+    /// Only use regions as chapters, not simple markers.
+    #[arg(long = "youtube-no-markers")]
+    no_markers: bool,
+}
+
+/// Options for SRT, WebVTT and LRC subtitles.
+#[derive(Args)]
+#[command(next_help_heading = "Subtitle options")]
+struct SubtitleArgs {
+    /// Only use regions as captions, not simple markers.
+    #[arg(long = "subtitle-no-markers", id = "subtitle_no_markers")]
+    no_markers: bool,
+
+    /// Maximum duration of simple marker captions in seconds.
+    ///
+    /// Markers last until the next marker, and the last one until the end of the audio.
+    #[arg(long = "subtitle-marker-duration", value_name = "SECONDS")]
+    marker_duration: Option<f64>,
+
+    /// Overlapping captions: `stack` (shown together) or `merge` (split into consecutive cues).
+    ///
+    /// LRC is always merged.
+    #[arg(long = "subtitle-overlap", default_value_t = Overlap::Stack)]
+    overlap: Overlap,
+}
+
+/// Options for the Standard MIDI File.
+#[derive(Args)]
+#[command(next_help_heading = "MIDI options")]
+struct MidiArgs {
+    /// Pulses per quarter note.
+    #[arg(long = "midi-ppq", default_value_t = midi::DEFAULT_PPQ,
+          value_parser = clap::value_parser!(u16).range(1..=0x7FFF))]
+    ppq: u16,
+
+    /// Tempo in beats per minute [default: the input's tempo, or 120].
+    #[arg(long = "midi-tempo", value_name = "BPM", value_parser = parse_midi_tempo)]
+    tempo: Option<f64>,
+
+    /// Use an SMPTE division at this frame rate (24, 25, 29.97df or 30) instead of PPQ.
+    #[arg(long = "midi-smpte", value_name = "RATE")]
+    smpte: Option<SmpteFormat>,
+
+    /// Ticks per frame for the SMPTE division.
+    #[arg(long = "midi-ticks-per-frame", default_value_t = 80)]
+    ticks_per_frame: u8,
+
+    /// Regions as `cue` point pairs or `marker` pairs.
+    #[arg(long = "midi-regions", default_value_t = MidiRegions::Cue)]
+    regions: MidiRegions,
+}
+
+/// Options for the ReaScript.
+#[derive(Args)]
+#[command(next_help_heading = "ReaScript options")]
+struct ReascriptArgs {
+    /// Seconds added to every position, e.g. where the mixdown starts in the project.
+    #[arg(
+        long = "reascript-offset",
+        value_name = "SECONDS",
+        default_value_t = 0.0,
+        allow_hyphen_values = true
+    )]
+    offset: f64,
+}
+
+/// Supported input formats for marker data.
+#[derive(Copy, Clone, PartialEq, Eq, PartialOrd, Ord, ValueEnum)]
+enum InputFormat {
+    /// WAV file with embedded markers and regions
+    Wav,
+    /// Audacity label track (`start<TAB>end<TAB>name`)
+    Audacity,
+    /// CUE sheet, with tracks as regions
+    Cue,
+    /// REAPER project, with positions on the project timeline
+    Rpp,
+}
+
+impl InputFormat {
+    /// Guesses the input format from the file extension.
+    fn detect(file: &str) -> Self {
+        let extension = std::path::Path::new(file)
+            .extension()
+            .map(|ext| ext.to_string_lossy().to_lowercase());
+        match extension.as_deref() {
+            Some("txt") => InputFormat::Audacity,
+            Some("cue") => InputFormat::Cue,
+            Some("rpp") => InputFormat::Rpp,
+            _ => InputFormat::Wav,
+        }
+    }
 }
 
 /// Supported output formats for marker data.
@@ -168,6 +998,55 @@ enum OutputFormat {
     /// Formatted for easy reading in terminal output with
     /// clear labels, indentation, and grouping.
     Human,
+    /// Avid Media Composer marker list
+    ///
+    /// Tab-delimited text for Media Composer's marker import,
+    /// with region durations converted to frames.
+    Avid,
+    /// Audacity label track
+    ///
+    /// Markers become point labels and regions range labels,
+    /// with times in seconds at full precision.
+    Audacity,
+    /// CUE sheet
+    ///
+    /// Regions become CD tracks and markers inside them index points,
+    /// with positions rounded to CD frames.
+    Cue,
+    /// FFmpeg metadata file
+    ///
+    /// `;FFMETADATA1` with a chapter per region, for use with
+    /// `ffmpeg -i input -i metadata.txt -map_metadata 1`.
+    Ffmetadata,
+    /// Matroska chapters XML
+    ///
+    /// Chapter file for `mkvmerge --chapters`, with times
+    /// computed exactly from sample positions.
+    Mkvchapters,
+    /// Podcasting 2.0 chapters JSON
+    ///
+    /// The `chapters.json` document referenced by `<podcast:chapters>`.
+    PodcastChapters,
+    /// YouTube chapter list
+    ///
+    /// `0:00 Title` lines for a video description, following
+    /// YouTube's chapter rules.
+    Youtube,
+    /// SubRip subtitles
+    Srt,
+    /// WebVTT subtitles
+    Vtt,
+    /// LRC synced lyrics
+    Lrc,
+    /// REAPER Region/Marker Manager CSV
+    ReaperCsv,
+    /// Lua ReaScript adding the markers to a REAPER project
+    Reascript,
+    /// Standard MIDI File (binary)
+    ///
+    /// Type-1 SMF with markers as Marker meta-events and
+    /// regions as Cue Point or Marker pairs.
+    Midi,
 }
 
 /// Main entry point for the Reaper Regions CLI.
@@ -191,6 +1070,8 @@ fn main() {
     // Configure logging
     let log_level = if cli.debug {
         log::LevelFilter::Debug
+    } else if cli.verbose {
+        log::LevelFilter::Info
     } else {
         log::LevelFilter::Warn
     };
@@ -201,16 +1082,617 @@ fn main() {
         .format_timestamp(None)
         .init();
 
+    if let Some(command) = cli.command {
+        match command {
+            Command::Compare(args) => compare(&args),
+            Command::Loops(args) => write_loops(&args),
+            Command::Split(args) => split_regions(&args),
+            Command::Concat(args) => concat_files(&args),
+            Command::Transplant(args) => transplant_markers(&args),
+            Command::Add(args) => {
+                let edit = MarkerEdit::Add {
+                    name: args.name,
+                    start: args.start,
+                    end: args.end,
+                };
+                edit_markers(&args.file, edit, &args.write)
+            }
+            Command::Rename(args) => {
+                let edit = MarkerEdit::Rename {
+                    filter: edit_filter(&args.selection),
+                    name: args.name,
+                };
+                edit_markers(&args.file, edit, &args.write)
+            }
+            Command::Delete(args) => {
+                let edit = MarkerEdit::Delete {
+                    filter: edit_filter(&args.selection),
+                };
+                edit_markers(&args.file, edit, &args.write)
+            }
+            Command::Move(args) => {
+                let edit = MarkerEdit::Move {
+                    filter: edit_filter(&args.selection),
+                    start: args.start,
+                    end: args.end,
+                };
+                edit_markers(&args.file, edit, &args.write)
+            }
+            Command::Diff(args) => diff_files(&args),
+            Command::Structure(args) => show_structure(&args),
+        }
+    }
+
     // Parse regions
-    let result = parse_markers_from_file(&cli.file);
+    let file = cli.file.expect("file is required without a subcommand");
+    let input_format = cli
+        .input_format
+        .unwrap_or_else(|| InputFormat::detect(&file));
+    let mut result = match input_format {
+        InputFormat::Wav => parse_markers_from_file(&file),
+        InputFormat::Audacity => audacity::import_file(&file, cli.sample_rate),
+        InputFormat::Cue => cue::import_file(&file, cli.sample_rate),
+        InputFormat::Rpp => rpp::import_file(&file, cli.sample_rate),
+    };
+
+    if let Ok(data) = &mut result {
+        data.retain_markers(&cli.selection.filter());
+        set_musical_time(data, &cli.musical);
+    }
+    let bars = cli.musical.time_format == TimeFormat::Bars;
 
     // Output in requested format
     match cli.format {
-        OutputFormat::Json => output_json(&result),
-        OutputFormat::Csv => output_delimited(&result, ',', !cli.no_header),
-        OutputFormat::Tsv => output_delimited(&result, '\t', !cli.no_header),
-        OutputFormat::Psv => output_delimited(&result, '|', !cli.no_header),
-        OutputFormat::Human => output_human(&result),
+        OutputFormat::Json => output_json(&result, cli.nest),
+        OutputFormat::Csv => output_delimited(&result, ',', !cli.no_header, bars),
+        OutputFormat::Tsv => output_delimited(&result, '\t', !cli.no_header, bars),
+        OutputFormat::Psv => output_delimited(&result, '|', !cli.no_header, bars),
+        OutputFormat::Human => output_human(&result, bars),
+        OutputFormat::Avid => {
+            let options = AvidOptions {
+                user: cli.avid.user,
+                track: cli.avid.track,
+                start_timecode: parse_timecode(&cli.timecode),
+                marker_color: cli.avid.marker_color,
+                region_color: cli.avid.region_color,
+            };
+            output_export(&result, |data| avid::export(data, &options))
+        }
+        OutputFormat::Audacity => output_export(&result, audacity::export),
+        OutputFormat::Cue => {
+            let options = CueOptions {
+                file: cli.cue.file,
+                title: cli.cue.title,
+                performer: cli.cue.performer,
+                index_markers: !cli.cue.no_index_markers,
+                rounding: cli.cue.cd_rounding,
+            };
+            output_export(&result, |data| cue::export(data, &options))
+        }
+        OutputFormat::Ffmetadata => {
+            let options = FfmetadataOptions {
+                markers: cli.ffmetadata.markers,
+            };
+            output_export(&result, |data| ffmetadata::export(data, &options))
+        }
+        OutputFormat::Mkvchapters => {
+            let options = MkvChaptersOptions {
+                nest_markers: cli.mkv.nest_markers,
+                language: cli.mkv.language,
+            };
+            output_export(&result, |data| mkvchapters::export(data, &options))
+        }
+        OutputFormat::PodcastChapters => {
+            let options = PodcastOptions {
+                markers: cli.podcast.markers,
+            };
+            output_export(&result, |data| {
+                let chapters = podcast::chapters(data, &options);
+                serde_json::to_string_pretty(&chapters).unwrap() + "\n"
+            })
+        }
+        OutputFormat::Youtube => {
+            let options = YoutubeOptions {
+                intro: cli.youtube.intro,
+                include_markers: !cli.youtube.no_markers,
+            };
+            output_export(&result, |data| youtube::export(data, &options))
+        }
+        OutputFormat::Srt | OutputFormat::Vtt | OutputFormat::Lrc => {
+            let options = SubtitleOptions {
+                include_markers: !cli.subtitles.no_markers,
+                marker_duration: cli.subtitles.marker_duration,
+                overlap: cli.subtitles.overlap,
+            };
+            let export = match cli.format {
+                OutputFormat::Srt => subtitles::srt,
+                OutputFormat::Vtt => subtitles::vtt,
+                _ => subtitles::lrc,
+            };
+            output_export(&result, |data| export(data, &options))
+        }
+        OutputFormat::ReaperCsv => output_export(&result, reaper::export_csv),
+        OutputFormat::Reascript => {
+            let options = ReascriptOptions {
+                offset: cli.reascript.offset,
+            };
+            output_export(&result, |data| reaper::export_reascript(data, &options))
+        }
+        OutputFormat::Midi => {
+            let division = match cli.midi.smpte {
+                Some(format) => Division::Smpte {
+                    format,
+                    ticks_per_frame: cli.midi.ticks_per_frame,
+                },
+                None => Division::Ppq(cli.midi.ppq),
+            };
+            output_binary(&result, |data| {
+                let tempo = cli.midi.tempo.unwrap_or_else(|| {
+                    data.tempo
+                        .as_ref()
+                        .and_then(|tempo| tempo.changes.first())
+                        .map_or(midi::DEFAULT_TEMPO, |change| change.bpm)
+                });
+                let options = MidiOptions {
+                    division,
+                    tempo,
+                    regions: cli.midi.regions,
+                };
+                midi::export(data, &options)
+            })
+        }
+    }
+}
+
+/// Summarizes sampler data, e.g. `unity note C3 (60) +0.0 cents, keys C2-B3, velocities 1-127`.
+fn describe_sampler(sampler: &Sampler) -> String {
+    let mut parts = vec![format!(
+        "unity note {} ({}) {:+.1} cents",
+        sampler.unity_note_name, sampler.unity_note, sampler.pitch_fraction
+    )];
+    if let Some(offset) = &sampler.smpte_offset {
+        parts.push(format!("SMPTE {offset} at {} fps", sampler.smpte_format));
+    }
+    if let (Some(low), Some(high)) = (sampler.low_note, sampler.high_note) {
+        parts.push(format!(
+            "keys {}-{}",
+            note_num_to_name(low as u32),
+            note_num_to_name(high as u32)
+        ));
+    }
+    if let (Some(low), Some(high)) = (sampler.low_velocity, sampler.high_velocity) {
+        parts.push(format!("velocities {low}-{high}"));
+    }
+    if let Some(fine_tune) = sampler.fine_tune {
+        parts.push(format!("fine tune {fine_tune:+} cents"));
+    }
+    if let Some(gain) = sampler.gain {
+        parts.push(format!("gain {gain:+} dB"));
+    }
+    parts.join(", ")
+}
+
+/// Summarizes an ACID chunk, e.g. `120 BPM, 4/4, 8 beats, root C3, loop, stretch`.
+fn describe_acid(acid: &AcidChunk) -> String {
+    let mut parts = vec![
+        format!("{} BPM", acid.tempo),
+        format!("{}/{}", acid.meter_numerator, acid.meter_denominator),
+        format!("{} beats", acid.beats),
+    ];
+    if acid.root_note_set {
+        parts.push(format!("root {}", note_num_to_name(acid.root_note as u32)));
+    }
+    parts.push(if acid.one_shot { "one-shot" } else { "loop" }.to_string());
+    if acid.stretch {
+        parts.push("stretch".to_string());
+    }
+    if acid.disk_based {
+        parts.push("disk-based".to_string());
+    }
+    parts.join(", ")
+}
+
+/// Parses the `--meter` argument.
+fn parse_meter(value: &str) -> Result<(u32, u32), String> {
+    tempo::parse_meter(value).ok_or_else(|| format!("invalid time signature: {value}"))
+}
+
+/// Parses the `--midi-tempo` argument.
+fn parse_midi_tempo(value: &str) -> Result<f64, String> {
+    value
+        .parse()
+        .ok()
+        .filter(|&tempo| midi::is_valid_tempo(tempo))
+        .ok_or_else(|| format!("invalid tempo: {value} (expected a positive number of BPM)"))
+}
+
+/// Parses the `--bpm` argument.
+fn parse_bpm(value: &str) -> Result<f64, String> {
+    value
+        .parse()
+        .ok()
+        .filter(|bpm: &f64| bpm.is_finite() && *bpm > 0.0)
+        .ok_or_else(|| format!("invalid tempo: {value} (expected a positive number of BPM)"))
+}
+
+/// Derives the musical time of the markers if bars:beats are requested or a tempo is given.
+///
+/// The tempo comes from `--tempo-map`, `--bpm`/`--meter` or the input, in that order.
+/// Exits with code 1 if the tempo map cannot be read.
+fn set_musical_time(data: &mut WavData, args: &MusicalTimeArgs) {
+    if args.time_format != TimeFormat::Bars
+        && args.bpm.is_none()
+        && args.meter.is_none()
+        && args.tempo_map.is_none()
+    {
+        return;
+    }
+
+    let tempo = match &args.tempo_map {
+        Some(path) => std::fs::read_to_string(path)
+            .map_err(|error| error.to_string())
+            .and_then(|text| TempoMap::parse_csv(&text).map_err(|error| error.to_string()))
+            .unwrap_or_else(|error| {
+                error!("{path}: {error}");
+                std::process::exit(1);
+            }),
+        None => {
+            // A tempo map without changes has no tempo to fall back on
+            let source = data
+                .tempo
+                .clone()
+                .filter(|tempo| !tempo.changes.is_empty())
+                .unwrap_or_default();
+            if args.bpm.is_none() && args.meter.is_none() {
+                source
+            } else {
+                let first = source
+                    .changes
+                    .first()
+                    .copied()
+                    .unwrap_or(TempoMap::default().changes[0]);
+                let (numerator, denominator) =
+                    args.meter.unwrap_or((first.numerator, first.denominator));
+                TempoMap::constant(args.bpm.unwrap_or(first.bpm), numerator, denominator)
+            }
+        }
+    };
+    debug!("Tempo map: {tempo:?}");
+    data.set_musical_time(&tempo);
+}
+
+/// Parses the `--start-timecode` argument at the `--frame-rate` frame rate.
+///
+/// Exits with code 1 if the timecode is invalid.
+fn parse_timecode(args: &TimecodeArgs) -> Timecode {
+    match Timecode::parse(&args.start_timecode, args.frame_rate) {
+        Ok(timecode) => timecode,
+        Err(error) => {
+            error!("{error}");
+            std::process::exit(1);
+        }
+    }
+}
+
+/// Outputs parsed markers using one of the library's export formats.
+///
+/// # Arguments
+/// * `result` - The parsing result containing markers
+/// * `export` - Function converting the parsed data into the output text
+///
+/// Diagnostics reported by the exporter are logged to stderr:
+/// warnings always, informational ones with `--verbose`.
+fn output_export<E: Into<Export>>(result: &ParseResult, export: impl FnOnce(&WavData) -> E) {
+    let data = match result {
+        Ok(data) => data,
+        Err(error) => {
+            error!("{error}");
+            std::process::exit(1);
+        }
+    };
+
+    let export = export(data).into();
+    for diagnostic in &export.diagnostics {
+        match diagnostic.severity {
+            Severity::Info => info!("{diagnostic}"),
+            Severity::Warning => warn!("{diagnostic}"),
+        }
+    }
+    print!("{}", export.output);
+}
+
+/// Compares a REAPER project with its render and prints the differences, then exits.
+///
+/// Exits with status 0 if they match, 1 if there are differences and 2 on errors.
+fn compare(args: &CompareArgs) -> ! {
+    let fail = |error: String| -> ! {
+        error!("{error}");
+        std::process::exit(2);
+    };
+    let project = std::fs::read_to_string(&args.project)
+        .map_err(|error| error.to_string())
+        .and_then(|text| RppProject::parse(&text).map_err(|error| error.to_string()))
+        .unwrap_or_else(|error| fail(format!("{}: {error}", args.project)));
+    let mut render = parse_markers_from_file(&args.render)
+        .unwrap_or_else(|error| fail(format!("{}: {error}", args.render)));
+
+    let bounds = project.render_bounds();
+    let start = match (args.render_start, bounds) {
+        (Some(start), _) => start,
+        (None, Some((start, _))) => start,
+        (None, None) => {
+            warn!(
+                "The project's render bounds are not a single time range, assuming the render starts at 0"
+            );
+            0.0
+        }
+    };
+    let sample_rate = render.sample_rate as f64;
+    // Stripped or truncated renders have no audio, so prefer the project's render bounds
+    let length = match bounds {
+        Some((bounds_start, end)) if end.is_finite() && args.render_start.is_none() => {
+            Some(((end - bounds_start) * sample_rate).round() as u32)
+        }
+        _ => render.length.filter(|&length| length > 0),
+    };
+    debug!("Render starts at {start} s, length {length:?} samples");
+
+    let filter = args.selection.filter();
+    let mut markers = project.to_markers(render.sample_rate, start);
+    markers.retain(|marker| filter.matches(marker));
+    render.retain_markers(&filter);
+    let tolerance = (args.tolerance * sample_rate).round() as u32;
+    let changes = rpp::compare(&markers, &render.markers, length, tolerance);
+    if changes.is_empty() {
+        println!("No differences");
+        std::process::exit(0);
+    }
+    for change in &changes {
+        println!("{change}");
+    }
+    std::process::exit(1);
+}
+
+/// Runs the `loops` subcommand: writes the selected regions as `smpl` loops.
+///
+/// Exits with status 1 on errors.
+fn write_loops(args: &LoopsArgs) -> ! {
+    let filter = args.selection.filter();
+    let options = LoopOptions {
+        loop_type: args.loop_type,
+        play_count: args.play_count,
+        unity_note: args.unity_note,
+    };
+    match loops::write_loops_to_file(&args.input, &args.output, &filter, &options) {
+        Ok(ids) => {
+            let ids: Vec<String> = ids.iter().map(ToString::to_string).collect();
+            println!(
+                "Wrote {} loop(s) to {}: region ID(s) {}",
+                ids.len(),
+                args.output,
+                ids.join(", ")
+            );
+            std::process::exit(0);
+        }
+        Err(error) => {
+            error!("{}: {error}", args.input);
+            std::process::exit(1);
+        }
+    }
+}
+
+/// Runs the `split` subcommand: writes every region to its own file.
+///
+/// Exits with status 1 on errors.
+fn split_regions(args: &SplitArgs) -> ! {
+    let options = SplitOptions {
+        template: args.template.clone(),
+        include_markers: !args.no_markers,
+        filter: args.selection.filter(),
+    };
+    match split::split(&args.input, args.output_dir.as_ref(), &options) {
+        Ok(result) => {
+            for diagnostic in &result.diagnostics {
+                warn!("{diagnostic}");
+            }
+            for file in &result.files {
+                println!(
+                    "{} (region {}, {} marker(s))",
+                    file.path.display(),
+                    file.region_id,
+                    file.markers
+                );
+            }
+            std::process::exit(0);
+        }
+        Err(error) => {
+            error!("{}: {error}", args.input);
+            std::process::exit(1);
+        }
+    }
+}
+
+/// Runs the `concat` subcommand: appends WAV files and their markers.
+///
+/// Exits with status 1 on errors.
+fn concat_files(args: &ConcatArgs) -> ! {
+    let options = ConcatOptions {
+        source_regions: args.source_regions,
+        title: args.title.clone(),
+        filter: args.selection.filter(),
+    };
+    match concat::concat(&args.inputs, &args.output, &options) {
+        Ok(result) => {
+            for diagnostic in &result.diagnostics {
+                warn!("{diagnostic}");
+            }
+            println!(
+                "Wrote {} file(s) to {}: {} samples, {} marker(s)",
+                args.inputs.len(),
+                args.output,
+                result.length,
+                result.markers.len()
+            );
+            std::process::exit(0);
+        }
+        Err(error) => {
+            error!("{error}");
+            std::process::exit(1);
+        }
+    }
+}
+
+/// Runs the `transplant` subcommand: copies markers from one WAV file to another.
+///
+/// Exits with status 1 on errors.
+fn transplant_markers(args: &TransplantArgs) -> ! {
+    let options = TransplantOptions {
+        offset: args.offset,
+        out_of_range: args.out_of_range,
+        filter: args.selection.filter(),
+    };
+    match transplant::transplant_to_file(&args.source, &args.destination, &args.output, &options) {
+        Ok(result) => {
+            for diagnostic in &result.diagnostics {
+                warn!("{diagnostic}");
+            }
+            println!(
+                "Wrote {} marker(s) to {}",
+                result.markers.len(),
+                args.output
+            );
+            std::process::exit(0);
+        }
+        Err(error) => {
+            error!("{error}");
+            std::process::exit(1);
+        }
+    }
+}
+
+/// The markers to rename, delete or move.
+///
+/// Exits with status 1 if no selection option is given, rather than editing every marker.
+fn edit_filter(selection: &SelectionArgs) -> Filter {
+    let filter = selection.filter();
+    if filter.is_all() {
+        error!("Select the markers to edit, e.g. with --ids, --name-glob or --name-regex");
+        std::process::exit(1);
+    }
+    filter
+}
+
+/// Runs the `add`, `rename`, `delete` and `move` subcommands: edits the markers of a
+/// WAV file and writes them back, or prints them with `--dry-run`.
+///
+/// Exits with status 1 on errors.
+fn edit_markers(file: &str, edit: MarkerEdit, write: &WriteArgs) -> ! {
+    let result = parse_markers_from_file(file).and_then(|mut data| {
+        let ids = data.apply_edit(&edit)?;
+        if write.dry_run {
+            return Ok((data, ids, None));
+        }
+        let options = markers::WriteOptions {
+            backup: write.backup,
+        };
+        let update = markers::write_markers(file, &data.markers, &options)?;
+        Ok((data, ids, Some(update)))
+    });
+    match result {
+        Ok((data, _, None)) => {
+            output_human(&Ok(data), false);
+            std::process::exit(0);
+        }
+        Ok((data, ids, Some(update))) => {
+            let ids: Vec<String> = ids.iter().map(ToString::to_string).collect();
+            let how = match update {
+                Update::InPlace => "in place",
+                Update::Rewritten => "by rewriting it",
+            };
+            println!(
+                "Updated {file} {how}: changed marker(s) {}, {} marker(s) in total",
+                ids.join(", "),
+                data.markers.len()
+            );
+            std::process::exit(0);
+        }
+        Err(error) => {
+            error!("{error}");
+            std::process::exit(1);
+        }
+    }
+}
+
+/// Runs the `diff` subcommand: compares the markers of two WAV files.
+///
+/// Exits with status 1 if there are differences, 2 on errors.
+fn diff_files(args: &DiffArgs) -> ! {
+    let read = |path: &str| {
+        parse_markers_from_file(path).unwrap_or_else(|error| {
+            error!("{path}: {error}");
+            std::process::exit(2);
+        })
+    };
+    let (old, new) = (read(&args.old), read(&args.new));
+    let options = DiffOptions {
+        match_by: args.match_by.clone(),
+        tolerance: args.tolerance,
+        filter: args.selection.filter(),
+    };
+    let diff = old.diff(&new, &options);
+    if args.json {
+        println!("{}", serde_json::to_string_pretty(&diff).unwrap());
+    } else if diff.is_empty() {
+        println!("No differences");
+    } else {
+        for change in &diff.changes {
+            println!("{change}");
+        }
+    }
+    std::process::exit(if diff.is_empty() { 0 } else { 1 });
+}
+
+/// Runs the `structure` subcommand: prints how the regions of a WAV file nest and
+/// overlap.
+///
+/// Exits with status 1 on errors.
+fn show_structure(args: &StructureArgs) -> ! {
+    let mut data = parse_markers_from_file(&args.file).unwrap_or_else(|error| {
+        error!("{}: {error}", args.file);
+        std::process::exit(1);
+    });
+    data.retain_markers(&args.selection.filter());
+    let structure = data.structure();
+    if args.json {
+        println!("{}", serde_json::to_string_pretty(&structure).unwrap());
+    } else {
+        println!("{structure}");
+    }
+    std::process::exit(0);
+}
+
+/// Writes a binary export to stdout, refusing to write to a terminal.
+///
+/// # Arguments
+/// * `result` - The parsing result containing markers or an error
+/// * `export` - Converts the parsed data to the output bytes
+fn output_binary(result: &ParseResult, export: impl FnOnce(&WavData) -> Vec<u8>) {
+    let data = match result {
+        Ok(data) => data,
+        Err(error) => {
+            error!("{error}");
+            std::process::exit(1);
+        }
+    };
+
+    let mut stdout = io::stdout();
+    if stdout.is_terminal() {
+        error!("Refusing to write binary output to a terminal, redirect stdout to a file");
+        std::process::exit(1);
+    }
+    if let Err(error) = stdout.write_all(&export(data)) {
+        error!("{error}");
+        std::process::exit(1);
     }
 }
 
@@ -218,6 +1700,7 @@ fn main() {
 ///
 /// # Arguments
 /// * `result` - The parsing result containing markers or an error
+/// * `nest` - Whether to nest markers and regions under their enclosing regions
 ///
 /// # Output
 /// Prints JSON to stdout with the following structure:
@@ -237,8 +1720,13 @@ fn main() {
 ///   "error": "Error message here"
 /// }
 /// ```
-fn output_json(result: &ParseResult) {
+fn output_json(result: &ParseResult, nest: bool) {
     let value = match result {
+        Ok(result) if nest => {
+            let mut value = serde_json::to_value(result).unwrap();
+            value["markers"] = serde_json::to_value(result.structure().tree).unwrap();
+            value
+        }
         Ok(result) => serde_json::to_value(result).unwrap(),
         Err(error) => serde_json::json!({
             "error": error.to_string()
@@ -267,9 +1755,12 @@ fn output_json(result: &ParseResult) {
 /// - duration: Duration in seconds (empty for markers)
 /// - sample_rate: File sample rate in Hz
 ///
+/// With `bars`, three more columns follow: musical_start, musical_end and
+/// musical_length as bars.beats.ticks.
+///
 /// # Panics
 /// Will panic if CSV writing fails, though this is rare for stdout.
-fn output_delimited(result: &ParseResult, delimiter: char, include_header: bool) {
+fn output_delimited(result: &ParseResult, delimiter: char, include_header: bool, bars: bool) {
     let result = match result {
         Ok(result) => result,
         Err(error) => {
@@ -284,7 +1775,7 @@ fn output_delimited(result: &ParseResult, delimiter: char, include_header: bool)
 
     // Header
     if include_header {
-        let _ = wtr.write_record(&[
+        let mut header = vec![
             "type",
             "id",
             "name",
@@ -294,13 +1785,17 @@ fn output_delimited(result: &ParseResult, delimiter: char, include_header: bool)
             "end_time",
             "duration",
             "sample_rate",
-        ]);
+        ];
+        if bars {
+            header.extend(["musical_start", "musical_end", "musical_length"]);
+        }
+        let _ = wtr.write_record(header);
     }
 
     // Data rows
     for marker in &result.markers {
-        let _ = wtr.write_record(&[
-            format!("{:?}", marker.r#type).to_lowercase(),
+        let mut record = vec![
+            marker.r#type.to_string(),
             marker.id.to_string(),
             marker.name.clone(),
             marker.start.to_string(),
@@ -316,7 +1811,18 @@ fn output_delimited(result: &ParseResult, delimiter: char, include_header: bool)
                 .map(|v| format!("{:.3}", round3(v)))
                 .unwrap_or_default(),
             result.sample_rate.to_string(),
-        ]);
+        ];
+        if bars {
+            record.extend(
+                [
+                    marker.musical_start,
+                    marker.musical_end,
+                    marker.musical_length,
+                ]
+                .map(|time| time.map(|t| t.to_string()).unwrap_or_default()),
+            );
+        }
+        let _ = wtr.write_record(record);
     }
 
     let _ = wtr.flush();
@@ -349,7 +1855,10 @@ fn output_delimited(result: &ParseResult, delimiter: char, include_header: bool)
 /// Marker (ID: 2): 'Chorus Start'
 ///   Position: 4.410s (44100 samples)
 /// ```
-fn output_human(result: &ParseResult) {
+///
+/// With `bars`, positions and durations are shown as bars.beats.ticks first,
+/// e.g. `Start: 3.1.000 (4.000s, 176400 samples)`.
+fn output_human(result: &ParseResult, bars: bool) {
     let data = match result {
         Ok(data) => data,
         Err(error) => {
@@ -364,6 +1873,14 @@ fn output_human(result: &ParseResult) {
 
     println!("Sample rate: {} Hz", data.sample_rate);
 
+    if let Some(sampler) = &data.sampler {
+        println!("Sampler: {}", describe_sampler(sampler));
+    }
+
+    if let Some(acid) = &data.acid {
+        println!("ACID: {}", describe_acid(acid));
+    }
+
     println!("Total markers: {}", data.markers.len());
 
     if let Some(reason) = data.reason {
@@ -377,8 +1894,8 @@ fn output_human(result: &ParseResult) {
     println!();
 
     for marker in data.markers.iter() {
-        match marker.end {
-            Some(end_sample) => {
+        match (marker.end, bars) {
+            (Some(end_sample), false) => {
                 // This is a region
                 println!("Region (ID: {}): '{}'", marker.id, marker.name);
                 println!(
@@ -396,7 +1913,7 @@ fn output_human(result: &ParseResult) {
                     marker.duration.unwrap()
                 );
             }
-            None => {
+            (None, false) => {
                 // This is a simple marker
                 println!("Marker (ID: {}): '{}'", marker.id, marker.name);
                 println!(
@@ -404,6 +1921,35 @@ fn output_human(result: &ParseResult) {
                     marker.start_time, marker.start
                 );
             }
+            (Some(end_sample), true) => {
+                println!("Region (ID: {}): '{}'", marker.id, marker.name);
+                println!(
+                    "  Start: {} ({:.3}s, {} samples)",
+                    marker.musical_start.unwrap(),
+                    marker.start_time,
+                    marker.start
+                );
+                println!(
+                    "  End: {} ({:.3}s, {} samples)",
+                    marker.musical_end.unwrap(),
+                    marker.end_time.unwrap(),
+                    end_sample
+                );
+                println!(
+                    "  Length: {} ({:.3}s)",
+                    marker.musical_length.unwrap(),
+                    marker.duration.unwrap()
+                );
+            }
+            (None, true) => {
+                println!("Marker (ID: {}): '{}'", marker.id, marker.name);
+                println!(
+                    "  Position: {} ({:.3}s, {} samples)",
+                    marker.musical_start.unwrap(),
+                    marker.start_time,
+                    marker.start
+                );
+            }
         }
 
         println!();
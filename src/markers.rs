@@ -0,0 +1,285 @@
+//! Adding, renaming, deleting and moving the markers and regions of a WAV file.
+//!
+//! A [`MarkerEdit`] changes the markers of a [`WavData`] (see [`WavData::apply_edit`]);
+//! [`write_markers`] then replaces the `cue `, `LIST`-`adtl` and `smpl` chunks of the
+//! file with ones built from the edited list. The audio and other chunks are kept. As
+//! REAPER writes these chunks after the audio, they are usually updated in place, which
+//! is quick even for large files; otherwise the file is written to a temporary file next
+//! to it and renamed over it.
+//!
+//! ```rust,no_run
+//! use reaper_regions::markers::{self, MarkerEdit, WriteOptions};
+//! use reaper_regions::parse_markers_from_file;
+//! use reaper_regions::select::Filter;
+//!
+//! let mut data = parse_markers_from_file("mix.wav").unwrap();
+//! data.apply_edit(&MarkerEdit::Rename {
+//!     filter: Filter::Name("Chrous".to_string()),
+//!     name: "Chorus".to_string(),
+//! })
+//! .unwrap();
+//! markers::write_markers("mix.wav", &data.markers, &WriteOptions { backup: true }).unwrap();
+//! ```
+
+use std::fs;
+use std::path::Path;
+use std::str::FromStr;
+
+use crate::select::Filter;
+use crate::write::{Update, replace_marker_chunks, sibling, update_metadata};
+use crate::{Marker, ParseError, WavData};
+
+/// A position in the audio, in samples or seconds.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Position {
+    /// Sample frames from the start
+    Samples(u32),
+    /// Seconds from the start
+    Seconds(f64),
+}
+
+impl Position {
+    /// The position in samples, rounded to the nearest sample.
+    pub fn to_samples(self, sample_rate: u32) -> u32 {
+        match self {
+            Position::Samples(samples) => samples,
+            Position::Seconds(seconds) => (seconds * sample_rate as f64).round() as u32,
+        }
+    }
+}
+
+impl From<u32> for Position {
+    fn from(samples: u32) -> Self {
+        Position::Samples(samples)
+    }
+}
+
+impl From<f64> for Position {
+    fn from(seconds: f64) -> Self {
+        Position::Seconds(seconds)
+    }
+}
+
+impl FromStr for Position {
+    type Err = ParseError;
+
+    /// Parses a whole number as samples, and a decimal number, a number with an `s`
+    /// suffix or `[h:]m:s` as a time.
+    ///
+    /// ```
+    /// use reaper_regions::markers::Position;
+    ///
+    /// assert_eq!("48000".parse::<Position>().unwrap(), Position::Samples(48000));
+    /// assert_eq!("12s".parse::<Position>().unwrap(), Position::Seconds(12.0));
+    /// assert_eq!("1:23.5".parse::<Position>().unwrap(), Position::Seconds(83.5));
+    /// assert_eq!("1:00:00".parse::<Position>().unwrap(), Position::Seconds(3600.0));
+    /// ```
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        let invalid = || ParseError::Other(format!("invalid position: {value}"));
+        let value = value.trim();
+        if let Ok(samples) = value.parse::<u32>() {
+            return Ok(Position::Samples(samples));
+        }
+        let seconds = value.strip_suffix('s').unwrap_or(value);
+        let mut parts = seconds.rsplit(':');
+        let seconds = parts.next().unwrap_or_default().parse::<f64>();
+        let minutes = parts.next().map(str::parse::<u32>).transpose();
+        let hours = parts.next().map(str::parse::<u32>).transpose();
+        match (hours, minutes, seconds, parts.next()) {
+            (Ok(hours), Ok(minutes), Ok(seconds), None)
+                if seconds.is_finite() && seconds >= 0.0 =>
+            {
+                let minutes = hours.unwrap_or(0) * 60 + minutes.unwrap_or(0);
+                Ok(Position::Seconds(minutes as f64 * 60.0 + seconds))
+            }
+            _ => Err(invalid()),
+        }
+    }
+}
+
+/// A change to the markers of a file.
+#[derive(Debug, Clone)]
+pub enum MarkerEdit {
+    /// Adds a marker, or a region if it has an end, with the next free ID
+    Add {
+        name: String,
+        start: Position,
+        end: Option<Position>,
+    },
+    /// Renames the selected markers; if the filter has one regular expression, alone or
+    /// combined with others by `and`, only the matched text is replaced, and `name` may
+    /// refer to capture groups as `$1`
+    Rename { filter: Filter, name: String },
+    /// Deletes the selected markers
+    Delete { filter: Filter },
+    /// Moves the selected marker; a region keeps its length unless `end` is given, and a
+    /// marker with an `end` becomes a region
+    Move {
+        filter: Filter,
+        start: Position,
+        end: Option<Position>,
+    },
+}
+
+impl WavData {
+    /// Applies an edit to the markers, keeping them sorted by position.
+    ///
+    /// # Returns
+    /// * `Result<Vec<u32>, ParseError>` - IDs of the markers added or changed
+    ///
+    /// # Errors
+    /// * [`ParseError::Other`] - If nothing is selected, more than one marker is selected
+    ///   to move, a region would end before it starts, or a position is past the end of
+    ///   the audio
+    pub fn apply_edit(&mut self, edit: &MarkerEdit) -> Result<Vec<u32>, ParseError> {
+        let sample_rate = self.sample_rate;
+        let selected = |markers: &[Marker], filter: &Filter| {
+            let ids: Vec<u32> = markers
+                .iter()
+                .filter(|marker| filter.matches(marker))
+                .map(|marker| marker.id)
+                .collect();
+            if ids.is_empty() {
+                Err(ParseError::Other("no marker matches the selection".into()))
+            } else {
+                Ok(ids)
+            }
+        };
+
+        let ids = match edit {
+            MarkerEdit::Add { name, start, end } => {
+                let (start, end) = self.range(*start, *end)?;
+                let id = self
+                    .markers
+                    .iter()
+                    .map(|marker| marker.id)
+                    .max()
+                    .unwrap_or(0)
+                    + 1;
+                self.markers
+                    .push(Marker::new(id, name.clone(), start, end, sample_rate));
+                vec![id]
+            }
+            MarkerEdit::Rename { filter, name } => {
+                let ids = selected(&self.markers, filter)?;
+                for marker in &mut self.markers {
+                    if !ids.contains(&marker.id) {
+                        continue;
+                    }
+                    #[cfg(feature = "regex")]
+                    let name = match filter.regex() {
+                        Some(regex) => regex.replace_all(&marker.name, name).into(),
+                        None => name.clone(),
+                    };
+                    #[cfg(not(feature = "regex"))]
+                    let name = name.clone();
+                    *marker = Marker::new(marker.id, name, marker.start, marker.end, sample_rate);
+                }
+                ids
+            }
+            MarkerEdit::Delete { filter } => {
+                let ids = selected(&self.markers, filter)?;
+                self.markers.retain(|marker| !ids.contains(&marker.id));
+                ids
+            }
+            MarkerEdit::Move { filter, start, end } => {
+                let ids = selected(&self.markers, filter)?;
+                if ids.len() > 1 {
+                    return Err(ParseError::Other(format!(
+                        "{} markers match the selection, move them one at a time",
+                        ids.len()
+                    )));
+                }
+                let marker = self
+                    .markers
+                    .iter()
+                    .find(|marker| marker.id == ids[0])
+                    .expect("selected marker");
+                // A region keeps its length unless given a new end
+                let end = end.or_else(|| {
+                    let samples = start.to_samples(sample_rate);
+                    marker.end.map(|old_end| {
+                        Position::Samples(
+                            samples.saturating_add(old_end.saturating_sub(marker.start)),
+                        )
+                    })
+                });
+                let (start, end) = self.range(*start, end)?;
+                let marker = self
+                    .markers
+                    .iter_mut()
+                    .find(|marker| marker.id == ids[0])
+                    .expect("selected marker");
+                *marker = Marker::new(marker.id, marker.name.clone(), start, end, sample_rate);
+                ids
+            }
+        };
+        self.markers.sort_by_key(|marker| (marker.start, marker.id));
+        Ok(ids)
+    }
+
+    /// Converts a start and optional end to samples, checking they are in order and
+    /// within the audio.
+    fn range(
+        &self,
+        start: Position,
+        end: Option<Position>,
+    ) -> Result<(u32, Option<u32>), ParseError> {
+        let start = start.to_samples(self.sample_rate);
+        let end = end.map(|end| end.to_samples(self.sample_rate));
+        if let Some(end) = end
+            && end <= start
+        {
+            return Err(ParseError::Other(format!(
+                "region would end at sample {end}, before its start at {start}"
+            )));
+        }
+        if let Some(length) = self.length
+            && end.unwrap_or(start) > length
+        {
+            return Err(ParseError::Other(format!(
+                "sample {} is past the end of the audio at {length}",
+                end.unwrap_or(start)
+            )));
+        }
+        Ok((start, end))
+    }
+}
+
+/// Options for [`write_markers`].
+#[derive(Debug, Clone, Copy, Default)]
+pub struct WriteOptions {
+    /// Keep a copy of the original file, with `.bak` appended to its name
+    pub backup: bool,
+}
+
+/// Replaces the markers of a WAV file.
+///
+/// The marker chunks are updated in place when possible, without copying the audio (see
+/// [`update_metadata`]). Otherwise the file is written to a temporary file in the same
+/// directory, which is then renamed over the original.
+///
+/// # Arguments
+/// * `path` - Path of the WAV file
+/// * `markers` - The new markers and regions
+/// * `options` - Whether to keep a backup
+///
+/// # Returns
+/// * `Result<Update, ParseError>` - Whether the file was updated in place or rewritten
+///
+/// # Errors
+/// * [`ParseError::Io`] - If the file cannot be read or written, or its `smpl` chunk is
+///   invalid
+/// * [`ParseError::Other`] - If there is no 'data' chunk
+pub fn write_markers(
+    path: &str,
+    markers: &[Marker],
+    options: &WriteOptions,
+) -> Result<Update, ParseError> {
+    if options.backup {
+        fs::copy(path, sibling(Path::new(path), ".bak"))?;
+    }
+    let ((), update) =
+        update_metadata(path, |riff_file| replace_marker_chunks(riff_file, markers))?;
+    Ok(update)
+}
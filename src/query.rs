@@ -0,0 +1,212 @@
+//! Position queries over the markers and regions of a file.
+//!
+//! [`WavData::index`] sorts the markers and regions once and builds an interval tree of
+//! the regions, so that looking up what plays at a position, what overlaps a window or
+//! what lies inside a region takes logarithmic time plus the size of the answer, even
+//! for files with thousands of markers. Positions are samples or seconds, given as
+//! [`Position`]s, `u32` samples or `f64` seconds; seconds are rounded to the nearest
+//! sample. Regions span from their start up to, but not including, their end.
+//!
+//! ```
+//! use reaper_regions::{Marker, WavData};
+//!
+//! let data = WavData {
+//!     sample_rate: 48000,
+//!     markers: vec![
+//!         Marker::new(1, "Main set".to_string(), 0, Some(48000 * 3600), 48000),
+//!         Marker::new(2, "Encore".to_string(), 48000 * 60, Some(48000 * 120), 48000),
+//!         Marker::new(3, "Applause".to_string(), 48000 * 90, None, 48000),
+//!     ],
+//!     ..Default::default()
+//! };
+//! let index = data.index();
+//! let playing: Vec<&str> = index.containing(83.456).iter().map(|m| m.name.as_str()).collect();
+//! assert_eq!(playing, ["Main set", "Encore"]);
+//! assert_eq!(index.markers_within(&data.markers[1]).len(), 1);
+//! assert_eq!(index.next(60.0).unwrap().name, "Applause");
+//! ```
+
+use std::ops::Range;
+
+use crate::markers::Position;
+use crate::{Marker, MarkerType, WavData};
+
+/// Markers and regions indexed by position, built by [`WavData::index`].
+#[derive(Debug, Clone)]
+pub struct MarkerIndex<'a> {
+    sample_rate: u32,
+    /// Markers and regions, by start and ID
+    sorted: Vec<&'a Marker>,
+    /// Regions, by start and ID, as an implicit binary tree: the root of each slice is
+    /// its middle element
+    regions: Vec<&'a Marker>,
+    /// Latest end in the subtree rooted at each element of `regions`
+    max_end: Vec<u32>,
+}
+
+impl WavData {
+    /// Indexes the markers and regions by position for queries.
+    ///
+    /// The index borrows the markers, so build it again after changing them.
+    pub fn index(&self) -> MarkerIndex<'_> {
+        let mut sorted: Vec<&Marker> = self.markers.iter().collect();
+        sorted.sort_by_key(|marker| (marker.start, marker.id));
+        let regions: Vec<&Marker> = sorted
+            .iter()
+            .copied()
+            .filter(|marker| marker.r#type == MarkerType::Region)
+            .collect();
+        let mut max_end = vec![0; regions.len()];
+        build(&regions, &mut max_end, 0, regions.len());
+        MarkerIndex {
+            sample_rate: self.sample_rate,
+            sorted,
+            regions,
+            max_end,
+        }
+    }
+}
+
+/// End of a region, or the start of a marker.
+fn end(marker: &Marker) -> u32 {
+    marker.end.unwrap_or(marker.start)
+}
+
+/// Fills in `max_end` for the subtree over `regions[lo..hi]`, returning its latest end.
+fn build(regions: &[&Marker], max_end: &mut [u32], lo: usize, hi: usize) -> u32 {
+    if lo >= hi {
+        return 0;
+    }
+    let mid = lo + (hi - lo) / 2;
+    let left = build(regions, max_end, lo, mid);
+    let right = build(regions, max_end, mid + 1, hi);
+    max_end[mid] = end(regions[mid]).max(left).max(right);
+    max_end[mid]
+}
+
+impl<'a> MarkerIndex<'a> {
+    fn samples(&self, position: impl Into<Position>) -> u32 {
+        position.into().to_samples(self.sample_rate)
+    }
+
+    /// Index in `sorted` of the first marker starting at or after `position`.
+    fn first_from(&self, position: u32) -> usize {
+        self.sorted
+            .partition_point(|marker| marker.start < position)
+    }
+
+    /// Collects the regions of the subtree over `regions[lo..hi]` overlapping
+    /// `start..end`, in order.
+    fn collect_overlapping(
+        &self,
+        lo: usize,
+        hi: usize,
+        start: u32,
+        end: u32,
+        found: &mut Vec<&'a Marker>,
+    ) {
+        if lo >= hi {
+            return;
+        }
+        let mid = lo + (hi - lo) / 2;
+        // No region of this subtree ends after the window starts
+        if self.max_end[mid] <= start {
+            return;
+        }
+        self.collect_overlapping(lo, mid, start, end, found);
+        let region = self.regions[mid];
+        // Regions to the right start later still
+        if region.start >= end {
+            return;
+        }
+        if self::end(region) > start {
+            found.push(region);
+        }
+        self.collect_overlapping(mid + 1, hi, start, end, found);
+    }
+
+    /// Sample rate of the file, for positions in seconds.
+    pub fn sample_rate(&self) -> u32 {
+        self.sample_rate
+    }
+
+    /// All markers and regions, by start and ID.
+    pub fn markers(&self) -> &[&'a Marker] {
+        &self.sorted
+    }
+
+    /// The markers and regions starting at a position, by ID.
+    pub fn at(&self, position: impl Into<Position>) -> &[&'a Marker] {
+        let position = self.samples(position);
+        let from = self.first_from(position);
+        let to = from + self.sorted[from..].partition_point(|marker| marker.start == position);
+        &self.sorted[from..to]
+    }
+
+    /// The regions playing at a position, by start and ID, so that enclosing regions come
+    /// before the regions nested in them.
+    pub fn containing(&self, position: impl Into<Position>) -> Vec<&'a Marker> {
+        let position = self.samples(position);
+        let mut found = Vec::new();
+        self.collect_overlapping(
+            0,
+            self.regions.len(),
+            position,
+            position.saturating_add(1),
+            &mut found,
+        );
+        found
+    }
+
+    /// The markers within a range and the regions overlapping it, by start and ID.
+    pub fn overlapping<P: Into<Position>>(&self, range: Range<P>) -> Vec<&'a Marker> {
+        let (start, end) = (self.samples(range.start), self.samples(range.end));
+        let mut found = Vec::new();
+        self.collect_overlapping(0, self.regions.len(), start, end, &mut found);
+        let from = self.first_from(start);
+        let to = self.first_from(end).max(from);
+        found.extend(
+            self.sorted[from..to]
+                .iter()
+                .filter(|marker| marker.r#type == MarkerType::Marker),
+        );
+        found.sort_by_key(|marker| (marker.start, marker.id));
+        found
+    }
+
+    /// The markers and regions entirely within a range, by start and ID; markers at the
+    /// end of the range are not within it.
+    pub fn within<P: Into<Position>>(&self, range: Range<P>) -> Vec<&'a Marker> {
+        let (start, end) = (self.samples(range.start), self.samples(range.end));
+        let from = self.first_from(start);
+        let to = self.first_from(end).max(from);
+        self.sorted[from..to]
+            .iter()
+            .copied()
+            .filter(|marker| self::end(marker) <= end)
+            .collect()
+    }
+
+    /// The markers and regions within a region, other than the region itself.
+    pub fn markers_within(&self, region: &Marker) -> Vec<&'a Marker> {
+        let mut found = self.within(region.start..end(region));
+        found.retain(|marker| marker.id != region.id);
+        found
+    }
+
+    /// The first marker or region starting after a position.
+    pub fn next(&self, position: impl Into<Position>) -> Option<&'a Marker> {
+        let position = self.samples(position);
+        let after = self
+            .sorted
+            .partition_point(|marker| marker.start <= position);
+        self.sorted.get(after).copied()
+    }
+
+    /// The last marker or region starting before a position.
+    pub fn prev(&self, position: impl Into<Position>) -> Option<&'a Marker> {
+        let position = self.samples(position);
+        let before = self.first_from(position);
+        before.checked_sub(1).map(|index| self.sorted[index])
+    }
+}
@@ -0,0 +1,381 @@
+//! Selecting markers and regions by type, name, ID, position and duration.
+//!
+//! A [`Filter`] matches markers on one property, and filters combine with `&`, `|` and
+//! `!` (or [`Filter::And`], [`Filter::Or`] and [`Filter::Not`]). [`WavData::retain_markers`]
+//! keeps the matching markers of a file, and the export, loop, split, concatenation and
+//! transplant operations take a filter in their options.
+//!
+//! Filters can also be parsed from text, where terms are `key:value` pairs combined with
+//! `and`, `or`, `not` and parentheses; terms next to each other must all match:
+//!
+//! | Term                 | Matches                                                  |
+//! |----------------------|----------------------------------------------------------|
+//! | `type:region`        | Regions, or markers with `type:marker`                   |
+//! | `name:Verse*`        | Names matching a pattern with `*` and `?` wildcards      |
+//! | `regex:^V\d`         | Names matching a regular expression (`regex` feature)    |
+//! | `id:1,3`             | Cue point IDs                                            |
+//! | `time:1:00..2:00`    | Markers within the window, regions overlapping it        |
+//! | `min-duration:5s`    | Regions at least this long                               |
+//! | `max-duration:5s`    | Regions at most this long                                |
+//!
+//! Values with spaces or parentheses are quoted, e.g. `name:"Take (alt)"`. Positions and
+//! durations are samples or times, as in [`Position`], and either end of a window may be
+//! left out.
+//!
+//! ```
+//! use reaper_regions::select::Filter;
+//! use reaper_regions::{Marker, MarkerType};
+//!
+//! let filter: Filter = "type:region and not (name:Take* or max-duration:1s)".parse().unwrap();
+//! let verse = Marker::new(1, "Verse".to_string(), 0, Some(96000), 48000);
+//! let take = Marker::new(2, "Take 2".to_string(), 0, Some(96000), 48000);
+//! assert!(filter.matches(&verse));
+//! assert!(!filter.matches(&take));
+//!
+//! // The same filter, built in code
+//! let filter = Filter::Type(MarkerType::Region)
+//!     & !(Filter::Name("Take*".to_string()) | Filter::MaxDuration("1s".parse().unwrap()));
+//! assert!(filter.matches(&verse));
+//! ```
+
+use std::cmp::Ordering;
+use std::ops;
+use std::str::FromStr;
+
+#[cfg(feature = "regex")]
+use regex::Regex;
+
+use crate::loops::glob_match;
+use crate::markers::Position;
+use crate::{Marker, MarkerType, ParseError, WavData};
+
+/// Selects markers and regions.
+#[derive(Debug, Clone, Default)]
+pub enum Filter {
+    /// Every marker
+    #[default]
+    All,
+    /// Markers or regions
+    Type(MarkerType),
+    /// Names matching a pattern, where `*` matches any text and `?` any single character
+    Name(String),
+    /// Names matching a regular expression
+    #[cfg(feature = "regex")]
+    Regex(Regex),
+    /// Cue point IDs
+    Ids(Vec<u32>),
+    /// Markers from `from` up to (not including) `to`, and regions overlapping that window
+    Time {
+        from: Option<Position>,
+        to: Option<Position>,
+    },
+    /// Regions at least this long; markers never match
+    MinDuration(Position),
+    /// Regions at most this long; markers never match
+    MaxDuration(Position),
+    /// Markers all filters match
+    And(Vec<Filter>),
+    /// Markers any filter matches
+    Or(Vec<Filter>),
+    /// Markers the filter does not match
+    Not(Box<Filter>),
+}
+
+/// Compares a position given in samples and seconds with a [`Position`], in its unit.
+fn compare(samples: u32, seconds: f64, position: Position) -> Ordering {
+    match position {
+        Position::Samples(position) => samples.cmp(&position),
+        Position::Seconds(position) => seconds.total_cmp(&position),
+    }
+}
+
+impl Filter {
+    /// Whether the filter selects a marker.
+    pub fn matches(&self, marker: &Marker) -> bool {
+        let start = (marker.start, marker.start_time);
+        let end = marker.end.zip(marker.end_time).unwrap_or(start);
+        let duration = marker
+            .end
+            .zip(marker.duration)
+            .map(|(end, seconds)| (end - marker.start, seconds));
+        match self {
+            Filter::All => true,
+            Filter::Type(marker_type) => marker.r#type == *marker_type,
+            Filter::Name(pattern) => glob_match(pattern, &marker.name),
+            #[cfg(feature = "regex")]
+            Filter::Regex(regex) => regex.is_match(&marker.name),
+            Filter::Ids(ids) => ids.contains(&marker.id),
+            Filter::Time { from, to } => {
+                let after_from = from.is_none_or(|from| match marker.r#type {
+                    MarkerType::Marker => compare(start.0, start.1, from).is_ge(),
+                    MarkerType::Region => compare(end.0, end.1, from).is_gt(),
+                });
+                let before_to = to.is_none_or(|to| compare(start.0, start.1, to).is_lt());
+                after_from && before_to
+            }
+            Filter::MinDuration(min) => {
+                duration.is_some_and(|(samples, seconds)| compare(samples, seconds, *min).is_ge())
+            }
+            Filter::MaxDuration(max) => {
+                duration.is_some_and(|(samples, seconds)| compare(samples, seconds, *max).is_le())
+            }
+            Filter::And(filters) => filters.iter().all(|filter| filter.matches(marker)),
+            Filter::Or(filters) => filters.iter().any(|filter| filter.matches(marker)),
+            Filter::Not(filter) => !filter.matches(marker),
+        }
+    }
+
+    /// Whether the filter selects every marker.
+    pub fn is_all(&self) -> bool {
+        matches!(self, Filter::All)
+    }
+
+    /// The regular expression of a [`Filter::Regex`], or of the only one among the
+    /// filters of a [`Filter::And`], e.g. for replacing the matched text of names.
+    #[cfg(feature = "regex")]
+    pub fn regex(&self) -> Option<&Regex> {
+        match self {
+            Filter::Regex(regex) => Some(regex),
+            Filter::And(filters) => {
+                let mut regexes = filters.iter().filter_map(|filter| match filter {
+                    Filter::Regex(regex) => Some(regex),
+                    _ => None,
+                });
+                match (regexes.next(), regexes.next()) {
+                    (Some(regex), None) => Some(regex),
+                    _ => None,
+                }
+            }
+            _ => None,
+        }
+    }
+}
+
+impl ops::BitAnd for Filter {
+    type Output = Filter;
+
+    fn bitand(self, other: Filter) -> Filter {
+        match (self, other) {
+            (Filter::All, filter) | (filter, Filter::All) => filter,
+            (Filter::And(mut filters), Filter::And(others)) => {
+                filters.extend(others);
+                Filter::And(filters)
+            }
+            (Filter::And(mut filters), filter) => {
+                filters.push(filter);
+                Filter::And(filters)
+            }
+            (filter, other) => Filter::And(vec![filter, other]),
+        }
+    }
+}
+
+impl ops::BitOr for Filter {
+    type Output = Filter;
+
+    fn bitor(self, other: Filter) -> Filter {
+        match (self, other) {
+            (Filter::Or(mut filters), Filter::Or(others)) => {
+                filters.extend(others);
+                Filter::Or(filters)
+            }
+            (Filter::Or(mut filters), filter) => {
+                filters.push(filter);
+                Filter::Or(filters)
+            }
+            (filter, other) => Filter::Or(vec![filter, other]),
+        }
+    }
+}
+
+impl ops::Not for Filter {
+    type Output = Filter;
+
+    fn not(self) -> Filter {
+        match self {
+            Filter::Not(filter) => *filter,
+            filter => Filter::Not(Box::new(filter)),
+        }
+    }
+}
+
+impl WavData {
+    /// Keeps only the markers and regions a filter selects.
+    pub fn retain_markers(&mut self, filter: &Filter) {
+        self.markers.retain(|marker| filter.matches(marker));
+    }
+}
+
+/// A token of a filter expression.
+#[derive(Debug, PartialEq)]
+enum Token {
+    Open,
+    Close,
+    /// A keyword or term, and whether any of it was quoted
+    Word(String, bool),
+}
+
+fn tokenize(text: &str) -> Result<Vec<Token>, String> {
+    let mut tokens = Vec::new();
+    let mut chars = text.chars().peekable();
+    while let Some(&c) = chars.peek() {
+        match c {
+            c if c.is_whitespace() => {
+                chars.next();
+            }
+            '(' | ')' => {
+                chars.next();
+                tokens.push(if c == '(' { Token::Open } else { Token::Close });
+            }
+            _ => {
+                let mut word = String::new();
+                let mut quoted = false;
+                while let Some(&c) = chars.peek() {
+                    if c.is_whitespace() || c == '(' || c == ')' {
+                        break;
+                    }
+                    chars.next();
+                    if c != '"' {
+                        word.push(c);
+                        continue;
+                    }
+                    quoted = true;
+                    loop {
+                        match chars.next() {
+                            Some('"') => break,
+                            Some('\\') => word.extend(chars.next()),
+                            Some(c) => word.push(c),
+                            None => return Err("unclosed quote".into()),
+                        }
+                    }
+                }
+                tokens.push(Token::Word(word, quoted));
+            }
+        }
+    }
+    Ok(tokens)
+}
+
+/// A recursive descent parser of filter expressions.
+struct Parser {
+    tokens: Vec<Token>,
+    position: usize,
+}
+
+impl Parser {
+    fn peek_keyword(&self, keyword: &str) -> bool {
+        matches!(self.tokens.get(self.position),
+            Some(Token::Word(word, false)) if word.eq_ignore_ascii_case(keyword))
+    }
+
+    fn or(&mut self) -> Result<Filter, String> {
+        let mut filter = self.and()?;
+        while self.peek_keyword("or") {
+            self.position += 1;
+            filter = filter | self.and()?;
+        }
+        Ok(filter)
+    }
+
+    fn and(&mut self) -> Result<Filter, String> {
+        let mut filter = self.not()?;
+        loop {
+            if self.peek_keyword("and") {
+                self.position += 1;
+            } else if self.peek_keyword("or")
+                || matches!(self.tokens.get(self.position), None | Some(Token::Close))
+            {
+                return Ok(filter);
+            }
+            filter = filter & self.not()?;
+        }
+    }
+
+    fn not(&mut self) -> Result<Filter, String> {
+        if self.peek_keyword("not") {
+            self.position += 1;
+            return Ok(!self.not()?);
+        }
+        let token = self.tokens.get(self.position);
+        self.position += 1;
+        match token {
+            Some(Token::Open) => {
+                let filter = self.or()?;
+                match self.tokens.get(self.position) {
+                    Some(Token::Close) => {
+                        self.position += 1;
+                        Ok(filter)
+                    }
+                    _ => Err("missing ')'".into()),
+                }
+            }
+            Some(Token::Word(word, _)) => term(word),
+            Some(Token::Close) => Err("unexpected ')'".into()),
+            None => Err("unexpected end".into()),
+        }
+    }
+}
+
+/// Parses a `key:value` term.
+fn term(word: &str) -> Result<Filter, String> {
+    let (key, value) = word
+        .split_once(':')
+        .ok_or_else(|| format!("expected key:value, got '{word}'"))?;
+    let position = |value: &str| Position::from_str(value).map_err(|error| error.to_string());
+    match key.to_ascii_lowercase().as_str() {
+        "type" => match value.to_ascii_lowercase().as_str() {
+            "marker" => Ok(Filter::Type(MarkerType::Marker)),
+            "region" => Ok(Filter::Type(MarkerType::Region)),
+            _ => Err(format!("unknown type '{value}', expected marker or region")),
+        },
+        "name" => Ok(Filter::Name(value.to_string())),
+        #[cfg(feature = "regex")]
+        "regex" => Regex::new(value)
+            .map(Filter::Regex)
+            .map_err(|error| error.to_string()),
+        #[cfg(not(feature = "regex"))]
+        "regex" => Err("regular expressions need the regex feature".into()),
+        "id" => value
+            .split(',')
+            .map(|id| id.trim().parse::<u32>())
+            .collect::<Result<Vec<_>, _>>()
+            .map(Filter::Ids)
+            .map_err(|_| format!("invalid IDs '{value}'")),
+        "time" => {
+            let (from, to) = value
+                .split_once("..")
+                .ok_or_else(|| format!("expected FROM..TO, got '{value}'"))?;
+            let bound = |value: &str| (!value.is_empty()).then(|| position(value)).transpose();
+            Ok(Filter::Time {
+                from: bound(from)?,
+                to: bound(to)?,
+            })
+        }
+        "min-duration" => position(value).map(Filter::MinDuration),
+        "max-duration" => position(value).map(Filter::MaxDuration),
+        _ => Err(format!("unknown key '{key}'")),
+    }
+}
+
+impl FromStr for Filter {
+    type Err = ParseError;
+
+    /// Parses a filter expression; an empty one selects everything.
+    fn from_str(text: &str) -> Result<Self, Self::Err> {
+        let invalid =
+            |message: String| ParseError::Other(format!("invalid selection '{text}': {message}"));
+        let tokens = tokenize(text).map_err(invalid)?;
+        if tokens.is_empty() {
+            return Ok(Filter::All);
+        }
+        let mut parser = Parser {
+            tokens,
+            position: 0,
+        };
+        let filter = parser.or().map_err(invalid)?;
+        if parser.position < parser.tokens.len() {
+            return Err(invalid("unexpected ')'".into()));
+        }
+        Ok(filter)
+    }
+}
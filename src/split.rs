@@ -0,0 +1,288 @@
+//! Splitting a WAV file into one file per region.
+//!
+//! [`split`] copies the audio of every region to its own WAV file, streaming it from the
+//! `data` chunk rather than loading the whole file. Files are named from a template (see
+//! [`file_name`]) and tagged with the region name as title (`INAM`) and its position as
+//! track number (`ITRK`). The other `INFO` tags are copied, and the source title becomes
+//! the album (`IPRD`) if there is none. Markers and regions inside a region are carried
+//! over, shifted to the start of its file.
+//!
+//! ```rust,no_run
+//! use reaper_regions::split::{self, SplitOptions};
+//!
+//! let result = split::split("concert.wav", "songs".as_ref(), &SplitOptions::default()).unwrap();
+//! for file in &result.files {
+//!     println!("{}", file.path.display());
+//! }
+//! ```
+
+use std::collections::HashMap;
+use std::fs::{self, File};
+use std::io::{Read, Seek, SeekFrom};
+use std::path::{Path, PathBuf};
+
+use log::debug;
+use serde::Serialize;
+
+use crate::diagnostic::Diagnostic;
+use crate::query::MarkerIndex;
+use crate::select::Filter;
+use crate::write::{AudioSource, write_wav};
+use crate::{Marker, MarkerType, ParseError};
+
+/// Default file name template.
+pub const DEFAULT_TEMPLATE: &str = "{index:02} - {name}.wav";
+
+/// Options for [`split`].
+#[derive(Debug, Clone)]
+pub struct SplitOptions {
+    /// File name template (see [`file_name`])
+    pub template: String,
+    /// Carry over the markers and regions inside each region
+    pub include_markers: bool,
+    /// Regions to split; the markers carried over are not filtered
+    pub filter: Filter,
+}
+
+impl Default for SplitOptions {
+    fn default() -> Self {
+        SplitOptions {
+            template: DEFAULT_TEMPLATE.to_string(),
+            include_markers: true,
+            filter: Filter::All,
+        }
+    }
+}
+
+/// A file written by [`split`].
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct SplitFile {
+    /// Path of the file
+    pub path: PathBuf,
+    /// ID of the region
+    pub region_id: u32,
+    /// Name of the region
+    pub name: String,
+    /// Length of the file in samples
+    pub length: u32,
+    /// Number of markers and regions carried over
+    pub markers: usize,
+}
+
+/// The files written by [`split`], and any issues found.
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct Split {
+    /// Files written, in region order
+    pub files: Vec<SplitFile>,
+    /// Regions skipped or shortened
+    pub diagnostics: Vec<Diagnostic>,
+}
+
+/// Replaces characters that are invalid in file names on common filesystems.
+///
+/// Path separators, `<>:"|?*` and control characters become `_`, and leading or trailing
+/// spaces and dots are removed.
+///
+/// ```
+/// use reaper_regions::split::sanitize;
+///
+/// assert_eq!(sanitize("AC/DC: Live?. "), "AC_DC_ Live_");
+/// ```
+pub fn sanitize(name: &str) -> String {
+    name.chars()
+        .map(|c| match c {
+            '/' | '\\' | '<' | '>' | ':' | '"' | '|' | '?' | '*' => '_',
+            c if c.is_control() => '_',
+            c => c,
+        })
+        .collect::<String>()
+        .trim_matches([' ', '.'])
+        .to_string()
+}
+
+/// Expands a file name template for a region.
+///
+/// Placeholders are `{index}` (1-based position of the region), `{id}` and `{name}`
+/// (sanitized with [`sanitize`], or `Region {id}` if empty). Numbers may be zero-padded
+/// with a width, e.g. `{index:02}`.
+///
+/// # Errors
+/// * [`ParseError::Other`] - If the template has an unknown or unclosed placeholder
+///
+/// ```
+/// use reaper_regions::Marker;
+/// use reaper_regions::split::file_name;
+///
+/// let region = Marker::new(7, "Intro".to_string(), 0, Some(48000), 48000);
+/// assert_eq!(file_name("{index:02} - {name}.wav", 3, &region).unwrap(), "03 - Intro.wav");
+/// ```
+pub fn file_name(template: &str, index: usize, region: &Marker) -> Result<String, ParseError> {
+    let invalid = |message: &str| ParseError::Other(format!("{message} in template: {template}"));
+    let mut name = String::new();
+    let mut rest = template;
+    while let Some(open) = rest.find('{') {
+        name.push_str(&rest[..open]);
+        let close = rest[open..]
+            .find('}')
+            .ok_or_else(|| invalid("unclosed placeholder"))?;
+        let placeholder = &rest[open + 1..open + close];
+        let (key, width) = match placeholder.split_once(':') {
+            Some((key, width)) => (
+                key,
+                width
+                    .parse::<usize>()
+                    .map_err(|_| invalid("invalid width"))?,
+            ),
+            None => (placeholder, 0),
+        };
+        let value = match key {
+            "index" => format!("{index:0width$}"),
+            "id" => format!("{:0width$}", region.id),
+            "name" => match sanitize(&region.name) {
+                name if name.is_empty() => format!("Region {}", region.id),
+                name => format!("{name:width$}"),
+            },
+            _ => return Err(invalid(&format!("unknown placeholder {{{key}}}"))),
+        };
+        name.push_str(&value);
+        rest = &rest[open + close + 1..];
+    }
+    name.push_str(rest);
+    Ok(name)
+}
+
+/// Writes every region of a WAV file to its own file.
+///
+/// # Arguments
+/// * `input` - Path of the WAV file
+/// * `output_dir` - Directory for the files, created if missing
+/// * `options` - File name template and whether to carry over markers
+///
+/// # Returns
+/// * `Result<Split, ParseError>` - The files written, and diagnostics for regions that
+///   were skipped or cut at the end of the audio
+///
+/// # Errors
+/// * [`ParseError::Io`] - If a file cannot be read or written
+/// * [`ParseError::MissingFormatChunk`] - If the file has no format chunk
+/// * [`ParseError::Other`] - If there is no 'data' chunk, the audio is compressed, there
+///   are no regions, the template is invalid or names two regions' files the same
+pub fn split(input: &str, output_dir: &Path, options: &SplitOptions) -> Result<Split, ParseError> {
+    let source = AudioSource::open(input, "split")?;
+    let (location, block_align, length) = (source.location, source.block_align, source.length);
+    let format = source.format_chunk();
+    let data = source.data;
+    let marker_index = data.index();
+
+    let mut regions: Vec<&Marker> = data
+        .markers
+        .iter()
+        .filter(|marker| marker.r#type == MarkerType::Region && options.filter.matches(marker))
+        .collect();
+    if regions.is_empty() {
+        return Err(ParseError::Other("no regions to split".into()));
+    }
+    regions.sort_by_key(|region| (region.start, region.id));
+
+    // Name every file first, so that nothing is written if two regions share a name
+    let mut paths: Vec<PathBuf> = Vec::with_capacity(regions.len());
+    let mut seen: HashMap<PathBuf, &Marker> = HashMap::new();
+    for (index, region) in regions.iter().enumerate() {
+        let path = output_dir.join(file_name(&options.template, index + 1, region)?);
+        if let Some(other) = seen.insert(path.clone(), region) {
+            return Err(ParseError::Other(format!(
+                "'{}' and '{}' would both be written to {}, use {{index}} or {{id}} in the template",
+                other.name,
+                region.name,
+                path.display()
+            )));
+        }
+        paths.push(path);
+    }
+
+    fs::create_dir_all(output_dir)?;
+    let mut audio = File::open(input)?;
+    let mut result = Split::default();
+    for (index, (region, path)) in regions.iter().zip(paths).enumerate() {
+        let index = index + 1;
+        let start = region.start;
+        let end = region.end.unwrap_or(start);
+        if start >= length {
+            result.diagnostics.push(Diagnostic::warning(
+                Some(region.id),
+                format!(
+                    "'{}' starts after the end of the audio, skipped",
+                    region.name
+                ),
+            ));
+            continue;
+        }
+        if end > length {
+            result.diagnostics.push(Diagnostic::warning(
+                Some(region.id),
+                format!("'{}' ends after the end of the audio, cut", region.name),
+            ));
+        }
+        let end = end.min(length);
+        if end == start {
+            result.diagnostics.push(Diagnostic::warning(
+                Some(region.id),
+                format!("'{}' is empty, skipped", region.name),
+            ));
+            continue;
+        }
+
+        let markers = if options.include_markers {
+            markers_within(&marker_index, region.id, start, end)
+        } else {
+            Vec::new()
+        };
+        let mut info = data.info.clone();
+        if let Some(title) = info.remove("INAM") {
+            info.entry("IPRD".to_string()).or_insert(title);
+        }
+        info.insert("INAM".to_string(), region.name.clone());
+        info.insert("ITRK".to_string(), index.to_string());
+
+        debug!("Writing region {} to {}", region.id, path.display());
+        audio.seek(SeekFrom::Start(
+            location.offset + start as u64 * block_align,
+        ))?;
+        let size = (end - start) as u64 * block_align;
+        write_wav(
+            &mut File::create(&path)?,
+            &format,
+            &info,
+            &markers,
+            (&mut audio).take(size),
+            size as u32,
+        )?;
+
+        result.files.push(SplitFile {
+            path,
+            region_id: region.id,
+            name: region.name.clone(),
+            length: end - start,
+            markers: markers.len(),
+        });
+    }
+    Ok(result)
+}
+
+/// The markers and regions within a region, shifted to its start.
+fn markers_within(index: &MarkerIndex, region_id: u32, start: u32, end: u32) -> Vec<Marker> {
+    index
+        .within(start..end)
+        .into_iter()
+        .filter(|marker| marker.id != region_id)
+        .map(|marker| {
+            Marker::new(
+                marker.id,
+                marker.name.clone(),
+                marker.start - start,
+                marker.end.map(|marker_end| marker_end - start),
+                index.sample_rate(),
+            )
+        })
+        .collect()
+}
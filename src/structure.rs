@@ -0,0 +1,376 @@
+//! The structure of the regions of a file: nesting, overlaps, gaps and coverage.
+//!
+//! [`WavData::structure`] arranges the markers and regions in a tree, where each region
+//! holds the regions and markers inside it (see [`Node`]), and lists the regions that
+//! overlap, the stretches of audio outside every region and how much of the audio the
+//! regions cover. This shows, before exporting to a format that forbids overlapping
+//! chapters, which regions need attention.
+//!
+//! A region is inside another if it starts and ends within it, and a marker is inside a
+//! region if it lies from its start up to, but not including, its end. Markers and
+//! regions inside several overlapping regions belong to the one starting last.
+//!
+//! ```
+//! use reaper_regions::{Marker, WavData};
+//!
+//! let data = WavData {
+//!     sample_rate: 1000,
+//!     length: Some(10000),
+//!     markers: vec![
+//!         Marker::new(1, "Song".to_string(), 1000, Some(5000), 1000),
+//!         Marker::new(2, "Solo".to_string(), 2000, Some(3000), 1000),
+//!         Marker::new(3, "Clap".to_string(), 2500, None, 1000),
+//!         Marker::new(4, "Encore".to_string(), 4000, Some(8000), 1000),
+//!     ],
+//!     ..Default::default()
+//! };
+//! let structure = data.structure();
+//! assert_eq!(structure.tree.len(), 2);
+//! assert_eq!(structure.tree[0].children[0].children[0].marker.name, "Clap");
+//! assert_eq!(structure.overlaps.len(), 2);
+//! assert_eq!(structure.gaps.len(), 2);
+//! assert_eq!(structure.coverage.fraction, Some(0.7));
+//! ```
+
+use std::cmp::Reverse;
+use std::fmt;
+
+use serde::Serialize;
+
+use crate::{Marker, MarkerType, WavData, serialize_f64, serialize_opt_f64};
+
+/// A marker or region in the containment tree, with the markers and regions inside it.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct Node {
+    #[serde(flatten)]
+    pub marker: Marker,
+    /// Markers and regions inside this region, by start
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub children: Vec<Node>,
+}
+
+/// A stretch of audio, in samples and seconds.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize)]
+pub struct Span {
+    /// Start in samples
+    pub start: u32,
+    /// End in samples
+    pub end: u32,
+    /// Start in seconds
+    #[serde(serialize_with = "serialize_f64")]
+    pub start_time: f64,
+    /// End in seconds
+    #[serde(serialize_with = "serialize_f64")]
+    pub end_time: f64,
+    /// Length in seconds
+    #[serde(serialize_with = "serialize_f64")]
+    pub duration: f64,
+}
+
+impl Span {
+    fn new(start: u32, end: u32, sample_rate: u32) -> Self {
+        let seconds = |samples: u32| samples as f64 / sample_rate as f64;
+        Span {
+            start,
+            end,
+            start_time: seconds(start),
+            end_time: seconds(end),
+            duration: seconds(end - start),
+        }
+    }
+}
+
+/// How two regions overlap.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum OverlapKind {
+    /// One region is inside the other
+    Nested,
+    /// Each region sticks out of the other
+    Partial,
+}
+
+/// Two regions playing at the same time.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct Overlap {
+    /// ID of the region starting first
+    pub first: u32,
+    /// ID of the other region
+    pub second: u32,
+    pub kind: OverlapKind,
+    /// Where both regions play
+    #[serde(flatten)]
+    pub span: Span,
+}
+
+/// A stretch of audio outside every region.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct Gap {
+    /// ID of the region ending at the start of the gap, if any
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub after: Option<u32>,
+    /// ID of the region starting at the end of the gap, if any
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub before: Option<u32>,
+    #[serde(flatten)]
+    pub span: Span,
+}
+
+/// How much of the audio is inside a region.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct Coverage {
+    /// Samples inside at least one region
+    pub covered: u32,
+    /// Seconds inside at least one region
+    #[serde(serialize_with = "serialize_f64")]
+    pub covered_time: f64,
+    /// Length of the audio in samples, if known
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub length: Option<u32>,
+    /// Length of the audio in seconds, if known
+    #[serde(
+        serialize_with = "serialize_opt_f64",
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub length_time: Option<f64>,
+    /// Fraction of the audio covered, if its length is known
+    #[serde(
+        serialize_with = "serialize_opt_f64",
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub fraction: Option<f64>,
+}
+
+/// The structure found by [`WavData::structure`].
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct Structure {
+    /// Markers and regions outside every region, with those inside them, by start
+    pub tree: Vec<Node>,
+    /// Overlapping regions, by the start of the first region
+    pub overlaps: Vec<Overlap>,
+    /// Stretches outside every region, by start, including those at the start and end
+    /// of the audio
+    pub gaps: Vec<Gap>,
+    pub coverage: Coverage,
+}
+
+impl WavData {
+    /// Analyses how the regions nest, overlap and cover the audio.
+    ///
+    /// Gaps at the end of the audio and the covered fraction need the length of the
+    /// audio, which files with an empty or no `data` chunk don't have. Parts of regions past the
+    /// end of the audio are not counted as covered.
+    pub fn structure(&self) -> Structure {
+        let sample_rate = self.sample_rate;
+        // Stripped files have an empty data chunk
+        let length = self.length.filter(|&length| length > 0);
+        let end = |marker: &Marker| marker.end.unwrap_or(marker.start);
+
+        // Enclosing regions come before the markers and regions inside them
+        let mut sorted: Vec<&Marker> = self.markers.iter().collect();
+        sorted.sort_by_key(|marker| {
+            (
+                marker.start,
+                Reverse(end(marker)),
+                marker.r#type == MarkerType::Marker,
+                marker.id,
+            )
+        });
+        let mut children: Vec<Vec<usize>> = vec![Vec::new(); sorted.len()];
+        let mut roots = Vec::new();
+        // Regions that may still contain later markers, innermost last
+        let mut open: Vec<usize> = Vec::new();
+        for (index, marker) in sorted.iter().enumerate() {
+            let is_region = marker.r#type == MarkerType::Region;
+            while let Some(&top) = open.last() {
+                let top_end = end(sorted[top]);
+                let contains = if is_region {
+                    top_end >= end(marker)
+                } else {
+                    top_end > marker.start
+                };
+                if contains {
+                    break;
+                }
+                // Whatever follows starts no earlier, so it can only be inside `top` if it
+                // is also inside a region starting later, which is the better parent
+                open.pop();
+            }
+            match open.last() {
+                Some(&parent) => children[parent].push(index),
+                None => roots.push(index),
+            }
+            if is_region {
+                open.push(index);
+            }
+        }
+        fn node(index: usize, sorted: &[&Marker], children: &[Vec<usize>]) -> Node {
+            Node {
+                marker: sorted[index].clone(),
+                children: children[index]
+                    .iter()
+                    .map(|&child| node(child, sorted, children))
+                    .collect(),
+            }
+        }
+        let tree = roots
+            .iter()
+            .map(|&root| node(root, &sorted, &children))
+            .collect();
+
+        let index = self.index();
+        let regions: Vec<&Marker> = index
+            .markers()
+            .iter()
+            .copied()
+            .filter(|marker| marker.r#type == MarkerType::Region)
+            .collect();
+        let mut overlaps = Vec::new();
+        for first in &regions {
+            let later = index
+                .overlapping(first.start..end(first))
+                .into_iter()
+                .filter(|other| other.r#type == MarkerType::Region)
+                .filter(|other| (other.start, other.id) > (first.start, first.id));
+            for second in later {
+                let nested = end(second) <= end(first)
+                    || (second.start == first.start && end(second) >= end(first));
+                overlaps.push(Overlap {
+                    first: first.id,
+                    second: second.id,
+                    kind: if nested {
+                        OverlapKind::Nested
+                    } else {
+                        OverlapKind::Partial
+                    },
+                    span: Span::new(second.start, end(first).min(end(second)), sample_rate),
+                });
+            }
+        }
+
+        // Walk the regions by start, tracking the end of the covered stretch
+        let limit = length.unwrap_or(u32::MAX);
+        let mut gaps = Vec::new();
+        let mut covered = 0;
+        let mut position = 0;
+        let mut last: Option<&Marker> = None;
+        for region in &regions {
+            let (start, region_end) = (region.start.min(limit), end(region).min(limit));
+            // Empty regions cover nothing, so they don't split a gap
+            if region_end <= start {
+                continue;
+            }
+            if start > position {
+                gaps.push(Gap {
+                    after: last.map(|last| last.id),
+                    before: Some(region.id),
+                    span: Span::new(position, start, sample_rate),
+                });
+            }
+            if region_end > position {
+                covered += region_end - start.max(position);
+                position = region_end;
+                last = Some(region);
+            }
+        }
+        if let Some(length) = length
+            && length > position
+        {
+            gaps.push(Gap {
+                after: last.map(|last| last.id),
+                before: None,
+                span: Span::new(position, length, sample_rate),
+            });
+        }
+        let coverage = Coverage {
+            covered,
+            covered_time: covered as f64 / sample_rate as f64,
+            length,
+            length_time: length.map(|length| length as f64 / sample_rate as f64),
+            fraction: length.map(|length| covered as f64 / length as f64),
+        };
+
+        Structure {
+            tree,
+            overlaps,
+            gaps,
+            coverage,
+        }
+    }
+}
+
+impl fmt::Display for Node {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let depth = f.width().unwrap_or(0);
+        let marker = &self.marker;
+        write!(
+            f,
+            "{:indent$}{} '{}' (ID {}) at {:.3} s",
+            "",
+            marker.r#type,
+            marker.name,
+            marker.id,
+            marker.start_time,
+            indent = depth * 2
+        )?;
+        if let Some(end_time) = marker.end_time {
+            write!(f, " to {end_time:.3} s")?;
+        }
+        for child in &self.children {
+            write!(f, "\n{child:depth$}", depth = depth + 1)?;
+        }
+        Ok(())
+    }
+}
+
+impl fmt::Display for Span {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{:.3} s to {:.3} s ({:.3} s)",
+            self.start_time, self.end_time, self.duration
+        )
+    }
+}
+
+impl fmt::Display for Structure {
+    /// Prints the tree, indented by depth, followed by the overlaps, gaps and coverage.
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for node in &self.tree {
+            writeln!(f, "{node}")?;
+        }
+        if !self.overlaps.is_empty() {
+            writeln!(f, "\nOverlaps:")?;
+        }
+        for overlap in &self.overlaps {
+            let kind = match overlap.kind {
+                OverlapKind::Nested => "nested",
+                OverlapKind::Partial => "partial",
+            };
+            writeln!(
+                f,
+                "  IDs {} and {}: {}, {kind}",
+                overlap.first, overlap.second, overlap.span
+            )?;
+        }
+        if !self.gaps.is_empty() {
+            writeln!(f, "\nGaps:")?;
+        }
+        for gap in &self.gaps {
+            write!(f, "  {}", gap.span)?;
+            match (gap.after, gap.before) {
+                (Some(after), Some(before)) => write!(f, " between IDs {after} and {before}")?,
+                (Some(after), None) => write!(f, " after ID {after}")?,
+                (None, Some(before)) => write!(f, " before ID {before}")?,
+                (None, None) => {}
+            }
+            writeln!(f)?;
+        }
+        let coverage = &self.coverage;
+        write!(f, "\nCoverage: {:.3} s", coverage.covered_time)?;
+        if let (Some(length), Some(fraction)) = (coverage.length_time, coverage.fraction) {
+            write!(f, " of {length:.3} s ({:.1}%)", fraction * 100.0)?;
+        }
+        Ok(())
+    }
+}
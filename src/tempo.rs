@@ -0,0 +1,348 @@
+//! Tempo maps and musical (bars:beats) time.
+//!
+//! A [`TempoMap`] holds the tempo and time signature changes along the timeline, from a
+//! constant tempo, a REAPER project or a small CSV file. It converts positions in seconds
+//! into [`MusicalTime`] as `bar.beat.ticks`. Tempos count beats of the time signature's
+//! beat unit per minute, so a bar always has `numerator` beats.
+
+use crate::ParseError;
+use serde::{Deserialize, Serialize};
+use std::fmt;
+use std::str::FromStr;
+
+/// A tempo and/or time signature change.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize)]
+pub struct TempoChange {
+    /// Position of the change in seconds
+    pub position: f64,
+    /// Tempo in beats (of the time signature's beat unit) per minute
+    pub bpm: f64,
+    /// Time signature numerator (beats per bar)
+    pub numerator: u32,
+    /// Time signature denominator (beat unit)
+    pub denominator: u32,
+    /// Ramp the tempo linearly to the next change instead of jumping
+    pub linear: bool,
+}
+
+/// Tempo and time signature changes, sorted by position.
+///
+/// The first change applies from the start of the timeline, even if it is positioned later.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct TempoMap {
+    /// Tempo changes, sorted by position
+    pub changes: Vec<TempoChange>,
+}
+
+impl TempoMap {
+    /// Creates a tempo map with a constant tempo and time signature.
+    ///
+    /// # Example
+    /// ```
+    /// use reaper_regions::tempo::TempoMap;
+    ///
+    /// let map = TempoMap::constant(120.0, 4, 4);
+    /// assert_eq!(map.changes.len(), 1);
+    /// assert_eq!(map.changes[0].bpm, 120.0);
+    /// ```
+    pub fn constant(bpm: f64, numerator: u32, denominator: u32) -> Self {
+        TempoMap {
+            changes: vec![TempoChange {
+                position: 0.0,
+                bpm,
+                numerator,
+                denominator,
+                linear: false,
+            }],
+        }
+    }
+}
+
+impl Default for TempoMap {
+    /// 120 BPM in 4/4.
+    fn default() -> Self {
+        TempoMap::constant(120.0, 4, 4)
+    }
+}
+
+/// Ticks per beat used for the ticks of a [`MusicalTime`].
+pub const TICKS_PER_BEAT: u32 = 960;
+
+/// A musical position or length as bars, beats and ticks.
+///
+/// Positions count bars and beats from 1 (`33.1.000` is the downbeat of bar 33), lengths
+/// from 0 (`8.0.000` is eight bars). Ticks are [`TICKS_PER_BEAT`] per beat. Displays and
+/// parses as `bar.beat.ticks`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct MusicalTime {
+    /// Bar (from 1 for positions)
+    pub bar: u32,
+    /// Beat within the bar (from 1 for positions)
+    pub beat: u32,
+    /// Ticks within the beat
+    pub ticks: u32,
+}
+
+impl fmt::Display for MusicalTime {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}.{}.{:03}", self.bar, self.beat, self.ticks)
+    }
+}
+
+impl FromStr for MusicalTime {
+    type Err = ParseError;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        let invalid = || ParseError::Other(format!("invalid bars.beats.ticks: {value}"));
+        let mut fields = value.split('.').map(|field| field.parse::<u32>());
+        let (Some(Ok(bar)), Some(Ok(beat)), Some(Ok(ticks)), None) =
+            (fields.next(), fields.next(), fields.next(), fields.next())
+        else {
+            return Err(invalid());
+        };
+        Ok(MusicalTime { bar, beat, ticks })
+    }
+}
+
+impl Serialize for MusicalTime {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_str(self)
+    }
+}
+
+impl<'de> Deserialize<'de> for MusicalTime {
+    fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let value = String::deserialize(deserializer)?;
+        value.parse().map_err(serde::de::Error::custom)
+    }
+}
+
+/// Beat and bar count at a tempo change, from the start of the timeline.
+#[derive(Debug, Clone, Copy)]
+struct Anchor {
+    position: f64,
+    beats: f64,
+    bars: f64,
+}
+
+impl TempoMap {
+    /// Parses a tempo map from CSV lines of `position,bpm[,meter[,shape]]`.
+    ///
+    /// - `position` is in seconds, and not negative
+    /// - `bpm` counts beats of the time signature's beat unit per minute, and is positive
+    /// - `meter` is a time signature such as `3/4` (defaults to the previous one, or 4/4)
+    /// - `shape` is `linear` to ramp the tempo to the next change (defaults to `square`)
+    ///
+    /// Empty lines, lines starting with `#` and a header line are skipped.
+    ///
+    /// # Errors
+    /// * [`ParseError::InvalidLine`] - If a line has missing or invalid fields
+    ///
+    /// # Example
+    /// ```
+    /// use reaper_regions::tempo::TempoMap;
+    ///
+    /// let map = TempoMap::parse_csv("position,bpm,meter\n0,120,4/4\n32,90,6/8\n").unwrap();
+    /// assert_eq!(map.changes[1].bpm, 90.0);
+    /// assert_eq!((map.changes[1].numerator, map.changes[1].denominator), (6, 8));
+    /// ```
+    pub fn parse_csv(text: &str) -> Result<Self, ParseError> {
+        let mut changes: Vec<TempoChange> = Vec::new();
+        for (index, line) in text.lines().enumerate() {
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+            let invalid = || ParseError::InvalidLine(index + 1, line.to_string());
+            let fields: Vec<&str> = line.split(',').map(str::trim).collect();
+            let Ok(position) = fields[0].parse::<f64>() else {
+                if changes.is_empty() && index == 0 {
+                    // Header
+                    continue;
+                }
+                return Err(invalid());
+            };
+            if !position.is_finite() || position < 0.0 {
+                return Err(invalid());
+            }
+            let bpm = fields
+                .get(1)
+                .and_then(|bpm| bpm.parse::<f64>().ok())
+                .filter(|&bpm| bpm.is_finite() && bpm > 0.0)
+                .ok_or_else(invalid)?;
+            let (numerator, denominator) = match fields.get(2).filter(|meter| !meter.is_empty()) {
+                Some(meter) => parse_meter(meter).ok_or_else(invalid)?,
+                None => changes.last().map_or((4, 4), |previous| {
+                    (previous.numerator, previous.denominator)
+                }),
+            };
+            let linear = match fields.get(3).map(|shape| shape.to_ascii_lowercase()) {
+                None => false,
+                Some(shape) if shape.is_empty() || shape == "square" => false,
+                Some(shape) if shape == "linear" => true,
+                Some(_) => return Err(invalid()),
+            };
+            changes.push(TempoChange {
+                position,
+                bpm,
+                numerator,
+                denominator,
+                linear,
+            });
+        }
+        if changes.is_empty() {
+            return Err(ParseError::Other("empty tempo map".to_string()));
+        }
+        changes.sort_by(|a, b| a.position.total_cmp(&b.position));
+        Ok(TempoMap { changes })
+    }
+
+    /// Computes the beat and bar count at every change.
+    ///
+    /// A time signature change starts a new bar, even if the previous bar is incomplete.
+    fn anchors(&self) -> Vec<Anchor> {
+        let mut anchors: Vec<Anchor> = Vec::with_capacity(self.changes.len());
+        for (index, change) in self.changes.iter().enumerate() {
+            let anchor = match index {
+                0 => Anchor {
+                    position: 0.0,
+                    beats: 0.0,
+                    bars: 0.0,
+                },
+                _ => {
+                    let previous = &self.changes[index - 1];
+                    let start = anchors[index - 1];
+                    let beats = start.beats + self.beats_in_segment(index - 1, change.position);
+                    let mut bars = start.bars + (beats - start.beats) / previous.numerator as f64;
+                    if (previous.numerator, previous.denominator)
+                        != (change.numerator, change.denominator)
+                    {
+                        bars = (bars - 1e-9).ceil();
+                    }
+                    Anchor {
+                        position: change.position,
+                        beats,
+                        bars,
+                    }
+                }
+            };
+            anchors.push(anchor);
+        }
+        anchors
+    }
+
+    /// Beats elapsed from the start of segment `index` to `seconds`.
+    fn beats_in_segment(&self, index: usize, seconds: f64) -> f64 {
+        let change = &self.changes[index];
+        let start = if index == 0 { 0.0 } else { change.position };
+        let elapsed = seconds - start;
+        match self.changes.get(index + 1) {
+            Some(next) if change.linear && next.position > start => {
+                let slope = (next.bpm - change.bpm) / (next.position - start);
+                let bpm = change.bpm + slope * elapsed;
+                elapsed * (change.bpm + bpm) / 2.0 / 60.0
+            }
+            _ => elapsed * change.bpm / 60.0,
+        }
+    }
+
+    /// Returns the index of the change in effect at `seconds`.
+    fn segment(&self, anchors: &[Anchor], seconds: f64) -> usize {
+        anchors
+            .iter()
+            .rposition(|anchor| anchor.position <= seconds)
+            .unwrap_or(0)
+    }
+
+    /// Returns the number of beats from the start of the timeline to `seconds`.
+    ///
+    /// # Example
+    /// ```
+    /// use reaper_regions::tempo::TempoMap;
+    ///
+    /// assert_eq!(TempoMap::constant(120.0, 4, 4).beats_at(3.0), 6.0);
+    /// ```
+    pub fn beats_at(&self, seconds: f64) -> f64 {
+        let anchors = self.anchors();
+        let seconds = seconds.max(0.0);
+        let index = self.segment(&anchors, seconds);
+        anchors[index].beats + self.beats_in_segment(index, seconds)
+    }
+
+    /// Converts a position in seconds to bars, beats and ticks.
+    ///
+    /// # Example
+    /// ```
+    /// use reaper_regions::tempo::TempoMap;
+    ///
+    /// let map = TempoMap::constant(120.0, 4, 4);
+    /// // 64 seconds at 120 BPM are 128 beats, or 32 bars of 4/4
+    /// assert_eq!(map.position(64.0).to_string(), "33.1.000");
+    /// assert_eq!(map.position(64.25).to_string(), "33.1.480");
+    /// ```
+    pub fn position(&self, seconds: f64) -> MusicalTime {
+        let anchors = self.anchors();
+        let seconds = seconds.max(0.0);
+        let index = self.segment(&anchors, seconds);
+        let numerator = self.changes[index].numerator.max(1);
+        let anchor = anchors[index];
+        let beats = self.beats_in_segment(index, seconds);
+        let bars = anchor.bars + beats / numerator as f64;
+
+        let mut bar = bars.floor();
+        let ticks_per_bar = numerator * TICKS_PER_BEAT;
+        let mut ticks = ((bars - bar) * ticks_per_bar as f64).round() as u32;
+        if ticks >= ticks_per_bar {
+            bar += 1.0;
+            ticks = 0;
+        }
+        MusicalTime {
+            bar: (bar as u32).saturating_add(1),
+            beat: ticks / TICKS_PER_BEAT + 1,
+            ticks: ticks % TICKS_PER_BEAT,
+        }
+    }
+
+    /// Returns the musical length between two positions in seconds.
+    ///
+    /// Beats are grouped into bars using the time signature at `start`.
+    ///
+    /// # Example
+    /// ```
+    /// use reaper_regions::tempo::TempoMap;
+    ///
+    /// let map = TempoMap::constant(90.0, 3, 4);
+    /// // 10 seconds at 90 BPM are 15 beats, or 5 bars of 3/4
+    /// assert_eq!(map.length(2.0, 12.0).to_string(), "5.0.000");
+    /// ```
+    pub fn length(&self, start: f64, end: f64) -> MusicalTime {
+        let anchors = self.anchors();
+        let numerator = self.changes[self.segment(&anchors, start.max(0.0))]
+            .numerator
+            .max(1);
+        let beats = (self.beats_at(end) - self.beats_at(start)).max(0.0);
+        let ticks = (beats * TICKS_PER_BEAT as f64).round() as u32;
+        let ticks_per_bar = numerator * TICKS_PER_BEAT;
+        MusicalTime {
+            bar: ticks / ticks_per_bar,
+            beat: (ticks % ticks_per_bar) / TICKS_PER_BEAT,
+            ticks: ticks % TICKS_PER_BEAT,
+        }
+    }
+}
+
+/// Parses a time signature such as `4/4` into `(numerator, denominator)`.
+///
+/// # Example
+/// ```
+/// use reaper_regions::tempo::parse_meter;
+///
+/// assert_eq!(parse_meter("7/8"), Some((7, 8)));
+/// assert_eq!(parse_meter("0/4"), None);
+/// ```
+pub fn parse_meter(value: &str) -> Option<(u32, u32)> {
+    let (numerator, denominator) = value.trim().split_once('/')?;
+    let numerator = numerator.trim().parse().ok().filter(|&n: &u32| n > 0)?;
+    let denominator = denominator.trim().parse().ok().filter(|&d: &u32| d > 0)?;
+    Some((numerator, denominator))
+}
@@ -0,0 +1,207 @@
+//! SMPTE timecode and video frame rate helpers.
+//!
+//! Used by the exporters that place markers on a video timeline, where positions
+//! are expressed as `HH:MM:SS:FF` timecode rather than seconds or samples.
+
+use crate::ParseError;
+use std::fmt;
+
+/// Video frame rate used for timecode conversion.
+///
+/// Fractional NTSC rates are available in both non-drop-frame and drop-frame flavors.
+/// Parse from strings such as `"25"`, `"29.97"` or `"29.97df"`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, strum::Display, strum::EnumString)]
+pub enum FrameRate {
+    /// 24000/1001 frames per second (23.976, non-drop-frame)
+    #[strum(serialize = "23.976")]
+    Fps23_976,
+    /// 24 frames per second
+    #[strum(serialize = "24")]
+    Fps24,
+    /// 25 frames per second (PAL)
+    #[strum(serialize = "25")]
+    Fps25,
+    /// 30000/1001 frames per second (29.97, non-drop-frame)
+    #[strum(serialize = "29.97")]
+    Fps29_97,
+    /// 30000/1001 frames per second (29.97, drop-frame)
+    #[strum(serialize = "29.97df")]
+    Fps29_97Df,
+    /// 30 frames per second
+    #[strum(serialize = "30")]
+    Fps30,
+    /// 50 frames per second
+    #[strum(serialize = "50")]
+    Fps50,
+    /// 60000/1001 frames per second (59.94, non-drop-frame)
+    #[strum(serialize = "59.94")]
+    Fps59_94,
+    /// 60000/1001 frames per second (59.94, drop-frame)
+    #[strum(serialize = "59.94df")]
+    Fps59_94Df,
+    /// 60 frames per second
+    #[strum(serialize = "60")]
+    Fps60,
+}
+
+impl FrameRate {
+    /// Returns the exact frame rate as a `(numerator, denominator)` ratio.
+    ///
+    /// # Example
+    /// ```
+    /// use reaper_regions::timecode::FrameRate;
+    ///
+    /// assert_eq!(FrameRate::Fps25.ratio(), (25, 1));
+    /// assert_eq!(FrameRate::Fps29_97Df.ratio(), (30000, 1001));
+    /// ```
+    pub fn ratio(self) -> (u64, u64) {
+        match self {
+            FrameRate::Fps23_976 => (24000, 1001),
+            FrameRate::Fps24 => (24, 1),
+            FrameRate::Fps25 => (25, 1),
+            FrameRate::Fps29_97 | FrameRate::Fps29_97Df => (30000, 1001),
+            FrameRate::Fps30 => (30, 1),
+            FrameRate::Fps50 => (50, 1),
+            FrameRate::Fps59_94 | FrameRate::Fps59_94Df => (60000, 1001),
+            FrameRate::Fps60 => (60, 1),
+        }
+    }
+
+    /// Returns the nominal (integer) number of frames per timecode second.
+    pub fn nominal(self) -> u64 {
+        let (num, den) = self.ratio();
+        num.div_ceil(den)
+    }
+
+    /// Returns `true` for drop-frame rates.
+    pub fn is_drop_frame(self) -> bool {
+        matches!(self, FrameRate::Fps29_97Df | FrameRate::Fps59_94Df)
+    }
+
+    /// Number of frame labels skipped at the start of each minute (except every tenth) for drop-frame rates.
+    fn dropped_per_minute(self) -> u64 {
+        match self {
+            FrameRate::Fps29_97Df => 2,
+            FrameRate::Fps59_94Df => 4,
+            _ => 0,
+        }
+    }
+
+    /// Converts a sample position to the nearest whole frame.
+    ///
+    /// # Arguments
+    /// * `samples` - Position in samples
+    /// * `sample_rate` - Sample rate of the audio in Hz
+    ///
+    /// # Example
+    /// ```
+    /// use reaper_regions::timecode::FrameRate;
+    ///
+    /// assert_eq!(FrameRate::Fps25.samples_to_frames(48000, 48000), 25);
+    /// assert_eq!(FrameRate::Fps25.samples_to_frames(1000, 48000), 1);
+    /// ```
+    pub fn samples_to_frames(self, samples: u64, sample_rate: u32) -> u64 {
+        let (num, den) = self.ratio();
+        let divisor = sample_rate as u128 * den as u128;
+        ((samples as u128 * num as u128 * 2 + divisor) / (divisor * 2)) as u64
+    }
+}
+
+/// A timecode position, stored as a frame count at a given [`FrameRate`].
+///
+/// Displays as `HH:MM:SS:FF`, or `HH:MM:SS;FF` for drop-frame rates.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Timecode {
+    /// Number of frames since `00:00:00:00`
+    pub frames: u64,
+    /// Frame rate the timecode is counted in
+    pub rate: FrameRate,
+}
+
+impl Timecode {
+    /// Creates a timecode from a frame count.
+    pub fn new(frames: u64, rate: FrameRate) -> Self {
+        Timecode { frames, rate }
+    }
+
+    /// Parses a timecode string such as `01:00:00:00` (`;` is also accepted as a separator).
+    ///
+    /// # Errors
+    /// * [`ParseError::InvalidTimecode`] - If the string is not four numeric fields,
+    ///   or a field is out of range for the frame rate
+    ///
+    /// # Example
+    /// ```
+    /// use reaper_regions::timecode::{FrameRate, Timecode};
+    ///
+    /// let tc = Timecode::parse("01:00:00:00", FrameRate::Fps25).unwrap();
+    /// assert_eq!(tc.frames, 90_000);
+    ///
+    /// let tc = Timecode::parse("00:01:00;02", FrameRate::Fps29_97Df).unwrap();
+    /// assert_eq!(tc.frames, 1800);
+    /// assert_eq!(tc.to_string(), "00:01:00;02");
+    /// ```
+    pub fn parse(value: &str, rate: FrameRate) -> Result<Self, ParseError> {
+        let invalid = || ParseError::InvalidTimecode(value.to_string());
+        let fields = value
+            .split([':', ';', '.'])
+            .map(|field| field.trim().parse::<u64>().map_err(|_| invalid()))
+            .collect::<Result<Vec<_>, _>>()?;
+        let [hours, minutes, seconds, frames] = fields[..] else {
+            return Err(invalid());
+        };
+        let nominal = rate.nominal();
+        if minutes >= 60 || seconds >= 60 || frames >= nominal {
+            return Err(invalid());
+        }
+
+        let total_minutes = hours * 60 + minutes;
+        let drop = rate.dropped_per_minute();
+        if drop > 0 && seconds == 0 && frames < drop && minutes % 10 != 0 {
+            // These labels do not exist in drop-frame timecode
+            return Err(invalid());
+        }
+        let labels = (total_minutes * 60 + seconds) * nominal + frames;
+        let dropped = drop * (total_minutes - total_minutes / 10);
+
+        Ok(Timecode::new(labels - dropped, rate))
+    }
+
+    /// Returns a new timecode offset by the given number of frames.
+    pub fn add_frames(self, frames: u64) -> Self {
+        Timecode::new(self.frames + frames, self.rate)
+    }
+}
+
+impl fmt::Display for Timecode {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let nominal = self.rate.nominal();
+        let drop = self.rate.dropped_per_minute();
+
+        // Convert the frame count into a frame label, re-inserting skipped labels for drop-frame
+        let mut labels = self.frames;
+        if drop > 0 {
+            let per_minute = nominal * 60 - drop;
+            let per_ten_minutes = per_minute * 10 + drop;
+            let tens = self.frames / per_ten_minutes;
+            let remainder = self.frames % per_ten_minutes;
+            labels += drop * 9 * tens;
+            if remainder > drop {
+                labels += drop * ((remainder - drop) / per_minute);
+            }
+        }
+
+        let frames = labels % nominal;
+        let total_seconds = labels / nominal;
+        let separator = if self.rate.is_drop_frame() { ';' } else { ':' };
+        write!(
+            f,
+            "{:02}:{:02}:{:02}{}{:02}",
+            total_seconds / 3600,
+            (total_seconds / 60) % 60,
+            total_seconds % 60,
+            separator,
+            frames
+        )
+    }
+}
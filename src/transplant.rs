@@ -0,0 +1,219 @@
+//! Copying markers from one WAV file to another, e.g. from an old render of a mix to a new
+//! one with a different sample rate or a pre-roll.
+//!
+//! [`transplant`] converts the markers and regions of a source file to the sample rate of
+//! the destination (see [`rescale`]), shifts them by an offset and replaces the markers
+//! of the destination with them. Markers that end up outside the destination's audio are
+//! dropped or clamped to its edges (see [`OutOfRange`]); regions that stick out are
+//! shortened. Each change is reported as a [`Diagnostic`].
+//!
+//! ```rust,no_run
+//! use reaper_regions::transplant::{self, TransplantOptions};
+//!
+//! // The new master starts with half a second of silence
+//! let options = TransplantOptions {
+//!     offset: 0.5,
+//!     ..Default::default()
+//! };
+//! let result = transplant::transplant_to_file("old.wav", "new.wav", "new.wav", &options).unwrap();
+//! println!("{} markers", result.markers.len());
+//! ```
+
+use crate::diagnostic::Diagnostic;
+use crate::edit::{Mapped, OutOfRange, report};
+use crate::select::Filter;
+use crate::wavtag::RiffFile;
+use crate::write::copy_with_metadata;
+use crate::{Marker, ParseError, WavData, parse_markers_from_file};
+
+/// Options for [`transplant`].
+#[derive(Debug, Clone, Default)]
+pub struct TransplantOptions {
+    /// Seconds added to every position, negative to move markers earlier
+    pub offset: f64,
+    /// What happens to markers outside the destination's audio
+    pub out_of_range: OutOfRange,
+    /// Markers and regions of the source to copy
+    pub filter: Filter,
+}
+
+/// The result of [`transplant`].
+#[derive(Debug, Clone, Default)]
+pub struct Transplant {
+    /// Markers and regions written to the destination
+    pub markers: Vec<Marker>,
+    /// Markers and regions that were moved, cut or dropped
+    pub diagnostics: Vec<Diagnostic>,
+}
+
+/// Converts a position between sample rates, rounding to the nearest sample (halves up).
+///
+/// ```
+/// use reaper_regions::transplant::rescale;
+///
+/// assert_eq!(rescale(44_100, 44_100, 48_000), 48_000);
+/// // 1.5 samples at 48 kHz
+/// assert_eq!(rescale(11, 352_000, 48_000), 2);
+/// ```
+pub fn rescale(position: u32, from_rate: u32, to_rate: u32) -> u64 {
+    if from_rate == to_rate || from_rate == 0 {
+        return position as u64;
+    }
+    let (from_rate, to_rate) = (from_rate as u128, to_rate as u128);
+    ((2 * position as u128 * to_rate + from_rate) / (2 * from_rate)) as u64
+}
+
+/// The audio of the destination: positions are shifted by `offset` and must fall within
+/// `0..=length`.
+struct Window {
+    offset: i64,
+    length: u32,
+    out_of_range: OutOfRange,
+}
+
+impl Window {
+    fn shift(&self, position: u64) -> i64 {
+        position as i64 + self.offset
+    }
+
+    fn clamp(&self, position: i64) -> u32 {
+        position.clamp(0, self.length as i64) as u32
+    }
+
+    fn point(&self, position: u64) -> Mapped<u32> {
+        let position = self.shift(position);
+        if (0..=self.length as i64).contains(&position) {
+            Mapped::Kept(position as u32)
+        } else if self.out_of_range == OutOfRange::Clamp {
+            Mapped::Clamped(self.clamp(position))
+        } else {
+            Mapped::Dropped
+        }
+    }
+
+    fn range(&self, start: u64, end: u64) -> Mapped<(u32, u32)> {
+        let (start, end) = (self.shift(start), self.shift(end));
+        let clamped = (self.clamp(start), self.clamp(end));
+        if start >= 0 && end <= self.length as i64 {
+            Mapped::Kept(clamped)
+        } else if start < self.length as i64 && end > 0 {
+            Mapped::Cut(clamped)
+        } else if self.out_of_range == OutOfRange::Clamp {
+            Mapped::Clamped(clamped)
+        } else {
+            Mapped::Dropped
+        }
+    }
+}
+
+/// Replaces the markers of a RIFF file with those of another file.
+///
+/// # Arguments
+/// * `source` - The markers to copy, from [`parse_markers_from_file`]
+/// * `riff_file` - The destination file
+/// * `options` - Offset and handling of markers outside the destination's audio
+///
+/// # Returns
+/// * `Result<Transplant, ParseError>` - The markers written, at the destination's sample
+///   rate, and diagnostics for those that were moved, cut or dropped
+///
+/// # Errors
+/// * [`ParseError::MissingFormatChunk`] - If the destination has no format chunk
+/// * [`ParseError::Other`] - If the destination has no 'data' chunk
+/// * [`ParseError::Io`] - If the destination's `smpl` chunk is invalid
+///
+/// # Note
+/// Region ends are converted on their own rather than from the region length, so a
+/// region that ends where the next one starts still does after the conversion.
+pub fn transplant(
+    source: &WavData,
+    riff_file: &mut RiffFile,
+    options: &TransplantOptions,
+) -> Result<Transplant, ParseError> {
+    let length = riff_file.audio_length()?;
+    transplant_with_length(source, riff_file, length, options)
+}
+
+/// [`transplant`] into a file with `length` samples of audio, which need not be loaded.
+fn transplant_with_length(
+    source: &WavData,
+    riff_file: &mut RiffFile,
+    length: u32,
+    options: &TransplantOptions,
+) -> Result<Transplant, ParseError> {
+    let sample_rate = crate::get_sample_rate(riff_file)?;
+    let window = Window {
+        offset: (options.offset * sample_rate as f64).round() as i64,
+        length,
+        out_of_range: options.out_of_range,
+    };
+
+    let mut result = Transplant::default();
+    for marker in source
+        .markers
+        .iter()
+        .filter(|marker| options.filter.matches(marker))
+    {
+        let start = rescale(marker.start, source.sample_rate, sample_rate);
+        let position = match marker.end {
+            Some(end) => {
+                let end = rescale(end, source.sample_rate, sample_rate);
+                report(
+                    &mut result.diagnostics,
+                    marker.id,
+                    window.range(start, end),
+                    "region",
+                )
+                .map(|(start, end)| (start, Some(end)))
+            }
+            None => report(
+                &mut result.diagnostics,
+                marker.id,
+                window.point(start),
+                "marker",
+            )
+            .map(|start| (start, None)),
+        };
+        if let Some((start, end)) = position {
+            result.markers.push(Marker::new(
+                marker.id,
+                marker.name.clone(),
+                start,
+                end,
+                sample_rate,
+            ));
+        }
+    }
+    result
+        .markers
+        .sort_by_key(|marker| (marker.start, marker.id));
+
+    crate::write::replace_marker_chunks(riff_file, &result.markers)?;
+    Ok(result)
+}
+
+/// Writes a copy of a WAV file with the markers of another file.
+///
+/// See [`transplant`]. The audio is copied without loading it, through a temporary file
+/// next to the output. The destination and output may be the same file, which is then
+/// updated with [`update_metadata`](crate::write::update_metadata).
+///
+/// # Arguments
+/// * `source` - Path of the WAV file to copy the markers from
+/// * `destination` - Path of the WAV file to copy the markers to
+/// * `output` - Path of the file to write
+/// * `options` - Offset and handling of markers outside the destination's audio
+pub fn transplant_to_file(
+    source: &str,
+    destination: &str,
+    output: &str,
+    options: &TransplantOptions,
+) -> Result<Transplant, ParseError> {
+    let source = parse_markers_from_file(source)?;
+    let length = parse_markers_from_file(destination)?
+        .length
+        .ok_or_else(|| ParseError::Other("no 'data' chunk found".into()))?;
+    copy_with_metadata(destination, output, |riff_file| {
+        transplant_with_length(&source, riff_file, length, options)
+    })
+}
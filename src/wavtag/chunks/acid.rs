@@ -0,0 +1,177 @@
+use std::io;
+use std::io::{Cursor, Error};
+
+use byteorder::{LittleEndian, ReadBytesExt, WriteBytesExt};
+use serde::Serialize;
+
+use crate::tempo::TempoMap;
+use crate::wavtag::{ChunkType, RiffChunk, RiffFile};
+
+const FLAG_ONE_SHOT: u32 = 0x01;
+const FLAG_ROOT_NOTE: u32 = 0x02;
+const FLAG_STRETCH: u32 = 0x04;
+const FLAG_DISK_BASED: u32 = 0x08;
+const FLAG_HIGH_OCTAVE: u32 = 0x10;
+
+/// ACID loop information, as written by ACID, REAPER and loop libraries.
+#[derive(Clone, Copy, Debug, PartialEq, Serialize)]
+pub struct AcidChunk {
+    /// The file is a one-shot rather than a loop.
+    pub one_shot: bool,
+
+    /// The root note is set; loops without it are not transposed.
+    pub root_note_set: bool,
+
+    /// The file may be time-stretched to the project tempo.
+    pub stretch: bool,
+
+    /// The file is played from disk instead of RAM.
+    pub disk_based: bool,
+
+    /// Flag set by ACID for root notes in the upper octave (meaning unclear).
+    pub high_octave: bool,
+
+    /// Root note as a MIDI note number (60 is middle C).
+    pub root_note: u16,
+
+    /// Number of beats in the file.
+    pub beats: u32,
+
+    /// Time signature numerator.
+    pub meter_numerator: u16,
+
+    /// Time signature denominator.
+    pub meter_denominator: u16,
+
+    /// Tempo in beats per minute.
+    pub tempo: f32,
+
+    /// Undocumented, usually 0x8000.
+    #[serde(skip)]
+    pub reserved1: u16,
+
+    /// Undocumented, usually 0.
+    #[serde(skip)]
+    pub reserved2: f32,
+}
+
+impl Default for AcidChunk {
+    fn default() -> Self {
+        AcidChunk {
+            one_shot: false,
+            root_note_set: false,
+            stretch: true,
+            disk_based: false,
+            high_octave: false,
+            root_note: 60,
+            beats: 0,
+            meter_numerator: 4,
+            meter_denominator: 4,
+            tempo: 120.0,
+            reserved1: 0x8000,
+            reserved2: 0.0,
+        }
+    }
+}
+
+impl AcidChunk {
+    pub fn from_chunk(chunk: &RiffChunk) -> Result<Self, io::Error> {
+        if chunk.header != ChunkType::Acid {
+            return Err(Error::other("attempted from_chunk() on non-acid chunk"));
+        };
+
+        let mut data = Cursor::new(&chunk.data);
+
+        let flags = data.read_u32::<LittleEndian>()?;
+        let root_note = data.read_u16::<LittleEndian>()?;
+        let reserved1 = data.read_u16::<LittleEndian>()?;
+        let reserved2 = data.read_f32::<LittleEndian>()?;
+        let beats = data.read_u32::<LittleEndian>()?;
+        let meter_denominator = data.read_u16::<LittleEndian>()?;
+        let meter_numerator = data.read_u16::<LittleEndian>()?;
+        let tempo = data.read_f32::<LittleEndian>()?;
+
+        Ok(AcidChunk {
+            one_shot: flags & FLAG_ONE_SHOT != 0,
+            root_note_set: flags & FLAG_ROOT_NOTE != 0,
+            stretch: flags & FLAG_STRETCH != 0,
+            disk_based: flags & FLAG_DISK_BASED != 0,
+            high_octave: flags & FLAG_HIGH_OCTAVE != 0,
+            root_note,
+            beats,
+            meter_numerator,
+            meter_denominator,
+            tempo,
+            reserved1,
+            reserved2,
+        })
+    }
+
+    /// The flags field, from the boolean fields.
+    pub fn flags(&self) -> u32 {
+        [
+            (self.one_shot, FLAG_ONE_SHOT),
+            (self.root_note_set, FLAG_ROOT_NOTE),
+            (self.stretch, FLAG_STRETCH),
+            (self.disk_based, FLAG_DISK_BASED),
+            (self.high_octave, FLAG_HIGH_OCTAVE),
+        ]
+        .iter()
+        .filter(|(set, _)| *set)
+        .fold(0, |flags, (_, flag)| flags | flag)
+    }
+
+    /// A constant tempo map from the tempo and meter, if the tempo is set.
+    pub fn tempo_map(&self) -> Option<TempoMap> {
+        if !(self.tempo.is_finite() && self.tempo > 0.0) {
+            return None;
+        }
+        let numerator = if self.meter_numerator == 0 {
+            4
+        } else {
+            self.meter_numerator
+        };
+        let denominator = if self.meter_denominator == 0 {
+            4
+        } else {
+            self.meter_denominator
+        };
+        Some(TempoMap::constant(
+            self.tempo as f64,
+            numerator as u32,
+            denominator as u32,
+        ))
+    }
+
+    pub fn serialise(&self) -> Vec<u8> {
+        let mut chunk = Vec::with_capacity(24);
+        // Writing to a Vec cannot fail
+        chunk.write_u32::<LittleEndian>(self.flags()).unwrap();
+        chunk.write_u16::<LittleEndian>(self.root_note).unwrap();
+        chunk.write_u16::<LittleEndian>(self.reserved1).unwrap();
+        chunk.write_f32::<LittleEndian>(self.reserved2).unwrap();
+        chunk.write_u32::<LittleEndian>(self.beats).unwrap();
+        chunk
+            .write_u16::<LittleEndian>(self.meter_denominator)
+            .unwrap();
+        chunk
+            .write_u16::<LittleEndian>(self.meter_numerator)
+            .unwrap();
+        chunk.write_f32::<LittleEndian>(self.tempo).unwrap();
+        chunk
+    }
+}
+
+impl RiffFile {
+    pub fn get_acid_chunk(&self) -> Option<Result<AcidChunk, io::Error>> {
+        self.find_chunk_by_type(ChunkType::Acid)
+            .map(AcidChunk::from_chunk)
+    }
+
+    pub fn set_acid_chunk(&mut self, chunk: AcidChunk) {
+        self.replace_chunk_by_type(RiffChunk {
+            header: ChunkType::Acid,
+            data: chunk.serialise(),
+        });
+    }
+}
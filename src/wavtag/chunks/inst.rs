@@ -1,5 +1,5 @@
 use std::io;
-use std::io::{Cursor, Error, ErrorKind};
+use std::io::{Cursor, Error};
 
 use byteorder::ReadBytesExt;
 
@@ -12,16 +12,16 @@ pub struct InstrumentChunk {
     /// in the format chunk). (0-127)
     pub unshifted_note: u8,
 
-    /// Fine Tune (dB)
+    /// Fine Tune (cents)
     /// The fine tune value specifies how much the sample's pitch should be altered when the sound is played back
     /// in cents (1/100 of a semitone). A negative value means that the pitch should be played lower and a positive
     /// value means that it should be played at a higher pitch.
-    pub fine_tune: u8, // -50 - +50
+    pub fine_tune: i8, // -50 - +50
 
     /// The gain value specifies the number of decibels to adjust the output when it is played. A value of 0dB
     /// means no change, 6dB means double the amplitude of each sample and -6dB means to halve the amplitude of
     /// each sample. Every additional +/-6dB will double or halve the amplitude again.
-    pub gain: u8, // -64 - +64
+    pub gain: i8, // -64 - +64
     pub low_note: u8,
     pub high_note: u8,
     pub low_vel: u8,
@@ -45,8 +45,7 @@ impl Default for InstrumentChunk {
 impl InstrumentChunk {
     pub fn from_chunk(chunk: &RiffChunk) -> Result<Self, io::Error> {
         if chunk.header != ChunkType::Instrument {
-            return Err(Error::new(
-                ErrorKind::Other,
+            return Err(Error::other(
                 "attempted from_chunk() on non-instrument chunk",
             ));
         };
@@ -55,8 +54,8 @@ impl InstrumentChunk {
 
         Ok(InstrumentChunk {
             unshifted_note: data.read_u8()?,
-            fine_tune: data.read_u8()?,
-            gain: data.read_u8()?,
+            fine_tune: data.read_i8()?,
+            gain: data.read_i8()?,
             low_note: data.read_u8()?,
             high_note: data.read_u8()?,
             low_vel: data.read_u8()?,
@@ -67,8 +66,8 @@ impl InstrumentChunk {
     pub fn serialise(&self) -> Vec<u8> {
         vec![
             self.unshifted_note,
-            self.fine_tune,
-            self.gain,
+            self.fine_tune as u8,
+            self.gain as u8,
             self.low_note,
             self.high_note,
             self.low_vel,
@@ -89,7 +88,7 @@ impl RiffFile {
     }
 
     pub fn set_instrument_chunk(&mut self, chunk: InstrumentChunk) {
-        self.add_or_replace_chunk_by_type(RiffChunk {
+        self.replace_chunk_by_type(RiffChunk {
             header: ChunkType::Instrument,
             data: chunk.serialise(),
         });
@@ -1,3 +1,6 @@
+mod acid;
+pub use self::acid::AcidChunk;
+
 mod inst;
 pub use self::inst::InstrumentChunk;
 
@@ -1,11 +1,11 @@
 use byteorder::{ByteOrder, LittleEndian, ReadBytesExt};
 
 use std::io;
-use std::io::{Cursor, Error, ErrorKind};
+use std::io::{Cursor, Error};
 
 use crate::wavtag::{ChunkType, RiffChunk, RiffFile};
 
-#[derive(Clone, Debug, PartialEq, Eq)]
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
 pub struct SamplerChunk {
     /// The manufacturer field specifies the MIDI Manufacturer's Association (MMA) Manufacturer
     /// code for the sampler intended to receive this file's waveform. Each manufacturer of a
@@ -81,25 +81,21 @@ pub struct SampleLoop {
     pub play_count: u32,
 }
 
-#[derive(Clone, Debug, PartialEq, Eq)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq, strum::Display, strum::EnumString)]
+#[strum(serialize_all = "kebab-case", ascii_case_insensitive)]
 pub enum LoopType {
     Forward,
     PingPong,
     Reverse,
 }
 
-impl Default for SamplerChunk {
-    fn default() -> Self {
-        Self {
-            manufacturer: 0,
-            product: 0,
-            sample_period: 0,
-            midi_unity_note: 0,
-            midi_pitch_fraction: 0,
-            smpte_format: 0,
-            smpte_offset: 0,
-            sample_loops: Vec::new(),
-            sampler_data: Vec::new(),
+impl LoopType {
+    /// The value of the loop's type field.
+    pub fn to_u32(self) -> u32 {
+        match self {
+            LoopType::Forward => 0,
+            LoopType::PingPong => 1,
+            LoopType::Reverse => 2,
         }
     }
 }
@@ -107,13 +103,11 @@ impl Default for SamplerChunk {
 impl SamplerChunk {
     pub fn from_chunk(chunk: &RiffChunk) -> Result<Self, io::Error> {
         if chunk.header != ChunkType::Sampler {
-            return Err(Error::new(
-                ErrorKind::Other,
-                "attempted from_chunk() on non-sampler chunk",
-            ));
+            return Err(Error::other("attempted from_chunk() on non-sampler chunk"));
         };
 
         let mut data = Cursor::new(&chunk.data);
+        let mut sampler_data_size = 0;
 
         Ok(SamplerChunk {
             manufacturer: data.read_u32::<LittleEndian>()?,
@@ -125,7 +119,7 @@ impl SamplerChunk {
             smpte_offset: data.read_u32::<LittleEndian>()?,
             sample_loops: {
                 let num_sample_loops = data.read_u32::<LittleEndian>()?;
-                let _ = data.read_u32::<LittleEndian>()?; // sampler_data_chunk_size
+                sampler_data_size = data.read_u32::<LittleEndian>()? as usize;
 
                 (0..num_sample_loops)
                     .map(|_| SampleLoop {
@@ -133,7 +127,9 @@ impl SamplerChunk {
                         loop_type: {
                             let lt = data.read_u32::<LittleEndian>().unwrap();
                             match lt {
-                                // TODO: other loop types!
+                                1 => LoopType::PingPong,
+                                2 => LoopType::Reverse,
+                                // TODO: sampler specific loop types!
                                 _ => LoopType::Forward,
                             }
                         },
@@ -144,40 +140,43 @@ impl SamplerChunk {
                     })
                     .collect()
             },
-            sampler_data: Vec::new(),
+            // Only cbSamplerData bytes follow the loops; anything after them is padding
+            sampler_data: {
+                let start = (data.position() as usize).min(chunk.data.len());
+                let end = start
+                    .saturating_add(sampler_data_size)
+                    .min(chunk.data.len());
+                chunk.data[start..end].to_vec()
+            },
         })
     }
 
     pub fn serialise(&self) -> Vec<u8> {
-        let mut chunk = Vec::with_capacity(36 + 24); // space for static fields and sample_loops
-        unsafe { chunk.set_len(36 + 24) }; // todo: find a safe way to zero the elements.
-
-        let sample_loop = self
-            .sample_loops
-            .first()
-            .expect("sampler chunk to have at least one sample loop");
-        let _ = 0; // sampler_data: greater than 0 if extra sampler data is present.
-
-        LittleEndian::write_u32_into(
-            &vec![
-                self.manufacturer,
-                self.product,
-                self.sample_period,
-                self.midi_unity_note,
-                self.midi_pitch_fraction,
-                self.smpte_format,
-                self.smpte_offset,
-                1_u32,
-                0,
+        let mut fields = vec![
+            self.manufacturer,
+            self.product,
+            self.sample_period,
+            self.midi_unity_note,
+            self.midi_pitch_fraction,
+            self.smpte_format,
+            self.smpte_offset,
+            self.sample_loops.len() as u32,
+            self.sampler_data.len() as u32, // greater than 0 if extra sampler data is present
+        ];
+        for sample_loop in &self.sample_loops {
+            fields.extend([
                 sample_loop.id,
-                0_u32,
+                sample_loop.loop_type.to_u32(),
                 sample_loop.start,
                 sample_loop.end,
                 sample_loop.fraction,
                 sample_loop.play_count,
-            ],
-            &mut chunk,
-        );
+            ]);
+        }
+
+        let mut chunk = vec![0; fields.len() * 4];
+        LittleEndian::write_u32_into(&fields, &mut chunk);
+        chunk.extend(&self.sampler_data);
 
         chunk
     }
@@ -192,7 +191,7 @@ impl RiffFile {
     }
 
     pub fn set_sampler_chunk(&mut self, chunk: SamplerChunk) {
-        self.add_or_replace_chunk_by_type(RiffChunk {
+        self.replace_chunk_by_type(RiffChunk {
             header: ChunkType::Sampler,
             data: chunk.serialise(),
         });
@@ -2,7 +2,7 @@ use byteorder::{LittleEndian, ReadBytesExt, WriteBytesExt};
 
 use std::fs;
 use std::io;
-use std::io::{Cursor, Error, ErrorKind, Read, Write};
+use std::io::{Cursor, Error, ErrorKind, Read, Seek, SeekFrom, Write};
 
 use crate::wavtag::utils;
 
@@ -15,6 +15,10 @@ impl RiffChunk {
     pub fn len(&self) -> usize {
         self.data.len() // todo: investigate if this is actually valid given we have padded bytes.
     }
+
+    pub fn is_empty(&self) -> bool {
+        self.data.is_empty()
+    }
 }
 
 #[derive(Clone, Debug, PartialEq, Eq)]
@@ -41,7 +45,7 @@ impl ChunkType {
             ChunkType::Fact => *b"fact",
             ChunkType::Cue => *b"cue ",
             ChunkType::Playlist => *b"plst",
-            ChunkType::List => *b"list",
+            ChunkType::List => *b"LIST",
             ChunkType::Label => *b"labl",
             ChunkType::Note => *b"note",
             ChunkType::Sampler => *b"smpl",
@@ -66,12 +70,21 @@ fn header_to_rifftype(tag: [u8; 4]) -> ChunkType {
         b"labl" | b"LABL" => ChunkType::Label,
         b"note" | b"NOTE" => ChunkType::Note,
         b"smpl" | b"SMPL" => ChunkType::Sampler,
-        b"ltxt" | b"LTXT" | b"INST" | b"inst" => ChunkType::Instrument,
+        b"INST" | b"inst" => ChunkType::Instrument,
         b"acid" | b"ACID" => ChunkType::Acid,
         _ => ChunkType::Unknown(String::from_utf8_lossy(&tag).into_owned()),
     }
 }
 
+/// Position and size of the audio in the 'data' chunk of a file.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct DataLocation {
+    /// Offset of the first byte of audio from the start of the file
+    pub offset: u64,
+    /// Size of the audio in bytes, from the chunk header
+    pub size: u32,
+}
+
 pub struct RiffFile {
     pub filename: String,
     pub chunks: Vec<RiffChunk>,
@@ -80,20 +93,42 @@ pub struct RiffFile {
 impl RiffFile {
     pub fn len(&self) -> usize {
         // (4 for WAVE header chunk, RIFF chunk not included)
-        4 + self.chunks.iter().fold(0, |acc, &ref chunk| {
+        4 + self.chunks.iter().fold(0, |acc, chunk| {
             acc + super::utils::padded_size(chunk.len() as u32) as usize + 8
         }) // add 8 bytes for each chunks header
     }
 
-    pub fn read(mut reader: fs::File, filename: String) -> Result<Self, io::Error> {
+    pub fn is_empty(&self) -> bool {
+        self.chunks.is_empty()
+    }
+
+    pub fn read(reader: fs::File, filename: String) -> Result<Self, io::Error> {
+        Self::read_chunks(reader, filename, false).map(|(riff_file, _)| riff_file)
+    }
+
+    /// Reads all chunks except the audio of the 'data' chunk, which is skipped and located.
+    ///
+    /// The 'data' chunk is kept in place with no data, so the file must not be written back.
+    pub fn read_metadata(
+        reader: fs::File,
+        filename: String,
+    ) -> Result<(Self, Option<DataLocation>), io::Error> {
+        Self::read_chunks(reader, filename, true)
+    }
+
+    fn read_chunks(
+        mut reader: fs::File,
+        filename: String,
+        skip_data: bool,
+    ) -> Result<(Self, Option<DataLocation>), io::Error> {
         // don't store stuff like the RIFF header chunk as it'll be regenerated on output
         {
             // read RIFF header
             let mut tag = [0u8; 4]; // header tag
-            reader.read(&mut tag)?;
+            reader.read_exact(&mut tag)?;
 
             if &tag != b"RIFF" {
-                return Err(Error::new(ErrorKind::Other, "no RIFF tag found"));
+                return Err(Error::other("no RIFF tag found"));
             }
         }
 
@@ -102,14 +137,15 @@ impl RiffFile {
         {
             // read WAVE header
             let mut tag = [0u8; 4]; // header tag
-            reader.read(&mut tag)?;
+            reader.read_exact(&mut tag)?;
 
             if &tag != b"WAVE" {
-                return Err(Error::new(ErrorKind::Other, "no WAVE tag found"));
+                return Err(Error::other("no WAVE tag found"));
             }
         }
 
         let mut chunks = Vec::new();
+        let mut data_location = None;
 
         loop {
             // read chunks
@@ -138,6 +174,23 @@ impl RiffFile {
                 Ok(length) => length,
             };
 
+            let header = header_to_rifftype(tag);
+            if skip_data && header == ChunkType::Data {
+                let offset = reader.stream_position()?;
+                data_location = Some(DataLocation {
+                    offset,
+                    size: chunk_len,
+                });
+                reader.seek(SeekFrom::Current(
+                    super::utils::padded_size(chunk_len) as i64
+                ))?;
+                chunks.push(RiffChunk {
+                    data: Vec::new(),
+                    header,
+                });
+                continue;
+            }
+
             let chunk = Cursor::new(super::utils::read_bytes(
                 &mut reader,
                 super::utils::padded_size(chunk_len) as usize,
@@ -145,11 +198,11 @@ impl RiffFile {
 
             chunks.push(RiffChunk {
                 data: chunk.into_inner(),
-                header: header_to_rifftype(tag),
+                header,
             });
         }
 
-        Ok(RiffFile { filename, chunks })
+        Ok((RiffFile { filename, chunks }, data_location))
     }
 
     pub fn validate(&self) -> Result<(), Error> {
@@ -160,15 +213,15 @@ impl RiffFile {
         self.validate()?;
 
         // RIFF, WAVE, FMT, DATA chunks
-        writer.write(b"RIFF")?; // RIFF tag
+        writer.write_all(b"RIFF")?; // RIFF tag
         writer.write_u32::<LittleEndian>(self.len() as u32)?; // file size (not including RIFF chunk of 8 bytes)
-        writer.write(b"WAVE")?;
+        writer.write_all(b"WAVE")?;
 
         for chunk in self.chunks.iter() {
             let header = chunk.header.clone();
             let chunk_len = chunk.len() as u32;
 
-            writer.write(&header.to_tag())?;
+            writer.write_all(&header.to_tag())?;
             writer.write_u32::<LittleEndian>(chunk.len() as u32)?;
 
             if utils::padded_size(chunk_len) != chunk_len {
@@ -178,9 +231,9 @@ impl RiffFile {
                     &mut padded_chunk_data,
                     (utils::padded_size(chunk_len) - chunk_len) as usize,
                 );
-                writer.write(&padded_chunk_data)?;
+                writer.write_all(&padded_chunk_data)?;
             } else {
-                writer.write(&chunk.data)?;
+                writer.write_all(&chunk.data)?;
             }
         }
 
@@ -191,8 +244,52 @@ impl RiffFile {
         self.chunks.iter().find(|c| c.header == chunktype)
     }
 
+    pub fn find_chunks_by_type(&self, chunktype: ChunkType) -> impl Iterator<Item = &RiffChunk> {
+        self.chunks.iter().filter(move |c| c.header == chunktype)
+    }
+
+    /// Removes every chunk of the chunk's type and appends the chunk at the end.
+    ///
+    /// See [`RiffFile::replace_chunk_by_type`] to keep the position of the chunk.
     pub fn add_or_replace_chunk_by_type(&mut self, chunk: RiffChunk) {
         self.chunks.retain(|c| c.header != chunk.header);
         self.chunks.push(chunk);
     }
+
+    /// Replaces the first chunk of the chunk's type where it is and removes any others,
+    /// or appends the chunk if there is none.
+    pub fn replace_chunk_by_type(&mut self, chunk: RiffChunk) {
+        match self.chunk_index_by_type(chunk.header.clone()) {
+            Some(index) => {
+                for later in (index + 1..self.chunks.len()).rev() {
+                    if self.chunks[later].header == chunk.header {
+                        self.chunks.remove(later);
+                    }
+                }
+                self.chunks[index] = chunk;
+            }
+            None => self.chunks.push(chunk),
+        }
+    }
+
+    /// Position of the first chunk of a type.
+    pub fn chunk_index_by_type(&self, chunktype: ChunkType) -> Option<usize> {
+        self.chunks.iter().position(|c| c.header == chunktype)
+    }
+
+    /// Inserts a chunk at a position, before the chunk that was there.
+    ///
+    /// # Panics
+    /// If `index` is greater than the number of chunks.
+    pub fn insert_chunk(&mut self, index: usize, chunk: RiffChunk) {
+        self.chunks.insert(index, chunk);
+    }
+
+    /// Removes and returns the chunk at a position, keeping the order of the others.
+    ///
+    /// # Panics
+    /// If `index` is out of bounds.
+    pub fn remove_chunk(&mut self, index: usize) -> RiffChunk {
+        self.chunks.remove(index)
+    }
 }
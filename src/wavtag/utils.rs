@@ -2,7 +2,7 @@ use std::fs::File;
 use std::io;
 use std::io::Read;
 
-pub fn read_bytes(ref mut reader: &mut File, n: usize) -> io::Result<Vec<u8>> {
+pub fn read_bytes(reader: &mut File, n: usize) -> io::Result<Vec<u8>> {
     // We allocate a runtime fixed size buffer, and we are going to read
     // into it, so zeroing or filling the buffer is a waste. This method
     // is safe, because the contents of the buffer are only exposed when
@@ -16,13 +16,13 @@ pub fn read_bytes(ref mut reader: &mut File, n: usize) -> io::Result<Vec<u8>> {
     Ok(buf)
 }
 
-static NSTRS: &'static str = "C C#D D#E F F#G G#A A#B ";
+static NSTRS: &str = "C C#D D#E F F#G G#A A#B ";
 
 /// convert a midi note number to a name
 pub fn note_num_to_name(num: u32) -> String {
-    let oct = (num as f32 / 12 as f32).floor() - 2.0;
+    let oct = (num as f32 / 12.0).floor() - 2.0;
     let nmt = ((num % 12) * 2) as usize;
-    let slice = if NSTRS.as_bytes()[nmt + 1] == ' ' as u8 {
+    let slice = if NSTRS.as_bytes()[nmt + 1] == b' ' {
         &NSTRS[nmt..(nmt + 1)]
     } else {
         &NSTRS[nmt..(nmt + 2)]
@@ -31,7 +31,7 @@ pub fn note_num_to_name(num: u32) -> String {
 }
 
 pub fn padded_size(size: u32) -> u32 {
-    ((size + 1) / 2) * 2
+    size.div_ceil(2) * 2
 }
 
 pub fn pad_vec(v: &mut Vec<u8>, size: usize) {
@@ -60,4 +60,3 @@ pub fn dir_as_string(path: &str) -> String {
         .to_string_lossy()
         .into_owned()
 }
-
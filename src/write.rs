@@ -0,0 +1,562 @@
+//! RIFF chunks built from markers and text tags.
+//!
+//! These are the chunks REAPER writes for markers and regions: a `cue ` point for every
+//! marker, a `LIST`-`adtl` chunk with their names as `labl` labels, and a `smpl` chunk
+//! with a loop for every region. [`info_chunk`] builds the `LIST`-`INFO` text tags.
+
+use std::collections::BTreeMap;
+use std::fs::{self, File};
+use std::io::{self, BufWriter, Read, Seek, SeekFrom, Write};
+use std::path::{Path, PathBuf};
+
+use byteorder::{LittleEndian, WriteBytesExt};
+
+use crate::wavtag::{
+    ChunkType, DataLocation, LoopType, RiffChunk, RiffFile, SampleLoop, SamplerChunk, utils,
+};
+use crate::{Marker, MarkerType, ParseError, WavData};
+
+/// WAVE format tags whose audio can be cut at any sample frame.
+const PCM: u16 = 1;
+const IEEE_FLOAT: u16 = 3;
+const EXTENSIBLE: u16 = 0xFFFE;
+
+/// Builds the `cue ` chunk, with a cue point at the start of every marker.
+pub fn cue_chunk(markers: &[Marker]) -> RiffChunk {
+    let mut data = (markers.len() as u32).to_le_bytes().to_vec();
+    for marker in markers {
+        // dwIdentifier, dwPosition, fccChunk, dwChunkStart, dwBlockStart, dwSampleOffset
+        data.extend(marker.id.to_le_bytes());
+        data.extend(marker.start.to_le_bytes());
+        data.extend(b"data");
+        data.extend([0; 8]);
+        data.extend(marker.start.to_le_bytes());
+    }
+    RiffChunk {
+        header: ChunkType::Cue,
+        data,
+    }
+}
+
+/// Builds the `LIST`-`adtl` chunk, with a `labl` label for every marker.
+pub fn adtl_chunk(markers: &[Marker]) -> RiffChunk {
+    let mut data = b"adtl".to_vec();
+    for marker in markers {
+        let mut text = marker.name.as_bytes().to_vec();
+        text.push(0);
+        push_subchunk(
+            &mut data,
+            b"labl",
+            &[&marker.id.to_le_bytes()[..], &text].concat(),
+        );
+    }
+    RiffChunk {
+        header: ChunkType::List,
+        data,
+    }
+}
+
+/// Builds a `smpl` chunk with a forward loop for every region, as REAPER does.
+pub fn sampler_chunk(markers: &[Marker]) -> SamplerChunk {
+    SamplerChunk {
+        sample_loops: markers
+            .iter()
+            .filter(|marker| marker.r#type == MarkerType::Region)
+            .map(|marker| SampleLoop {
+                id: marker.id,
+                loop_type: LoopType::Forward,
+                start: marker.start,
+                end: marker.end.unwrap_or(marker.start),
+                fraction: 0,
+                play_count: 0,
+            })
+            .collect(),
+        ..SamplerChunk::default()
+    }
+}
+
+/// Builds the `cue `, `LIST`-`adtl` and, if there are regions, `smpl` chunks of markers.
+pub fn marker_chunks(markers: &[Marker]) -> Vec<RiffChunk> {
+    let mut chunks = vec![cue_chunk(markers), adtl_chunk(markers)];
+    let sampler = sampler_chunk(markers);
+    if !sampler.sample_loops.is_empty() {
+        chunks.push(RiffChunk {
+            header: ChunkType::Sampler,
+            data: sampler.serialise(),
+        });
+    }
+    chunks
+}
+
+/// Replaces the marker chunks of a RIFF file with those of `markers`.
+///
+/// The `cue `, `plst`, `labl` and `note` chunks and the `LIST`-`adtl` chunk are removed, and
+/// new ones are inserted where the first of them was, or at the end. The loops of an
+/// existing `smpl` chunk are replaced and its other fields kept; a `smpl` chunk is only
+/// added if there are regions.
+///
+/// # Errors
+/// * [`ParseError::Io`] - If the existing `smpl` chunk is invalid
+pub fn replace_marker_chunks(
+    riff_file: &mut RiffFile,
+    markers: &[Marker],
+) -> Result<(), ParseError> {
+    let sample_loops = sampler_chunk(markers).sample_loops;
+    let mut has_sampler = false;
+    for chunk in &mut riff_file.chunks {
+        if chunk.header == ChunkType::Sampler {
+            let mut existing = SamplerChunk::from_chunk(chunk)?;
+            existing.sample_loops = sample_loops.clone();
+            chunk.data = existing.serialise();
+            has_sampler = true;
+        }
+    }
+
+    let is_marker_chunk = |chunk: &RiffChunk| match chunk.header {
+        ChunkType::Cue | ChunkType::Playlist | ChunkType::Label | ChunkType::Note => true,
+        ChunkType::List => chunk.data.starts_with(b"adtl"),
+        _ => false,
+    };
+    let mut position = None;
+    let mut index = 0;
+    while index < riff_file.chunks.len() {
+        if is_marker_chunk(&riff_file.chunks[index]) {
+            riff_file.remove_chunk(index);
+            position.get_or_insert(index);
+        } else {
+            index += 1;
+        }
+    }
+
+    let mut chunks = Vec::new();
+    if !markers.is_empty() {
+        chunks.push(cue_chunk(markers));
+        chunks.push(adtl_chunk(markers));
+    }
+    if !has_sampler && !sample_loops.is_empty() {
+        chunks.push(RiffChunk {
+            header: ChunkType::Sampler,
+            data: SamplerChunk {
+                sample_loops,
+                ..SamplerChunk::default()
+            }
+            .serialise(),
+        });
+    }
+    let position = position.unwrap_or(riff_file.chunks.len());
+    for (offset, chunk) in chunks.into_iter().enumerate() {
+        riff_file.insert_chunk(position + offset, chunk);
+    }
+    Ok(())
+}
+
+/// Builds the `LIST`-`INFO` chunk from text tags such as `INAM` and `ITRK`.
+///
+/// Tags must have four-character IDs; others are skipped.
+pub fn info_chunk(info: &BTreeMap<String, String>) -> RiffChunk {
+    let mut data = b"INFO".to_vec();
+    for (tag, value) in info {
+        let Ok(tag) = <[u8; 4]>::try_from(tag.as_bytes()) else {
+            continue;
+        };
+        let mut text = value.as_bytes().to_vec();
+        text.push(0);
+        push_subchunk(&mut data, &tag, &text);
+    }
+    RiffChunk {
+        header: ChunkType::List,
+        data,
+    }
+}
+
+/// Appends a subchunk to a `LIST` chunk, padded to an even size.
+fn push_subchunk(data: &mut Vec<u8>, tag: &[u8; 4], content: &[u8]) {
+    data.extend(tag);
+    data.extend((content.len() as u32).to_le_bytes());
+    data.extend(content);
+    if content.len() % 2 == 1 {
+        data.push(0);
+    }
+}
+
+/// A WAV file whose metadata is read and whose audio stays on disk to be streamed.
+pub(crate) struct AudioSource {
+    /// Contents of the format chunk
+    pub format: Vec<u8>,
+    /// Bytes per sample frame
+    pub block_align: u64,
+    /// Position of the audio in the file
+    pub location: DataLocation,
+    /// Length of the audio in samples
+    pub length: u32,
+    /// Markers and text tags of the file
+    pub data: WavData,
+}
+
+impl AudioSource {
+    /// Reads the metadata of a WAV file with PCM or float audio.
+    ///
+    /// `action` names the operation in the error for compressed audio.
+    pub fn open(path: &str, action: &str) -> Result<Self, ParseError> {
+        let (riff_file, location) = RiffFile::read_metadata(File::open(path)?, path.to_string())
+            .map_err(crate::riff_error)?;
+        let location = location.ok_or_else(|| ParseError::Other("no 'data' chunk found".into()))?;
+        let format = riff_file
+            .find_chunk_by_type(ChunkType::Format)
+            .ok_or(ParseError::MissingFormatChunk)?;
+        if format.data.len() < 16 {
+            return Err(ParseError::InvalidFormatChunk(format.data.len()));
+        }
+        let format_tag = u16::from_le_bytes([format.data[0], format.data[1]]);
+        if ![PCM, IEEE_FLOAT, EXTENSIBLE].contains(&format_tag) {
+            return Err(ParseError::Other(format!(
+                "cannot {action} audio in format 0x{format_tag:04X}, only PCM and float"
+            )));
+        }
+        let block_align = u16::from_le_bytes([format.data[12], format.data[13]]) as u64;
+        if block_align == 0 {
+            return Err(ParseError::InvalidFormatChunk(format.data.len()));
+        }
+        let length = crate::get_audio_length(&riff_file, location.size).unwrap_or(0);
+        Ok(AudioSource {
+            format: format.data.clone(),
+            block_align,
+            location,
+            length,
+            data: crate::parse_markers_from_riff(&riff_file, path, Some(length))?,
+        })
+    }
+
+    /// The format chunk, to write into another file.
+    pub fn format_chunk(&self) -> RiffChunk {
+        RiffChunk {
+            header: ChunkType::Format,
+            data: self.format.clone(),
+        }
+    }
+}
+
+/// Writes a WAV file with the format, `INFO` tags and markers, and `size` bytes of audio
+/// from a reader.
+///
+/// The tags come before the audio and the markers after it, as REAPER writes them.
+pub(crate) fn write_wav(
+    file: &mut File,
+    format: &RiffChunk,
+    info: &BTreeMap<String, String>,
+    markers: &[Marker],
+    audio: impl Read,
+    size: u32,
+) -> Result<(), ParseError> {
+    let mut before = vec![RiffChunk {
+        header: format.header.clone(),
+        data: format.data.clone(),
+    }];
+    if !info.is_empty() {
+        before.push(info_chunk(info));
+    }
+    let after = if markers.is_empty() {
+        Vec::new()
+    } else {
+        marker_chunks(markers)
+    };
+
+    write_riff(file, &before, audio, size, &after)
+}
+
+/// Writes a RIFF WAVE file: the chunks in `before`, a `data` chunk with `size` bytes of
+/// audio from a reader, and the chunks in `after`.
+fn write_riff(
+    file: &mut File,
+    before: &[RiffChunk],
+    mut audio: impl Read,
+    size: u32,
+    after: &[RiffChunk],
+) -> Result<(), ParseError> {
+    let riff_size =
+        4 + chunks_size(before) + 8 + utils::padded_size(size) as u64 + chunks_size(after);
+    let riff_size = u32::try_from(riff_size).map_err(|_| {
+        ParseError::Other("the file would exceed the 4 GiB limit of WAV files".into())
+    })?;
+
+    let mut writer = BufWriter::new(file);
+    writer.write_all(b"RIFF")?;
+    writer.write_u32::<LittleEndian>(riff_size)?;
+    writer.write_all(b"WAVE")?;
+    write_chunks(&mut writer, before)?;
+    writer.write_all(b"data")?;
+    writer.write_u32::<LittleEndian>(size)?;
+    let copied = io::copy(&mut audio, &mut writer)?;
+    if copied != size as u64 {
+        return Err(ParseError::Io(io::Error::new(
+            io::ErrorKind::UnexpectedEof,
+            format!("expected {size} bytes of audio, got {copied}"),
+        )));
+    }
+    if size % 2 == 1 {
+        writer.write_all(&[0])?;
+    }
+    write_chunks(&mut writer, after)?;
+    writer.flush()?;
+    Ok(())
+}
+
+/// Size of chunks in a file, with their headers and padding.
+fn chunks_size<'a>(chunks: impl IntoIterator<Item = &'a RiffChunk>) -> u64 {
+    chunks
+        .into_iter()
+        .map(|chunk| 8 + utils::padded_size(chunk.len() as u32) as u64)
+        .sum()
+}
+
+/// Chunks in a file, with their headers and padding.
+fn chunk_bytes<'a>(chunks: impl IntoIterator<Item = &'a RiffChunk>) -> Vec<u8> {
+    let mut bytes = Vec::new();
+    write_chunks(&mut bytes, chunks).expect("writing to memory");
+    bytes
+}
+
+/// How [`update_metadata`] wrote a file.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Update {
+    /// The chunks were updated in place and the audio left untouched
+    InPlace,
+    /// The whole file was rewritten, because the chunks before the audio grew
+    Rewritten,
+}
+
+/// Chunk types that only reserve space.
+fn is_padding(chunk: &RiffChunk) -> bool {
+    matches!(&chunk.header, ChunkType::Unknown(tag) if tag == "JUNK" || tag == "PAD ")
+}
+
+/// Fits chunks into `space` bytes: as they are, or without their padding chunks if
+/// needed, followed by a `JUNK` chunk filling what is left.
+fn fit_chunks(chunks: &[RiffChunk], space: u64) -> Option<Vec<u8>> {
+    let mut chunks: Vec<&RiffChunk> = chunks.iter().collect();
+    if chunks_size(chunks.iter().copied()) > space {
+        chunks.retain(|chunk| !is_padding(chunk));
+    }
+    let mut bytes = chunk_bytes(chunks);
+    let size = bytes.len() as u64;
+    if size + 8 <= space {
+        let filler = RiffChunk {
+            header: ChunkType::Unknown("JUNK".to_string()),
+            data: vec![0; (space - size - 8) as usize],
+        };
+        bytes.extend(chunk_bytes(&[filler]));
+    }
+    (bytes.len() as u64 == space).then_some(bytes)
+}
+
+/// A path in the same directory as `path`, with `suffix` appended to the file name.
+pub(crate) fn sibling(path: &Path, suffix: &str) -> PathBuf {
+    let mut name = path.file_name().unwrap_or_default().to_os_string();
+    name.push(suffix);
+    path.with_file_name(name)
+}
+
+/// Changes the chunks of a WAV file without loading or, when possible, copying its audio.
+///
+/// `edit` gets the file with an empty `data` chunk, which it must keep. REAPER writes
+/// markers after the audio, so these are overwritten in place and the file is truncated
+/// or extended to fit. Chunks before the audio are overwritten if they fit into the space
+/// of the old ones, including any `JUNK` or `PAD ` chunks, with a `JUNK` chunk filling the
+/// rest. Otherwise the file is rewritten to a temporary file next to it, which is then
+/// renamed over it.
+///
+/// # Arguments
+/// * `path` - Path of the WAV file
+/// * `edit` - Changes the chunks
+///
+/// # Returns
+/// * `Result<(T, Update), ParseError>` - The result of `edit`, and how the file was written
+///
+/// # Errors
+/// * [`ParseError::Io`] - If the file cannot be read or written
+/// * [`ParseError::Other`] - If there is no 'data' chunk, `edit` removed it, or the file
+///   would exceed the 4 GiB limit of WAV files
+///
+/// # Note
+/// An update in place cannot be undone if it is interrupted, though the audio is never
+/// touched.
+pub fn update_metadata<T>(
+    path: &str,
+    edit: impl FnOnce(&mut RiffFile) -> Result<T, ParseError>,
+) -> Result<(T, Update), ParseError> {
+    let (mut riff_file, location) =
+        RiffFile::read_metadata(File::open(path)?, path.to_string()).map_err(crate::riff_error)?;
+    let location = location.ok_or_else(|| ParseError::Other("no 'data' chunk found".into()))?;
+    let no_data = || ParseError::Other("the 'data' chunk was removed".into());
+
+    let data_index = riff_file
+        .chunk_index_by_type(ChunkType::Data)
+        .ok_or_else(no_data)?;
+    let old_before = chunk_bytes(&riff_file.chunks[..data_index]);
+    let audio_end = location.offset + utils::padded_size(location.size) as u64;
+    // The chunks must be laid out as read, with nothing skipped or cut short
+    let consistent = location.offset == 12 + old_before.len() as u64 + 8
+        && audio_end + chunks_size(&riff_file.chunks[data_index + 1..])
+            == fs::metadata(path)?.len();
+
+    let value = edit(&mut riff_file)?;
+    let data_index = riff_file
+        .chunk_index_by_type(ChunkType::Data)
+        .ok_or_else(no_data)?;
+    let before = &riff_file.chunks[..data_index];
+    let after = &riff_file.chunks[data_index + 1..];
+    let path = Path::new(path);
+
+    let new_before = chunk_bytes(before);
+    let new_before = if new_before == old_before {
+        Some(None)
+    } else {
+        fit_chunks(before, old_before.len() as u64).map(Some)
+    };
+    if let (true, Some(new_before)) = (consistent, new_before) {
+        let after = chunk_bytes(after);
+        let riff_size = u32::try_from(audio_end + after.len() as u64 - 8).map_err(|_| {
+            ParseError::Other("the file would exceed the 4 GiB limit of WAV files".into())
+        })?;
+        let mut file = fs::OpenOptions::new().write(true).open(path)?;
+        file.seek(SeekFrom::Start(audio_end))?;
+        file.write_all(&after)?;
+        file.set_len(audio_end + after.len() as u64)?;
+        if let Some(new_before) = new_before {
+            file.seek(SeekFrom::Start(12))?;
+            file.write_all(&new_before)?;
+        }
+        file.seek(SeekFrom::Start(4))?;
+        file.write_u32::<LittleEndian>(riff_size)?;
+        file.sync_all()?;
+        return Ok((value, Update::InPlace));
+    }
+
+    replace_file(path, |file| copy_riff(file, path, &location, before, after))?;
+    Ok((value, Update::Rewritten))
+}
+
+/// Writes a copy of a WAV file with its chunks changed, without loading its audio.
+///
+/// `edit` gets the file with an empty `data` chunk, which it must keep. If `output` is
+/// `input`, the file is updated with [`update_metadata`]. Otherwise the audio is copied
+/// from `input` to a temporary file next to `output`, which then replaces it.
+///
+/// # Arguments
+/// * `input` - Path of the WAV file
+/// * `output` - Path of the file to write
+/// * `edit` - Changes the chunks
+///
+/// # Returns
+/// * `Result<T, ParseError>` - The result of `edit`
+///
+/// # Errors
+/// * [`ParseError::Io`] - If a file cannot be read or written
+/// * [`ParseError::Other`] - If there is no 'data' chunk, `edit` removed it, or the file
+///   would exceed the 4 GiB limit of WAV files
+pub fn copy_with_metadata<T>(
+    input: &str,
+    output: &str,
+    edit: impl FnOnce(&mut RiffFile) -> Result<T, ParseError>,
+) -> Result<T, ParseError> {
+    if let (Ok(input_path), Ok(output_path)) = (fs::canonicalize(input), fs::canonicalize(output))
+        && input_path == output_path
+    {
+        return update_metadata(input, edit).map(|(value, _)| value);
+    }
+
+    let (mut riff_file, location) = RiffFile::read_metadata(File::open(input)?, input.to_string())
+        .map_err(crate::riff_error)?;
+    let location = location.ok_or_else(|| ParseError::Other("no 'data' chunk found".into()))?;
+    let value = edit(&mut riff_file)?;
+    let data_index = riff_file
+        .chunk_index_by_type(ChunkType::Data)
+        .ok_or_else(|| ParseError::Other("the 'data' chunk was removed".into()))?;
+    let (before, after) = riff_file.chunks.split_at(data_index);
+    replace_file(Path::new(output), |file| {
+        copy_riff(file, Path::new(input), &location, before, &after[1..])
+    })?;
+    Ok(value)
+}
+
+/// Writes the chunks around the audio of a file located at `location` in `input`.
+fn copy_riff(
+    file: &mut File,
+    input: &Path,
+    location: &DataLocation,
+    before: &[RiffChunk],
+    after: &[RiffChunk],
+) -> Result<(), ParseError> {
+    let mut audio = File::open(input)?;
+    audio.seek(SeekFrom::Start(location.offset))?;
+    write_riff(
+        file,
+        before,
+        audio.take(location.size as u64),
+        location.size,
+        after,
+    )
+}
+
+/// Writes a file through a new temporary file next to it, which is renamed over it once
+/// complete, so that a failed write leaves the file as it was.
+///
+/// The temporary file gets a name no other file has, and the permissions of the file it
+/// replaces, if there is one.
+pub(crate) fn replace_file(
+    path: &Path,
+    write: impl FnOnce(&mut File) -> Result<(), ParseError>,
+) -> Result<(), ParseError> {
+    let (temp, mut file) = create_temp(path)?;
+    let written = (|| {
+        write(&mut file)?;
+        file.sync_all()?;
+        if let Ok(metadata) = fs::metadata(path) {
+            fs::set_permissions(&temp, metadata.permissions())?;
+        }
+        Ok(())
+    })();
+    drop(file);
+    if let Err(error) = written {
+        let _ = fs::remove_file(&temp);
+        return Err(error);
+    }
+    fs::rename(&temp, path)?;
+    Ok(())
+}
+
+/// Creates a new file next to `path`, named after it with a suffix not taken yet.
+fn create_temp(path: &Path) -> io::Result<(PathBuf, File)> {
+    for attempt in 0..100 {
+        let temp = sibling(path, &format!(".{}-{attempt}.tmp", std::process::id()));
+        match fs::OpenOptions::new()
+            .write(true)
+            .create_new(true)
+            .open(&temp)
+        {
+            Ok(file) => return Ok((temp, file)),
+            Err(error) if error.kind() == io::ErrorKind::AlreadyExists => continue,
+            Err(error) => return Err(error),
+        }
+    }
+    Err(io::Error::new(
+        io::ErrorKind::AlreadyExists,
+        format!("no free temporary file name next to {}", path.display()),
+    ))
+}
+
+/// Writes chunks with their headers, padded to an even size.
+fn write_chunks<'a>(
+    writer: &mut impl Write,
+    chunks: impl IntoIterator<Item = &'a RiffChunk>,
+) -> io::Result<()> {
+    for chunk in chunks {
+        writer.write_all(&chunk.header.clone().to_tag())?;
+        writer.write_u32::<LittleEndian>(chunk.len() as u32)?;
+        writer.write_all(&chunk.data)?;
+        if chunk.len() % 2 == 1 {
+            writer.write_all(&[0])?;
+        }
+    }
+    Ok(())
+}
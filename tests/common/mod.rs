@@ -0,0 +1,58 @@
+//! Helpers shared by the tests that edit audio.
+#![allow(dead_code)]
+
+use reaper_regions::wavtag::{ChunkType, RiffChunk, RiffFile};
+use reaper_regions::{Marker, write};
+use std::collections::BTreeMap;
+use std::ops::Range;
+
+/// Sample rate of the test files, so that a sample is a millisecond.
+pub const SAMPLE_RATE: u32 = 1000;
+
+pub fn marker(id: u32, name: &str, start: u32, end: Option<u32>) -> Marker {
+    Marker::new(id, name.to_string(), start, end, SAMPLE_RATE)
+}
+
+/// A 16-bit mono file whose samples hold the values of `samples`, with text tags and
+/// markers.
+pub fn pcm_wav(
+    samples: Range<u16>,
+    info: &BTreeMap<String, String>,
+    markers: &[Marker],
+) -> RiffFile {
+    let mut format = 1u16.to_le_bytes().to_vec(); // PCM
+    format.extend(1u16.to_le_bytes()); // mono
+    format.extend(SAMPLE_RATE.to_le_bytes());
+    format.extend((SAMPLE_RATE * 2).to_le_bytes());
+    format.extend(2u16.to_le_bytes()); // block align
+    format.extend(16u16.to_le_bytes());
+
+    let mut chunks = vec![RiffChunk {
+        header: ChunkType::Format,
+        data: format,
+    }];
+    if !info.is_empty() {
+        chunks.push(write::info_chunk(info));
+    }
+    chunks.push(RiffChunk {
+        header: ChunkType::Data,
+        data: samples.flat_map(u16::to_le_bytes).collect(),
+    });
+    if !markers.is_empty() {
+        chunks.extend(write::marker_chunks(markers));
+    }
+    RiffFile {
+        filename: String::new(),
+        chunks,
+    }
+}
+
+/// The samples of a 16-bit mono file.
+pub fn samples(riff: &RiffFile) -> Vec<u16> {
+    riff.find_chunk_by_type(ChunkType::Data)
+        .unwrap()
+        .data
+        .chunks_exact(2)
+        .map(|sample| u16::from_le_bytes([sample[0], sample[1]]))
+        .collect()
+}
@@ -0,0 +1,15 @@
+FILE "3-markers-3-regions-overlapping_stripped.wav" WAVE
+  TRACK 01 AUDIO
+    TITLE "Region 1"
+    INDEX 00 00:00:00
+    INDEX 01 00:06:04
+    INDEX 02 00:07:74
+  TRACK 02 AUDIO
+    TITLE "Region 2"
+    INDEX 00 00:18:35
+    INDEX 01 00:22:07
+  TRACK 03 AUDIO
+    TITLE "Region 3"
+    INDEX 00 00:31:13
+    INDEX 01 00:47:42
+    INDEX 02 00:49:32
@@ -0,0 +1,13 @@
+-- Recreates the markers and regions of a rendered file in the current project.
+-- Positions are in seconds, relative to offset.
+local offset = 0
+
+reaper.Undo_BeginBlock()
+reaper.AddProjectMarker2(0, true, offset + 6.056416666666666, offset + 18.466125, "Region 1", 1, 0)
+reaper.AddProjectMarker2(0, false, offset + 7.980208333333334, 0, "Marker 1", 2, 0)
+reaper.AddProjectMarker2(0, true, offset + 22.088104166666668, offset + 31.172708333333333, "Region 2", 3, 0)
+reaper.AddProjectMarker2(0, false, offset + 33.27464583333333, 0, "Marker 2", 4, 0)
+reaper.AddProjectMarker2(0, true, offset + 47.56064583333333, offset + 57.41716666666667, "Region 3", 5, 0)
+reaper.AddProjectMarker2(0, false, offset + 49.42508333333333, 0, "Marker 3", 6, 0)
+reaper.UpdateArrange()
+reaper.Undo_EndBlock("Add markers from rendered file", -1)
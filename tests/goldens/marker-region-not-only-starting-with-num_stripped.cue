@@ -0,0 +1,11 @@
+FILE "marker-region-not-only-starting-with-num_stripped.wav" WAVE
+  TRACK 01 AUDIO
+    TITLE "Region One"
+    INDEX 00 00:00:00
+    INDEX 01 01:45:48
+    INDEX 02 02:10:26
+    INDEX 03 02:39:13
+  TRACK 02 AUDIO
+    TITLE "#2 region"
+    INDEX 00 03:32:55
+    INDEX 01 03:46:48
@@ -0,0 +1,11 @@
+-- Recreates the markers and regions of a rendered file in the current project.
+-- Positions are in seconds, relative to offset.
+local offset = 0
+
+reaper.Undo_BeginBlock()
+reaper.AddProjectMarker2(0, true, offset + 105.6445625, offset + 212.73633333333333, "Region One", 1, 0)
+reaper.AddProjectMarker2(0, false, offset + 130.34791666666666, 0, "marker one", 2, 0)
+reaper.AddProjectMarker2(0, false, offset + 159.17420833333333, 0, "#2 marker", 3, 0)
+reaper.AddProjectMarker2(0, true, offset + 226.63439583333334, offset + 269.7081875, "#2 region", 4, 0)
+reaper.UpdateArrange()
+reaper.Undo_EndBlock("Add markers from rendered file", -1)
@@ -0,0 +1,5 @@
+FILE "marker-region-starting-with-num_stripped.wav" WAVE
+  TRACK 01 AUDIO
+    TITLE "#2 region"
+    INDEX 00 00:00:00
+    INDEX 01 03:46:48
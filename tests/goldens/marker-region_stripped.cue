@@ -0,0 +1,6 @@
+FILE "marker-region_stripped.wav" WAVE
+  TRACK 01 AUDIO
+    TITLE "Region One"
+    INDEX 00 00:00:00
+    INDEX 01 01:45:48
+    INDEX 02 02:10:26
@@ -0,0 +1 @@
+FILE "no-markers-or-regions_stripped.wav" WAVE
@@ -0,0 +1,7 @@
+-- Recreates the markers and regions of a rendered file in the current project.
+-- Positions are in seconds, relative to offset.
+local offset = 0
+
+reaper.Undo_BeginBlock()
+reaper.UpdateArrange()
+reaper.Undo_EndBlock("Add markers from rendered file", -1)
@@ -0,0 +1,5 @@
+FILE "one-marker-only_stripped.wav" WAVE
+  TRACK 01 AUDIO
+    TITLE "One Marker Only"
+    INDEX 00 00:00:00
+    INDEX 01 04:16:55
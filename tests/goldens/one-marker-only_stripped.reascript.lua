@@ -0,0 +1,8 @@
+-- Recreates the markers and regions of a rendered file in the current project.
+-- Positions are in seconds, relative to offset.
+local offset = 0
+
+reaper.Undo_BeginBlock()
+reaper.AddProjectMarker2(0, false, offset + 256.7307916666667, 0, "One Marker Only", 1, 0)
+reaper.UpdateArrange()
+reaper.Undo_EndBlock("Add markers from rendered file", -1)
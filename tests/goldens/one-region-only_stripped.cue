@@ -0,0 +1,5 @@
+FILE "one-region-only_stripped.wav" WAVE
+  TRACK 01 AUDIO
+    TITLE "Region One"
+    INDEX 00 00:00:00
+    INDEX 01 01:45:48
@@ -0,0 +1,10 @@
+FILE "without-bwf-bext-chunk_stripped.wav" WAVE
+  TRACK 01 AUDIO
+    TITLE "Region One"
+    INDEX 00 00:00:00
+    INDEX 01 00:15:12
+    INDEX 02 00:33:21
+  TRACK 02 AUDIO
+    TITLE "Another Region"
+    INDEX 00 00:46:10
+    INDEX 01 01:00:50
@@ -0,0 +1,10 @@
+-- Recreates the markers and regions of a rendered file in the current project.
+-- Positions are in seconds, relative to offset.
+local offset = 0
+
+reaper.Undo_BeginBlock()
+reaper.AddProjectMarker2(0, true, offset + 15.166541666666667, offset + 46.131541666666664, "Region One", 1, 0)
+reaper.AddProjectMarker2(0, false, offset + 33.28210416666667, 0, "Marker One", 2, 0)
+reaper.AddProjectMarker2(0, true, offset + 60.666125, offset + 90.57789583333333, "Another Region", 3, 0)
+reaper.UpdateArrange()
+reaper.Undo_EndBlock("Add markers from rendered file", -1)
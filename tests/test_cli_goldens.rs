@@ -14,6 +14,18 @@ const FORMATS: &[(&str, &str)] = &[
     ("tsv", "tsv"),
     ("psv", "psv"),
     ("human", "human"),
+    ("avid", "avid"),
+    ("audacity", "audacity.txt"),
+    ("cue", "cue"),
+    ("ffmetadata", "ffmetadata.txt"),
+    ("mkvchapters", "mkvchapters.xml"),
+    ("podcast-chapters", "chapters.json"),
+    ("youtube", "youtube.txt"),
+    ("srt", "srt"),
+    ("vtt", "vtt"),
+    ("lrc", "lrc"),
+    ("reaper-csv", "reaper.csv"),
+    ("reascript", "reascript.lua"),
 ];
 
 /// Test helper to run CLI with given arguments
@@ -28,7 +40,7 @@ fn run_cli(wav_path: &Path, format: &str) -> String {
 
     let output = cmd
         .output()
-        .expect(&format!("Failed to run CLI with format {}", format));
+        .unwrap_or_else(|_| panic!("Failed to run CLI with format {}", format));
 
     // Check for successful execution
     assert!(output.status.success(), "CLI failed: {:?}", output);
@@ -53,7 +65,7 @@ fn test_cli_goldens() {
     let fixtures_dir = Path::new("tests/fixtures");
 
     // Find all .wav files in fixtures directory
-    let wav_files: Vec<PathBuf> = fs::read_dir(&fixtures_dir)
+    let wav_files: Vec<PathBuf> = fs::read_dir(fixtures_dir)
         .expect("Failed to read fixtures directory")
         .filter_map(|entry| {
             let entry = entry.ok()?;
@@ -85,7 +97,7 @@ fn test_cli_goldens() {
 
                 // Write golden file
                 fs::write(&golden_file, &output)
-                    .expect(&format!("Failed to write golden file: {:?}", golden_file));
+                    .unwrap_or_else(|_| panic!("Failed to write golden file: {:?}", golden_file));
 
                 println!("  ✓ Updated golden file: {:?}", golden_file);
             } else {
@@ -98,7 +110,7 @@ fn test_cli_goldens() {
                 }
 
                 let expected = fs::read_to_string(&golden_file)
-                    .expect(&format!("Failed to read golden file: {:?}", golden_file));
+                    .unwrap_or_else(|_| panic!("Failed to read golden file: {:?}", golden_file));
 
                 // Compare with simple diff (good enough for most cases)
                 if output.trim() != expected.trim() {
@@ -193,3 +205,306 @@ fn test_cli_debug() {
     assert!(output_str.contains("Found 3 sample loop(s)"));
     assert!(output_str.contains("Cue ID 6"));
 }
+
+/// Test nesting markers as Matroska sub-chapters
+#[test]
+fn test_cli_mkv_nest_markers() {
+    let wav_path = Path::new(env!("CARGO_MANIFEST_DIR"))
+        .join("tests")
+        .join("fixtures")
+        .join("3-markers-3-regions-overlapping_stripped.wav");
+
+    let mut cmd = cargo_bin_cmd!();
+    let output = cmd
+        .arg(&wav_path)
+        .arg("--format")
+        .arg("mkvchapters")
+        .arg("--mkv-nest-markers")
+        .output()
+        .expect("Failed to run CLI");
+
+    assert!(output.status.success());
+    let output_str = String::from_utf8_lossy(&output.stdout);
+
+    // Markers 1 and 3 are inside regions, Marker 2 is not
+    assert_eq!(output_str.matches("\n    <ChapterAtom>").count(), 4);
+    assert_eq!(output_str.matches("\n      <ChapterAtom>").count(), 2);
+    assert!(output_str.contains(
+        "      <ChapterAtom>\n        <ChapterUID>6</ChapterUID>\n        <ChapterTimeStart>00:00:49.425083333</ChapterTimeStart>"
+    ));
+}
+
+/// Test comparing a REAPER project with its render
+#[test]
+fn test_cli_compare() {
+    let fixtures = Path::new(env!("CARGO_MANIFEST_DIR"))
+        .join("tests")
+        .join("fixtures");
+
+    let mut cmd = cargo_bin_cmd!();
+    let output = cmd
+        .arg("compare")
+        .arg(fixtures.join("3-markers-3-regions-overlapping.rpp"))
+        .arg(fixtures.join("3-markers-3-regions-overlapping_stripped.wav"))
+        .output()
+        .expect("Failed to run CLI");
+
+    // Differences are reported with exit status 1
+    assert_eq!(output.status.code(), Some(1));
+    let output_str = String::from_utf8_lossy(&output.stdout);
+    assert_eq!(
+        output_str,
+        "moved: region 'Region 2' from 22.088 s to 23.088 s (+1.000 s), end from 31.173 s to 32.173 s (+1.000 s)\n\
+         renamed: region 'Region 1' at 6.056 s is now 'Intro'\n\
+         missing: marker 'Outro \"cold\"' at 55.000 s is not in the render\n\
+         removed: marker 'Marker 3' at 49.425 s is no longer in the project\n"
+    );
+}
+
+/// Test comparing the markers of two WAV files
+#[test]
+fn test_cli_diff() {
+    let fixtures = Path::new(env!("CARGO_MANIFEST_DIR"))
+        .join("tests")
+        .join("fixtures");
+    let wav = fixtures.join("3-markers-3-regions-overlapping_stripped.wav");
+
+    let output = cargo_bin_cmd!()
+        .arg("diff")
+        .arg(&wav)
+        .arg(&wav)
+        .output()
+        .expect("Failed to run CLI");
+    assert_eq!(output.status.code(), Some(0));
+    assert_eq!(String::from_utf8_lossy(&output.stdout), "No differences\n");
+
+    let output = cargo_bin_cmd!()
+        .arg("diff")
+        .arg(fixtures.join("one-region-only_stripped.wav"))
+        .arg(&wav)
+        .args(["--match", "name", "--json"])
+        .output()
+        .expect("Failed to run CLI");
+    assert_eq!(output.status.code(), Some(1));
+    let json: serde_json::Value = serde_json::from_slice(&output.stdout).unwrap();
+    let changes: Vec<_> = json["changes"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|change| change["change"].as_str().unwrap())
+        .collect();
+    // The removed region of the old file is at 105 s, after all the new markers
+    assert_eq!(
+        changes,
+        [
+            "added", "added", "added", "added", "added", "added", "removed"
+        ]
+    );
+
+    let output = cargo_bin_cmd!()
+        .args(["diff", "missing.wav"])
+        .arg(&wav)
+        .output()
+        .expect("Failed to run CLI");
+    assert_eq!(output.status.code(), Some(2));
+}
+
+/// Test bars:beats positions from a constant tempo
+#[test]
+fn test_cli_time_format_bars() {
+    let wav_path = Path::new(env!("CARGO_MANIFEST_DIR"))
+        .join("tests")
+        .join("fixtures")
+        .join("3-markers-3-regions-overlapping_stripped.wav");
+
+    let mut cmd = cargo_bin_cmd!();
+    let output = cmd
+        .arg(&wav_path)
+        .args(["--time-format", "bars", "--bpm", "90", "--meter", "3/4"])
+        .output()
+        .expect("Failed to run CLI");
+
+    assert!(output.status.success());
+    let output_str = String::from_utf8_lossy(&output.stdout);
+    // 6.056 s at 90 BPM is 9.085 beats: bar 4, beat 1
+    assert!(
+        output_str
+            .contains("Region (ID: 1): 'Region 1'\n  Start: 4.1.081 (6.056s, 290708 samples)\n")
+    );
+}
+
+/// Test selecting markers for an output format, and that every subcommand's options parse
+#[test]
+fn test_cli_selection() {
+    let wav_path = Path::new(env!("CARGO_MANIFEST_DIR"))
+        .join("tests")
+        .join("fixtures")
+        .join("3-markers-3-regions-overlapping_stripped.wav");
+
+    let output = cargo_bin_cmd!()
+        .arg(&wav_path)
+        .args(["--format", "csv", "--no-header", "--type", "region"])
+        .args([
+            "--from",
+            "20s",
+            "--to",
+            "0:50",
+            "--where",
+            "not name:\"Region 3\"",
+        ])
+        .output()
+        .expect("Failed to run CLI");
+    assert!(output.status.success());
+    assert_eq!(
+        String::from_utf8_lossy(&output.stdout),
+        "region,3,Region 2,1060229,1496290,22.088,31.173,9.085,48000\n"
+    );
+
+    let output = cargo_bin_cmd!()
+        .arg(&wav_path)
+        .args(["--where", "type:region and"])
+        .output()
+        .expect("Failed to run CLI");
+    assert!(!output.status.success());
+
+    for command in [
+        "compare",
+        "loops",
+        "split",
+        "concat",
+        "transplant",
+        "diff",
+        "structure",
+        "rename",
+        "delete",
+        "move",
+    ] {
+        let output = cargo_bin_cmd!()
+            .args([command, "--help"])
+            .output()
+            .expect("Failed to run CLI");
+        assert!(output.status.success(), "{command}");
+        assert!(String::from_utf8_lossy(&output.stdout).contains("--name-glob"));
+    }
+}
+
+/// Test that editing commands need a selection
+#[test]
+fn test_cli_edit_needs_selection() {
+    let wav_path = Path::new(env!("CARGO_MANIFEST_DIR"))
+        .join("tests")
+        .join("fixtures")
+        .join("3-markers-3-regions-overlapping_stripped.wav");
+
+    let output = cargo_bin_cmd!()
+        .args(["delete", "--dry-run"])
+        .arg(&wav_path)
+        .output()
+        .expect("Failed to run CLI");
+    assert!(!output.status.success());
+    assert!(String::from_utf8_lossy(&output.stderr).contains("Select the markers to edit"));
+
+    let output = cargo_bin_cmd!()
+        .args(["delete", "--dry-run", "--type", "region"])
+        .arg(&wav_path)
+        .output()
+        .expect("Failed to run CLI");
+    assert!(output.status.success());
+    let output_str = String::from_utf8_lossy(&output.stdout);
+    assert!(output_str.contains("Marker 1"));
+    assert!(!output_str.contains("Region 1"));
+}
+
+/// Test the region structure, as a tree and as nested JSON
+#[test]
+fn test_cli_structure() {
+    let wav_path = Path::new(env!("CARGO_MANIFEST_DIR"))
+        .join("tests")
+        .join("fixtures")
+        .join("3-markers-3-regions-overlapping_stripped.wav");
+
+    let output = cargo_bin_cmd!()
+        .arg("structure")
+        .arg(&wav_path)
+        .output()
+        .expect("Failed to run CLI");
+    assert!(output.status.success());
+    let output_str = String::from_utf8_lossy(&output.stdout);
+    assert!(output_str.starts_with(
+        "region 'Region 1' (ID 1) at 6.056 s to 18.466 s\n  marker 'Marker 1' (ID 2) at 7.980 s\n"
+    ));
+    assert!(output_str.contains("\n  18.466 s to 22.088 s (3.622 s) between IDs 1 and 3\n"));
+    assert!(output_str.ends_with("\nCoverage: 31.351 s\n"));
+
+    let output = cargo_bin_cmd!()
+        .arg(&wav_path)
+        .args(["--format", "json", "--nest"])
+        .output()
+        .expect("Failed to run CLI");
+    assert!(output.status.success());
+    let json: serde_json::Value = serde_json::from_slice(&output.stdout).unwrap();
+    let markers = json["markers"].as_array().unwrap();
+    let ids: Vec<u64> = markers.iter().map(|m| m["id"].as_u64().unwrap()).collect();
+    assert_eq!(ids, [1, 3, 4, 5]);
+    assert_eq!(markers[3]["children"][0]["name"], "Marker 3");
+    assert_eq!(json["sample_rate"], 48000);
+}
+
+/// Test that the sample rate of imported labels must be positive
+#[test]
+fn test_cli_zero_sample_rate() {
+    let labels = std::env::temp_dir().join("reaper-regions-test-zero-rate.txt");
+    std::fs::write(&labels, "1.0\t2.0\tVerse\n").unwrap();
+
+    let output = cargo_bin_cmd!()
+        .arg(&labels)
+        .args(["-i", "audacity", "-s", "0", "-f", "mkvchapters"])
+        .output()
+        .expect("Failed to run CLI");
+    std::fs::remove_file(&labels).unwrap();
+
+    assert!(!output.status.success());
+    assert!(String::from_utf8_lossy(&output.stderr).contains("--sample-rate"));
+}
+
+/// Test that `--bpm` must be finite and positive
+#[test]
+fn test_cli_bpm() {
+    let wav_path = Path::new(env!("CARGO_MANIFEST_DIR"))
+        .join("tests")
+        .join("fixtures")
+        .join("3-markers-3-regions-overlapping_stripped.wav");
+
+    for bpm in ["NaN", "inf", "0", "-5"] {
+        let mut cmd = cargo_bin_cmd!();
+        let output = cmd
+            .arg(&wav_path)
+            .args(["--time-format", "bars", &format!("--bpm={bpm}")])
+            .output()
+            .expect("Failed to run CLI");
+
+        assert!(!output.status.success(), "{bpm}");
+        assert!(String::from_utf8_lossy(&output.stderr).contains("invalid tempo"));
+    }
+}
+
+/// Test that MIDI tempos must be finite and positive
+#[test]
+fn test_cli_midi_tempo() {
+    let wav_path = Path::new(env!("CARGO_MANIFEST_DIR"))
+        .join("tests")
+        .join("fixtures")
+        .join("3-markers-3-regions-overlapping_stripped.wav");
+
+    for tempo in ["NaN", "inf", "0", "-120"] {
+        let mut cmd = cargo_bin_cmd!();
+        let output = cmd
+            .arg(&wav_path)
+            .args(["--format", "midi", &format!("--midi-tempo={tempo}")])
+            .output()
+            .expect("Failed to run CLI");
+
+        assert!(!output.status.success(), "{tempo}");
+        assert!(String::from_utf8_lossy(&output.stderr).contains("invalid tempo"));
+    }
+}
@@ -0,0 +1,141 @@
+//! Tests for concatenating WAV files.
+
+use reaper_regions::concat::{self, ConcatOptions};
+use reaper_regions::parse_markers_from_file;
+use reaper_regions::wavtag::RiffFile;
+use std::collections::BTreeMap;
+use std::fs;
+
+mod common;
+
+use common::{marker, pcm_wav, samples};
+
+#[test]
+fn test_concat_shifts_markers() {
+    let dir = std::env::temp_dir().join("reaper-regions-test-concat");
+    let _ = fs::remove_dir_all(&dir);
+    fs::create_dir_all(&dir).unwrap();
+    let first = dir.join("01 First.wav");
+    let second = dir.join("02 Second.wav");
+    let info = BTreeMap::from([("INAM".to_string(), "Opener".to_string())]);
+    pcm_wav(0..30, &info, &[marker(7, "Verse", 5, Some(20))])
+        .write(fs::File::create(&first).unwrap())
+        .unwrap();
+    pcm_wav(100..150, &BTreeMap::new(), &[marker(1, "Clap", 10, None)])
+        .write(fs::File::create(&second).unwrap())
+        .unwrap();
+
+    let output = dir.join("album.wav");
+    let options = ConcatOptions {
+        source_regions: true,
+        title: Some("Live".to_string()),
+        ..Default::default()
+    };
+    let result = concat::concat(
+        &[first.to_str().unwrap(), second.to_str().unwrap()],
+        output.to_str().unwrap(),
+        &options,
+    )
+    .unwrap();
+    assert_eq!(result.length, 80);
+    assert!(result.diagnostics.is_empty());
+
+    let expected = vec![
+        marker(1, "Opener", 0, Some(30)),
+        marker(2, "Verse", 5, Some(20)),
+        marker(3, "02 Second", 30, Some(80)),
+        marker(4, "Clap", 40, None),
+    ];
+    assert_eq!(result.markers, expected);
+
+    let data = parse_markers_from_file(output.to_str().unwrap()).unwrap();
+    assert_eq!(data.markers, expected);
+    assert_eq!(data.length, Some(80));
+    assert_eq!(data.info["INAM"], "Live");
+    let riff = RiffFile::read(fs::File::open(&output).unwrap(), String::new()).unwrap();
+    assert_eq!(samples(&riff), (0..30).chain(100..150).collect::<Vec<_>>());
+
+    fs::remove_dir_all(&dir).unwrap();
+}
+
+#[test]
+fn test_concat_rejects_different_formats() {
+    let dir = std::env::temp_dir().join("reaper-regions-test-concat-formats");
+    fs::create_dir_all(&dir).unwrap();
+    let mono = dir.join("mono.wav");
+    pcm_wav(0..10, &BTreeMap::new(), &[])
+        .write(fs::File::create(&mono).unwrap())
+        .unwrap();
+    let fixture = "tests/fixtures/marker-region_stripped.wav";
+
+    let output = dir.join("out.wav");
+    let error = concat::concat(
+        &[mono.to_str().unwrap(), fixture],
+        output.to_str().unwrap(),
+        &ConcatOptions::default(),
+    )
+    .unwrap_err();
+    assert!(error.to_string().contains("format"));
+    assert!(!output.exists());
+
+    fs::remove_dir_all(&dir).unwrap();
+}
+
+#[test]
+fn test_concat_onto_an_input() {
+    let dir = std::env::temp_dir().join("reaper-regions-test-concat-onto-input");
+    let _ = fs::remove_dir_all(&dir);
+    fs::create_dir_all(&dir).unwrap();
+    let first = dir.join("first.wav");
+    let second = dir.join("second.wav");
+    pcm_wav(0..30, &BTreeMap::new(), &[marker(1, "Verse", 5, None)])
+        .write(fs::File::create(&first).unwrap())
+        .unwrap();
+    pcm_wav(100..120, &BTreeMap::new(), &[])
+        .write(fs::File::create(&second).unwrap())
+        .unwrap();
+
+    // Appending to the first file in place
+    let result = concat::concat(
+        &[first.to_str().unwrap(), second.to_str().unwrap()],
+        first.to_str().unwrap(),
+        &ConcatOptions::default(),
+    )
+    .unwrap();
+    assert_eq!(result.length, 50);
+    let riff = RiffFile::read(fs::File::open(&first).unwrap(), String::new()).unwrap();
+    assert_eq!(samples(&riff), (0..30).chain(100..120).collect::<Vec<_>>());
+    assert_eq!(fs::read_dir(&dir).unwrap().count(), 2);
+
+    fs::remove_dir_all(&dir).unwrap();
+}
+
+#[test]
+fn test_concat_marker_past_4_gib() {
+    let dir = std::env::temp_dir().join("reaper-regions-test-concat-overflow");
+    let _ = fs::remove_dir_all(&dir);
+    fs::create_dir_all(&dir).unwrap();
+    let first = dir.join("first.wav");
+    let second = dir.join("second.wav");
+    pcm_wav(0..30, &BTreeMap::new(), &[])
+        .write(fs::File::create(&first).unwrap())
+        .unwrap();
+    pcm_wav(
+        0..30,
+        &BTreeMap::new(),
+        &[marker(1, "Far", u32::MAX - 10, None)],
+    )
+    .write(fs::File::create(&second).unwrap())
+    .unwrap();
+
+    let output = dir.join("album.wav");
+    let result = concat::concat(
+        &[first.to_str().unwrap(), second.to_str().unwrap()],
+        output.to_str().unwrap(),
+        &ConcatOptions::default(),
+    );
+    assert!(result.is_err());
+    assert!(!output.exists());
+
+    fs::remove_dir_all(&dir).unwrap();
+}
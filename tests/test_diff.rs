@@ -0,0 +1,112 @@
+//! Tests for comparing the markers of two files.
+
+use reaper_regions::diff::{Change, Delta, DiffOptions, MatchBy};
+use reaper_regions::{Marker, WavData};
+
+mod common;
+
+use common::marker;
+
+fn wav(sample_rate: u32, markers: Vec<Marker>) -> WavData {
+    WavData {
+        sample_rate,
+        markers,
+        ..Default::default()
+    }
+}
+
+#[test]
+fn test_diff_changes() {
+    let old = wav(
+        1000,
+        vec![
+            marker(1, "Intro", 0, Some(1000)),
+            marker(2, "Verse", 1000, Some(3000)),
+            marker(3, "Hit", 1500, None),
+            marker(4, "Outro", 3000, Some(4000)),
+        ],
+    );
+    let new = wav(
+        1000,
+        vec![
+            marker(1, "Intro", 0, Some(1000)),
+            // Re-numbered by REAPER, found by name
+            marker(7, "Verse", 1000, Some(3500)),
+            // New ID and name, found by position
+            marker(8, "Snare hit", 1500, None),
+            marker(9, "Bridge", 3500, Some(4000)),
+        ],
+    );
+
+    let diff = old.diff(&new, &DiffOptions::default());
+    let expected = vec![
+        Change::Moved {
+            old: marker(2, "Verse", 1000, Some(3000)),
+            new: marker(7, "Verse", 1000, Some(3500)),
+            start: Delta {
+                samples: 0,
+                seconds: 0.0,
+            },
+            end: Some(Delta {
+                samples: 500,
+                seconds: 0.5,
+            }),
+        },
+        Change::Renamed {
+            old: marker(3, "Hit", 1500, None),
+            new: marker(8, "Snare hit", 1500, None),
+        },
+        Change::Removed {
+            marker: marker(4, "Outro", 3000, Some(4000)),
+        },
+        Change::Added {
+            marker: marker(9, "Bridge", 3500, Some(4000)),
+        },
+    ];
+    assert_eq!(diff.changes, expected);
+
+    // By ID only, the renumbered region is removed and added again
+    let options = DiffOptions {
+        match_by: vec![MatchBy::Id],
+        ..Default::default()
+    };
+    let diff = old.diff(&new, &options);
+    assert_eq!(diff.changes.len(), 6);
+    assert!(old.diff(&old, &options).is_empty());
+}
+
+#[test]
+fn test_diff_sample_rates() {
+    let old = wav(
+        44100,
+        vec![Marker::new(
+            1,
+            "Drop".to_string(),
+            44100,
+            Some(88200),
+            44100,
+        )],
+    );
+    // Rendered at 48 kHz, one sample late
+    let new = wav(
+        48000,
+        vec![Marker::new(
+            1,
+            "Drop".to_string(),
+            48001,
+            Some(96000),
+            48000,
+        )],
+    );
+    assert!(old.diff(&new, &DiffOptions::default()).is_empty());
+
+    let options = DiffOptions {
+        tolerance: 0.0,
+        ..Default::default()
+    };
+    let diff = old.diff(&new, &options);
+    let json = serde_json::to_value(&diff).unwrap();
+    assert_eq!(json["changes"][0]["change"], "moved");
+    assert_eq!(json["changes"][0]["start"]["samples"], 1);
+    assert!(json["changes"][0].get("end").is_none());
+}
@@ -0,0 +1,162 @@
+//! Tests for cropping, padding and trimming WAV files.
+
+use reaper_regions::diagnostic::Severity;
+use reaper_regions::edit::OutOfRange;
+use reaper_regions::parse_markers_from_file;
+use reaper_regions::wavtag::{ChunkType, RiffFile};
+use std::fs;
+
+mod common;
+
+use common::{marker, pcm_wav, samples};
+
+/// A 16-bit mono file of 100 samples, each holding its own index, with markers and
+/// an `ltxt` segment over the first region.
+fn source() -> RiffFile {
+    let mut riff = pcm_wav(
+        0..100,
+        &Default::default(),
+        &[
+            marker(1, "Song", 10, Some(40)),
+            marker(2, "Hit", 20, None),
+            marker(3, "Encore", 50, Some(120)),
+            marker(5, "Count-in", 5, None),
+        ],
+    );
+    let adtl = riff
+        .chunks
+        .iter_mut()
+        .find(|chunk| chunk.header == ChunkType::List)
+        .unwrap();
+    adtl.data.extend(b"ltxt");
+    for value in [20u32, 1, 30] {
+        adtl.data.extend(value.to_le_bytes());
+    }
+    adtl.data.extend(b"rgn ");
+    adtl.data.extend([0; 8]);
+    riff
+}
+
+/// Writes a file and parses its markers back.
+fn reparse(riff: &RiffFile, name: &str) -> reaper_regions::WavData {
+    let path = std::env::temp_dir().join(name);
+    riff.write(fs::File::create(&path).unwrap()).unwrap();
+    let mut data = parse_markers_from_file(path.to_str().unwrap()).unwrap();
+    fs::remove_file(&path).unwrap();
+    data.markers.sort_by_key(|marker| marker.id);
+    data
+}
+
+#[test]
+fn test_crop_rebases_markers() {
+    let mut riff = source();
+    let diagnostics = riff.crop(15, 60, OutOfRange::Drop).unwrap();
+    assert_eq!(samples(&riff), (15..60).collect::<Vec<_>>());
+
+    // Both regions stick out and are cut, the count-in is before the crop and removed
+    let summary: Vec<_> = diagnostics
+        .iter()
+        .map(|d| (d.marker_id.unwrap(), d.severity))
+        .collect();
+    assert_eq!(
+        summary,
+        [
+            (1, Severity::Info),
+            (3, Severity::Info),
+            (5, Severity::Warning)
+        ]
+    );
+
+    let data = reparse(&riff, "reaper-regions-test-crop.wav");
+    assert_eq!(data.length, Some(45));
+    assert_eq!(
+        data.markers,
+        [
+            marker(1, "Song", 0, Some(25)),
+            marker(2, "Hit", 5, None),
+            marker(3, "Encore", 35, Some(45)),
+        ]
+    );
+
+    // The ltxt segment is cut like its region
+    let adtl = &riff.find_chunk_by_type(ChunkType::List).unwrap().data;
+    let ltxt = adtl.windows(4).position(|tag| tag == b"ltxt").unwrap();
+    assert_eq!(&adtl[ltxt + 12..ltxt + 16], &25u32.to_le_bytes());
+    assert!(!adtl.windows(8).any(|text| text == b"Count-in"));
+}
+
+#[test]
+fn test_insert_silence() {
+    let mut riff = source();
+    assert!(riff.insert_silence(10).unwrap().is_empty());
+    let audio = samples(&riff);
+    assert_eq!(audio.len(), 110);
+    assert!(audio[..10].iter().all(|&sample| sample == 0));
+    assert_eq!(audio[10], 0);
+    assert_eq!(audio[109], 99);
+
+    let data = reparse(&riff, "reaper-regions-test-pad.wav");
+    // Markers past the end of the audio are moved too
+    assert_eq!(data.markers[2], marker(3, "Encore", 60, Some(130)));
+    assert_eq!(data.markers[3], marker(5, "Count-in", 15, None));
+}
+
+#[test]
+fn test_trim_clamps_markers() {
+    let mut riff = source();
+    let diagnostics = riff.trim_start(30, OutOfRange::Clamp).unwrap();
+    let clamped: Vec<_> = diagnostics
+        .iter()
+        .filter(|d| d.severity == Severity::Warning)
+        .map(|d| d.marker_id.unwrap())
+        .collect();
+    assert_eq!(clamped, [2, 5]);
+    let data = reparse(&riff, "reaper-regions-test-trim-start.wav");
+    assert_eq!(
+        data.markers,
+        [
+            marker(1, "Song", 0, Some(10)),
+            marker(2, "Hit", 0, None),
+            marker(3, "Encore", 20, Some(70)),
+            marker(5, "Count-in", 0, None),
+        ]
+    );
+
+    let mut riff = source();
+    riff.trim_end(50, OutOfRange::Drop).unwrap();
+    let data = reparse(&riff, "reaper-regions-test-trim-end.wav");
+    assert_eq!(data.length, Some(50));
+    let ids: Vec<_> = data.markers.iter().map(|marker| marker.id).collect();
+    assert_eq!(ids, [1, 2, 5]);
+}
+
+#[test]
+fn test_crop_to_region() {
+    let mut riff = source();
+    riff.crop_to_region(1, OutOfRange::Drop).unwrap();
+    assert_eq!(samples(&riff), (10..40).collect::<Vec<_>>());
+    assert!(riff.crop_to_region(2, OutOfRange::Drop).is_err());
+}
+
+#[test]
+fn test_crop_keeps_short_adtl_subchunks() {
+    let mut riff = source();
+    let adtl = riff
+        .chunks
+        .iter_mut()
+        .find(|chunk| chunk.header == ChunkType::List)
+        .unwrap();
+    // Subchunks too short to hold a cue point ID, first and last
+    let short = [b"note".as_slice(), &2u32.to_le_bytes(), b"ab"].concat();
+    let empty = [b"labl".as_slice(), &0u32.to_le_bytes()].concat();
+    adtl.data.splice(4..4, short.iter().copied());
+    adtl.data.extend(&empty);
+
+    riff.crop(15, 60, OutOfRange::Drop).unwrap();
+    let adtl = &riff.find_chunk_by_type(ChunkType::List).unwrap().data;
+    assert_eq!(&adtl[4..14], short.as_slice());
+    assert!(adtl.ends_with(&empty));
+
+    let data = reparse(&riff, "reaper-regions-test-crop-short-adtl.wav");
+    assert_eq!(data.markers.len(), 3);
+}
@@ -0,0 +1,301 @@
+//! Tests for the export formats; binary formats are read back with minimal parsers.
+
+use reaper_regions::formats::midi::{self, Division, MidiOptions, SmpteFormat};
+use reaper_regions::formats::reaper::{self, ReascriptOptions};
+use reaper_regions::{Marker, MarkerType, WavData, parse_markers_from_file};
+use std::fs;
+
+/// Reads a variable-length quantity, advancing `position`.
+fn read_variable_length(bytes: &[u8], position: &mut usize) -> u64 {
+    let mut value = 0;
+    loop {
+        let byte = bytes[*position];
+        *position += 1;
+        value = (value << 7) | (byte & 0x7F) as u64;
+        if byte & 0x80 == 0 {
+            return value;
+        }
+    }
+}
+
+/// Returns the division and the `(absolute tick, meta type, text)` of every meta-event
+/// in a single-track SMF.
+fn read_smf(smf: &[u8]) -> ([u8; 2], Vec<(u64, u8, String)>) {
+    assert_eq!(&smf[..4], b"MThd");
+    assert_eq!(&smf[8..12], &[0, 1, 0, 1], "format 1, one track");
+    assert_eq!(&smf[14..18], b"MTrk");
+    let length = u32::from_be_bytes(smf[18..22].try_into().unwrap()) as usize;
+    let track = &smf[22..];
+    assert_eq!(track.len(), length);
+
+    let mut events = Vec::new();
+    let mut position = 0;
+    let mut tick = 0;
+    while position < track.len() {
+        tick += read_variable_length(track, &mut position);
+        assert_eq!(track[position], 0xFF, "only meta-events are written");
+        let meta = track[position + 1];
+        position += 2;
+        let size = read_variable_length(track, &mut position) as usize;
+        let text = String::from_utf8_lossy(&track[position..position + size]).to_string();
+        position += size;
+        events.push((tick, meta, text));
+    }
+    assert_eq!(events.last().unwrap().1, 0x2F, "ends with End of Track");
+    ([smf[12], smf[13]], events)
+}
+
+#[test]
+fn test_midi_ppq_positions() {
+    let path = "tests/fixtures/3-markers-3-regions-overlapping_stripped.wav";
+    let data = parse_markers_from_file(path).unwrap();
+    let options = MidiOptions {
+        division: Division::Ppq(480),
+        tempo: 90.0,
+        ..MidiOptions::default()
+    };
+    let (division, events) = read_smf(&midi::export(&data, &options));
+    assert_eq!(division, [0x01, 0xE0]);
+    assert_eq!(
+        events[0],
+        (
+            0,
+            0x51,
+            String::from_utf8_lossy(&[0x0A, 0x2C, 0x2B]).to_string()
+        )
+    );
+
+    let ticks_per_second = 480.0 * 90.0 / 60.0;
+    for marker in &data.markers {
+        let (name, meta) = match marker.r#type {
+            MarkerType::Marker => (marker.name.clone(), 0x06),
+            MarkerType::Region => (format!("{} start", marker.name), 0x07),
+        };
+        let (tick, ..) = events
+            .iter()
+            .find(|(_, m, text)| *m == meta && *text == name)
+            .unwrap_or_else(|| panic!("{name} not found"));
+        let expected = marker.start as f64 / data.sample_rate as f64 * ticks_per_second;
+        assert!(
+            (*tick as f64 - expected).abs() <= 0.5,
+            "{name}: {tick} vs {expected}"
+        );
+    }
+}
+
+#[test]
+fn test_midi_smpte_division() {
+    let path = "tests/fixtures/without-bwf-bext-chunk_stripped.wav";
+    let data = parse_markers_from_file(path).unwrap();
+    let options = MidiOptions {
+        division: Division::Smpte {
+            format: SmpteFormat::Fps25,
+            ticks_per_frame: 40,
+        },
+        ..MidiOptions::default()
+    };
+    let (division, events) = read_smf(&midi::export(&data, &options));
+    assert_eq!(division, [(-25i8) as u8, 40]);
+
+    // 25 frames of 40 ticks: one tick per millisecond
+    let region = &data.markers[0];
+    let (tick, ..) = events
+        .iter()
+        .find(|(_, _, text)| *text == format!("{} end", region.name))
+        .unwrap();
+    assert_eq!(*tick, (region.end_time.unwrap() * 1000.0).round() as u64);
+}
+
+#[test]
+fn test_cue_index_numbers() {
+    use reaper_regions::formats::cue::{self, CueOptions};
+    use reaper_regions::{Marker, WavData};
+
+    let mut markers = vec![
+        Marker::new(1, "Set".to_string(), 0, Some(44100 * 200), 44100),
+        Marker::new(2, "First".to_string(), 44100, None, 44100),
+        // On the same CD frame as "First"
+        Marker::new(3, "Same frame".to_string(), 44100, None, 44100),
+    ];
+    markers.extend(
+        (0..100).map(|i| Marker::new(4 + i, format!("Point {i}"), 44100 * (2 + i), None, 44100)),
+    );
+    let data = WavData {
+        sample_rate: 44100,
+        markers,
+        ..WavData::default()
+    };
+    let export = cue::export(&data, &CueOptions::default());
+    let sheet = export.output;
+    assert!(sheet.contains("    INDEX 02 00:01:00\n    INDEX 03 00:02:00\n"));
+    // Index points stop at 99
+    assert!(sheet.contains("    INDEX 99 01:38:00\n"));
+    assert!(!sheet.contains("INDEX 100"));
+    assert_eq!(sheet.matches("INDEX").count(), 99);
+    let skipped: Vec<Option<u32>> = export.diagnostics.iter().map(|d| d.marker_id).collect();
+    assert_eq!(skipped, [Some(3), Some(101), Some(102), Some(103)]);
+}
+
+#[test]
+fn test_cue_uses_info_tags() {
+    use reaper_regions::formats::cue::{self, CueOptions};
+    use reaper_regions::wavtag::{ChunkType, RiffChunk, RiffFile};
+
+    // Copy a fixture and add a LIST-INFO chunk in front of its LIST-adtl chunk
+    let fixture = "tests/fixtures/marker-region_stripped.wav";
+    let mut riff = RiffFile::read(fs::File::open(fixture).unwrap(), fixture.to_string()).unwrap();
+    let mut info = b"INFO".to_vec();
+    for (tag, value) in [(b"IART", &b"The Band\0"[..]), (b"IPRD", &b"Live\0\0"[..])] {
+        info.extend(tag);
+        info.extend((value.len() as u32).to_le_bytes());
+        info.extend(value);
+        if value.len() % 2 == 1 {
+            info.push(0);
+        }
+    }
+    let adtl = riff
+        .chunks
+        .iter()
+        .position(|c| c.header == ChunkType::List)
+        .unwrap();
+    riff.chunks.insert(
+        adtl,
+        RiffChunk {
+            header: ChunkType::List,
+            data: info,
+        },
+    );
+    let path = std::env::temp_dir().join("reaper-regions-test-info.wav");
+    riff.write(fs::File::create(&path).unwrap()).unwrap();
+
+    let data = parse_markers_from_file(path.to_str().unwrap()).unwrap();
+    fs::remove_file(&path).unwrap();
+    assert_eq!(data.info["IART"], "The Band");
+    assert_eq!(data.info["IPRD"], "Live");
+    assert_eq!(data.markers.len(), 2);
+
+    let sheet = cue::export(&data, &CueOptions::default()).output;
+    assert!(sheet.starts_with("PERFORMER \"The Band\"\nTITLE \"Live\"\n"));
+    assert!(sheet.contains("    TITLE \"Region One\"\n    PERFORMER \"The Band\"\n"));
+}
+
+/// A file with point markers at whole seconds and an audio length in seconds.
+fn youtube_data(starts: &[u32], length: Option<u32>) -> reaper_regions::WavData {
+    use reaper_regions::{Marker, WavData};
+
+    WavData {
+        sample_rate: 1000,
+        length: length.map(|length| length * 1000),
+        markers: starts
+            .iter()
+            .zip(1..)
+            .map(|(&start, id)| Marker::new(id, format!("M{id}"), start * 1000, None, 1000))
+            .collect(),
+        ..WavData::default()
+    }
+}
+
+#[test]
+fn test_youtube_first_chapter_at_zero() {
+    use reaper_regions::formats::youtube::{self, YoutubeOptions};
+
+    let intro = YoutubeOptions {
+        intro: Some("Intro".to_string()),
+        ..YoutubeOptions::default()
+    };
+    let data = youtube_data(&[5, 60, 120], None);
+
+    // Without an intro, the first chapter moves to 0:00
+    let export = youtube::export(&data, &YoutubeOptions::default());
+    assert_eq!(export.output, "0:00 M1\n1:00 M2\n2:00 M3\n");
+    assert_eq!(export.diagnostics[0].marker_id, Some(1));
+
+    // An intro would be shorter than ten seconds, so the first chapter moves instead
+    let export = youtube::export(&data, &intro);
+    assert_eq!(export.output, "0:00 M1\n1:00 M2\n2:00 M3\n");
+    assert!(export.diagnostics[0].message.contains("'Intro'"));
+
+    let data = youtube_data(&[10, 60, 120], None);
+    let export = youtube::export(&data, &intro);
+    assert_eq!(export.output, "0:00 Intro\n0:10 M1\n1:00 M2\n2:00 M3\n");
+    assert!(export.diagnostics.is_empty());
+
+    let data = youtube_data(&[0, 60, 120], None);
+    let export = youtube::export(&data, &intro);
+    assert_eq!(export.output, "0:00 M1\n1:00 M2\n2:00 M3\n");
+    assert!(export.diagnostics.is_empty());
+}
+
+#[test]
+fn test_youtube_minimum_chapters() {
+    use reaper_regions::formats::youtube::{self, YoutubeOptions};
+
+    let export = youtube::export(&youtube_data(&[0, 60], None), &YoutubeOptions::default());
+    assert_eq!(export.output, "0:00 M1\n1:00 M2\n");
+    assert_eq!(export.diagnostics.len(), 1);
+    assert!(
+        export.diagnostics[0]
+            .message
+            .contains("at least 3 chapters")
+    );
+
+    let export = youtube::export(&youtube_data(&[], None), &YoutubeOptions::default());
+    assert_eq!(export.output, "");
+    assert!(export.diagnostics.is_empty());
+}
+
+#[test]
+fn test_youtube_minimum_chapter_length() {
+    use reaper_regions::formats::youtube::{self, YoutubeOptions};
+
+    // "M2" starts less than ten seconds after "M1"
+    let data = youtube_data(&[0, 9, 10, 20], Some(60));
+    let export = youtube::export(&data, &YoutubeOptions::default());
+    assert_eq!(export.output, "0:00 M1\n0:10 M3\n0:20 M4\n");
+    let skipped: Vec<Option<u32>> = export.diagnostics.iter().map(|d| d.marker_id).collect();
+    assert_eq!(skipped, [Some(2)]);
+
+    // The last chapter ends with the audio, less than ten seconds after it starts
+    let data = youtube_data(&[0, 10, 20, 30], Some(39));
+    let export = youtube::export(&data, &YoutubeOptions::default());
+    assert_eq!(export.output, "0:00 M1\n0:10 M2\n0:20 M3\n");
+    let skipped: Vec<Option<u32>> = export.diagnostics.iter().map(|d| d.marker_id).collect();
+    assert_eq!(skipped, [Some(4)]);
+
+    let data = youtube_data(&[0, 10, 20, 30], Some(40));
+    let export = youtube::export(&data, &YoutubeOptions::default());
+    assert_eq!(export.output, "0:00 M1\n0:10 M2\n0:20 M3\n0:30 M4\n");
+    assert!(export.diagnostics.is_empty());
+}
+
+#[test]
+fn test_midi_invalid_tempo() {
+    let path = "tests/fixtures/3-markers-3-regions-overlapping_stripped.wav";
+    let data = parse_markers_from_file(path).unwrap();
+    let default = midi::export(&data, &MidiOptions::default());
+    for tempo in [f64::NAN, f64::INFINITY, 0.0, -120.0] {
+        let options = MidiOptions {
+            tempo,
+            ..MidiOptions::default()
+        };
+        assert_eq!(midi::export(&data, &options), default, "{tempo}");
+    }
+}
+
+#[test]
+fn test_reascript_escapes_control_characters() {
+    let data = WavData {
+        sample_rate: 48000,
+        markers: vec![Marker::new(
+            1,
+            "Bell\u{7} Next\u{85}Line".to_string(),
+            0,
+            None,
+            48000,
+        )],
+        ..WavData::default()
+    };
+    let script = reaper::export_reascript(&data, &ReascriptOptions::default());
+    // C1 controls are escaped byte by byte in UTF-8
+    assert!(script.contains("\"Bell\\007 Next\\194\\133Line\""));
+}
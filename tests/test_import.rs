@@ -0,0 +1,316 @@
+//! Round-trip tests for the marker formats that can be read back.
+
+use reaper_regions::formats::audacity;
+use reaper_regions::parse_markers_from_file;
+use std::fs;
+use std::path::PathBuf;
+
+/// All WAV fixtures in `tests/fixtures`.
+fn fixtures() -> Vec<PathBuf> {
+    let mut paths: Vec<PathBuf> = fs::read_dir("tests/fixtures")
+        .expect("Failed to read fixtures directory")
+        .filter_map(|entry| Some(entry.ok()?.path()))
+        .filter(|path| path.extension().is_some_and(|ext| ext == "wav"))
+        .collect();
+    paths.sort();
+    paths
+}
+
+#[test]
+fn test_audacity_round_trip() {
+    for path in fixtures() {
+        let data = parse_markers_from_file(path.to_str().unwrap()).unwrap();
+        let labels = audacity::export(&data);
+        let markers = audacity::import(&labels, data.sample_rate).unwrap();
+
+        assert_eq!(markers.len(), data.markers.len(), "{}", path.display());
+        for (imported, original) in markers.iter().zip(&data.markers) {
+            assert_eq!(imported.name, original.name);
+            assert_eq!(imported.r#type, original.r#type);
+            assert_eq!(imported.start, original.start);
+            assert_eq!(imported.end, original.end);
+        }
+    }
+}
+
+#[test]
+fn test_audacity_import_skips_spectral_lines() {
+    let labels = "1.5\t2.5\tBird\n\\\t100.0\t4000.0\n3\t3\tClick\n";
+    let markers = audacity::import(labels, 44100).unwrap();
+    assert_eq!(markers.len(), 2);
+    assert_eq!(markers[0].end, Some(110250));
+    assert_eq!(markers[1].id, 2);
+    assert_eq!(markers[1].start, 132300);
+}
+
+#[test]
+fn test_audacity_import_rejects_invalid_lines() {
+    assert!(audacity::import("1.0\tabc\tBroken\n", 44100).is_err());
+    assert!(audacity::import("2.0\t1.0\tBackwards\n", 44100).is_err());
+}
+
+#[test]
+fn test_cue_round_trip() {
+    use reaper_regions::formats::cue::{self, CueOptions};
+
+    let data =
+        parse_markers_from_file("tests/fixtures/3-markers-3-regions-overlapping_stripped.wav")
+            .unwrap();
+    let sheet = cue::export(&data, &CueOptions::default()).output;
+    let markers = cue::import(&sheet, data.sample_rate, Some(3_000_000)).unwrap();
+
+    // Tracks run until the next track; markers inside regions come back as index points
+    let names: Vec<&str> = markers.iter().map(|m| m.name.as_str()).collect();
+    assert_eq!(
+        names,
+        [
+            "Region 1",
+            "Region 1 (index 02)",
+            "Region 2",
+            "Region 3",
+            "Region 3 (index 02)"
+        ]
+    );
+    // One CD frame is 640 samples at 48 kHz
+    for (imported, original) in [(0, 0), (1, 1), (2, 2), (3, 4), (4, 5)] {
+        let delta = markers[imported]
+            .start
+            .abs_diff(data.markers[original].start);
+        assert!(delta <= 320, "{} off by {delta}", markers[imported].name);
+    }
+    assert_eq!(markers[0].end, Some(markers[2].start));
+    assert_eq!(markers[3].end, Some(3_000_000));
+}
+
+#[test]
+fn test_cue_import_multiple_files() {
+    use reaper_regions::MarkerType;
+    use reaper_regions::formats::cue::CueSheet;
+
+    let sheet = CueSheet::parse(
+        r#"REM GENRE Live
+REM DATE 2024
+PERFORMER "The Band"
+TITLE "Live at the Hall"
+FILE "CD1.wav" WAVE
+  TRACK 01 AUDIO
+    TITLE "Intro"
+    INDEX 01 00:00:00
+  TRACK 02 AUDIO
+    TITLE "Song"
+    REM COMPOSER "Someone"
+    INDEX 00 00:09:00
+    INDEX 01 00:10:00
+    INDEX 02 00:15:00
+FILE "CD2.wav" WAVE
+  TRACK 03 AUDIO
+    TITLE "Encore"
+    INDEX 01 00:00:00
+"#,
+    )
+    .unwrap();
+    assert_eq!(sheet.rems, ["GENRE Live", "DATE 2024"]);
+    assert_eq!(sheet.title.as_deref(), Some("Live at the Hall"));
+    assert_eq!(sheet.files.len(), 2);
+    assert_eq!(sheet.files[0].tracks[1].rems, ["COMPOSER \"Someone\""]);
+
+    let markers = sheet.to_markers(44100, &[Some(882_000), None]).unwrap();
+    assert_eq!(markers.len(), 4);
+    assert_eq!((markers[0].start, markers[0].end), (0, Some(441_000)));
+    assert_eq!(markers[2].name, "Song (index 02)");
+    assert_eq!(markers[2].start, 661_500);
+    assert_eq!(markers[1].end, Some(882_000));
+    // The length of the last file is unknown, so the last track has no end
+    assert_eq!(markers[3].start, 882_000);
+    assert_eq!(markers[3].r#type, MarkerType::Marker);
+
+    // Files followed by other files need a length
+    assert!(sheet.to_markers(44100, &[None, None]).is_err());
+    // Lengths and positions must fit in 32-bit sample counts
+    assert!(
+        sheet
+            .to_markers(44100, &[Some(u32::MAX - 1000), Some(2000)])
+            .is_err()
+    );
+    // A single length can't place several files
+    let text = "FILE a.wav WAVE\nTRACK 01 AUDIO\nINDEX 01 00:00:00\nFILE b.wav WAVE\nTRACK 02 AUDIO\nINDEX 01 00:00:00\n";
+    assert!(reaper_regions::formats::cue::import(text, 44100, Some(441_000)).is_err());
+}
+
+#[test]
+fn test_rpp_import() {
+    use reaper_regions::formats::rpp::RppProject;
+
+    let text = fs::read_to_string("tests/fixtures/3-markers-3-regions-overlapping.rpp").unwrap();
+    let project = RppProject::parse(&text).unwrap();
+
+    // The track's MARKER line is not a project marker
+    assert_eq!(project.markers.len(), 6);
+    let region = &project.markers[2];
+    assert_eq!(region.index, 2);
+    assert_eq!(region.name, "Region 2");
+    assert_eq!(region.end, Some(42.172_708_333_333_33));
+    assert_eq!(region.color, Some(16576606));
+    assert_eq!(
+        region.guid.as_deref(),
+        Some("{2E0B5C1A-6B58-4F0B-9C7E-1D3A9C3F0003}")
+    );
+    assert_eq!(project.markers[5].name, "Outro \"cold\"");
+    assert_eq!(project.author.as_deref(), Some("Jane \"JD\" Doe"));
+
+    // The time signature carries over to envelope points that don't change it
+    let tempo: Vec<(f64, f64, u32, u32, bool)> = project
+        .tempo
+        .changes
+        .iter()
+        .map(|c| (c.position, c.bpm, c.numerator, c.denominator, c.linear))
+        .collect();
+    assert_eq!(
+        tempo,
+        [
+            (0.0, 120.0, 4, 4, false),
+            (40.0, 90.0, 3, 4, true),
+            (60.0, 100.0, 3, 4, false)
+        ]
+    );
+    assert_eq!(project.render_bounds(), Some((10.0, 80.0)));
+}
+
+#[test]
+fn test_rpp_compare() {
+    use reaper_regions::formats::rpp::{self, RenderChange, RppProject};
+
+    let text = fs::read_to_string("tests/fixtures/3-markers-3-regions-overlapping.rpp").unwrap();
+    let project = RppProject::parse(&text).unwrap();
+    let render =
+        parse_markers_from_file("tests/fixtures/3-markers-3-regions-overlapping_stripped.wav")
+            .unwrap();
+
+    let markers = project.to_markers(render.sample_rate, 10.0);
+    let changes = rpp::compare(&markers, &render.markers, None, 48);
+    let summary: Vec<String> = changes
+        .iter()
+        .map(|change| match change {
+            RenderChange::Missing(marker) => format!("missing {}", marker.name),
+            RenderChange::Removed(marker) => format!("removed {}", marker.name),
+            RenderChange::Renamed { project, render } => {
+                format!("renamed {} {}", render.name, project.name)
+            }
+            RenderChange::Moved { project, render } => {
+                format!("moved {} {}", project.name, project.start - render.start)
+            }
+        })
+        .collect();
+    assert_eq!(
+        summary,
+        [
+            "moved Region 2 48000",
+            "renamed Region 1 Intro",
+            "missing Outro \"cold\"",
+            "removed Marker 3"
+        ]
+    );
+
+    // Markers past the end of the render are not missing from it
+    let changes = rpp::compare(&markers, &render.markers, Some(2_500_000), 48);
+    assert!(
+        !changes
+            .iter()
+            .any(|c| matches!(c, RenderChange::Missing(_)))
+    );
+}
+
+#[test]
+fn test_sampler_and_instrument_data() {
+    use reaper_regions::wavtag::{ChunkType, InstrumentChunk, RiffFile};
+
+    // Copy a fixture, tune its 'smpl' chunk and add an 'inst' chunk
+    let fixture = "tests/fixtures/marker-region_stripped.wav";
+    let mut riff = RiffFile::read(fs::File::open(fixture).unwrap(), fixture.to_string()).unwrap();
+    let smpl = riff
+        .chunks
+        .iter_mut()
+        .find(|c| c.header == ChunkType::Sampler)
+        .unwrap();
+    // Unity note, pitch fraction, SMPTE format and offset
+    for (offset, value) in [(12, 60), (16, 0x4000_0000), (20, 25), (24, 0xFF02_0304_u32)] {
+        smpl.data[offset..offset + 4].copy_from_slice(&u32::to_le_bytes(value));
+    }
+    riff.set_instrument_chunk(InstrumentChunk {
+        fine_tune: -12,
+        gain: -6,
+        low_note: 48,
+        high_note: 71,
+        low_vel: 1,
+        ..InstrumentChunk::default()
+    });
+    assert_eq!(
+        riff.find_chunk_by_type(ChunkType::Instrument).unwrap().data[1..3],
+        [0xF4, 0xFA]
+    );
+    let path = std::env::temp_dir().join("reaper-regions-test-sampler.wav");
+    riff.write(fs::File::create(&path).unwrap()).unwrap();
+
+    let data = parse_markers_from_file(path.to_str().unwrap()).unwrap();
+    fs::remove_file(&path).unwrap();
+    assert_eq!(data.markers.len(), 2);
+    let sampler = data.sampler.as_ref().unwrap();
+    assert_eq!(sampler.unity_note_name, "C3");
+    assert_eq!(sampler.pitch_fraction, 25.0);
+    assert_eq!(sampler.smpte_offset.as_deref(), Some("-01:02:03:04"));
+    assert_eq!(sampler.fine_tune, Some(-12));
+    assert_eq!(sampler.gain, Some(-6));
+
+    let json = serde_json::to_value(&data).unwrap();
+    assert_eq!(json["sampler"]["unity_note"], 60);
+    assert_eq!(json["sampler"]["low_note"], 48);
+    assert_eq!(json["sampler"]["high_velocity"], 127);
+}
+
+#[test]
+fn test_malformed_instrument_chunk_is_ignored() {
+    use reaper_regions::wavtag::{ChunkType, InstrumentChunk, RiffFile};
+
+    // Copy a fixture and add an 'inst' chunk cut short after its unshifted note
+    let fixture = "tests/fixtures/marker-region_stripped.wav";
+    let mut riff = RiffFile::read(fs::File::open(fixture).unwrap(), fixture.to_string()).unwrap();
+    riff.set_instrument_chunk(InstrumentChunk::default());
+    let inst = riff
+        .chunks
+        .iter_mut()
+        .find(|c| c.header == ChunkType::Instrument)
+        .unwrap();
+    inst.data.truncate(1);
+    let path = std::env::temp_dir().join("reaper-regions-test-bad-inst.wav");
+    riff.write(fs::File::create(&path).unwrap()).unwrap();
+
+    let data = parse_markers_from_file(path.to_str().unwrap()).unwrap();
+    fs::remove_file(&path).unwrap();
+    assert_eq!(data.markers.len(), 2);
+    let sampler = data.sampler.as_ref().unwrap();
+    assert_eq!(sampler.fine_tune, None);
+    assert_eq!(sampler.gain, None);
+}
+
+#[test]
+fn test_sampler_data_uses_its_declared_size() {
+    use reaper_regions::wavtag::{ChunkType, RiffChunk, SamplerChunk};
+
+    // No loops, two bytes of sampler data and two bytes of trailing padding
+    let mut data = vec![0; 36];
+    data[32..36].copy_from_slice(&2u32.to_le_bytes());
+    data.extend(b"abzz");
+    let chunk = RiffChunk {
+        header: ChunkType::Sampler,
+        data,
+    };
+    let sampler = SamplerChunk::from_chunk(&chunk).unwrap();
+    assert_eq!(sampler.sampler_data, b"ab");
+    assert_eq!(sampler.serialise(), chunk.data[..38]);
+
+    // A size past the end of the chunk keeps what is there
+    let mut chunk = chunk;
+    chunk.data[32..36].copy_from_slice(&100u32.to_le_bytes());
+    let sampler = SamplerChunk::from_chunk(&chunk).unwrap();
+    assert_eq!(sampler.sampler_data, b"abzz");
+}
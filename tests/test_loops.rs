@@ -0,0 +1,100 @@
+//! Tests for writing regions as 'smpl' loops.
+
+use reaper_regions::loops::{self, LoopOptions, glob_match};
+use reaper_regions::parse_markers_from_file;
+use reaper_regions::select::Filter;
+use reaper_regions::wavtag::{ChunkType, LoopType, RiffFile};
+use std::fs;
+
+const FIXTURE: &str = "tests/fixtures/3-markers-3-regions-overlapping_stripped.wav";
+
+#[test]
+fn test_selected_regions_loop_first() {
+    let path = std::env::temp_dir().join("reaper-regions-test-loops.wav");
+    let output = path.to_str().unwrap();
+    let filter = Filter::Name("Region 2*".to_string());
+    let options = LoopOptions {
+        loop_type: LoopType::PingPong,
+        play_count: 2,
+        unity_note: 57,
+    };
+    let ids = loops::write_loops_to_file(FIXTURE, output, &filter, &options).unwrap();
+    assert_eq!(ids, vec![3]);
+
+    let riff = RiffFile::read(fs::File::open(&path).unwrap(), output.to_string()).unwrap();
+    let smpl = riff.get_sampler_chunk();
+    assert_eq!(smpl.sample_period, 20833);
+    assert_eq!(smpl.midi_unity_note, 57);
+    let loops: Vec<_> = smpl
+        .sample_loops
+        .iter()
+        .map(|l| (l.id, l.loop_type, l.play_count))
+        .collect();
+    assert_eq!(
+        loops,
+        vec![
+            (3, LoopType::PingPong, 2),
+            (1, LoopType::Forward, 0),
+            (5, LoopType::Forward, 0)
+        ]
+    );
+
+    // The cue points and labels are untouched and the file reads back the same
+    let original = RiffFile::read(fs::File::open(FIXTURE).unwrap(), FIXTURE.to_string()).unwrap();
+    for header in [ChunkType::Cue, ChunkType::List] {
+        assert_eq!(
+            riff.find_chunk_by_type(header.clone()).unwrap().data,
+            original.find_chunk_by_type(header).unwrap().data
+        );
+    }
+    let data = parse_markers_from_file(output).unwrap();
+    fs::remove_file(&path).unwrap();
+    assert_eq!(
+        data.markers,
+        parse_markers_from_file(FIXTURE).unwrap().markers
+    );
+}
+
+#[test]
+fn test_loops_onto_input() {
+    let path = std::env::temp_dir().join("reaper-regions-test-loops-in-place.wav");
+    let file = path.to_str().unwrap();
+    fs::copy(FIXTURE, &path).unwrap();
+    let ids =
+        loops::write_loops_to_file(file, file, &Filter::Ids(vec![5]), &LoopOptions::default())
+            .unwrap();
+    assert_eq!(ids, vec![5]);
+
+    let riff = RiffFile::read(fs::File::open(&path).unwrap(), file.to_string()).unwrap();
+    let original = RiffFile::read(fs::File::open(FIXTURE).unwrap(), FIXTURE.to_string()).unwrap();
+    assert_eq!(riff.get_sampler_chunk().sample_loops[0].id, 5);
+    assert_eq!(
+        riff.find_chunk_by_type(ChunkType::Data).unwrap().data,
+        original.find_chunk_by_type(ChunkType::Data).unwrap().data
+    );
+    let data = parse_markers_from_file(file).unwrap();
+    fs::remove_file(&path).unwrap();
+    assert_eq!(
+        data.markers,
+        parse_markers_from_file(FIXTURE).unwrap().markers
+    );
+}
+
+#[test]
+fn test_selection_without_regions_fails() {
+    let data = parse_markers_from_file(FIXTURE).unwrap();
+    let mut riff = RiffFile::read(fs::File::open(FIXTURE).unwrap(), FIXTURE.to_string()).unwrap();
+    // Marker 1 (ID 2) is not a region
+    let filter = Filter::Ids(vec![2]);
+    assert!(loops::write_loops(&mut riff, &data, &filter, &LoopOptions::default()).is_err());
+}
+
+#[test]
+fn test_glob_match() {
+    assert!(glob_match("*", ""));
+    assert!(glob_match("Region ?", "Region 1"));
+    assert!(glob_match("*oo*", "Loop"));
+    assert!(glob_match("a*b*c", "abxbc"));
+    assert!(!glob_match("a*b*c", "abxbcx"));
+    assert!(!glob_match("Region ?", "Region 10"));
+}
@@ -0,0 +1,157 @@
+//! Tests for adding, renaming, deleting and moving markers.
+
+use reaper_regions::markers::{self, MarkerEdit, Position, WriteOptions};
+use reaper_regions::select::Filter;
+use reaper_regions::wavtag::RiffFile;
+use reaper_regions::{WavData, parse_markers_from_file};
+use std::collections::BTreeMap;
+use std::fs;
+
+mod common;
+
+use common::{marker, pcm_wav, samples};
+
+#[test]
+fn test_edit_markers() {
+    let dir = std::env::temp_dir().join("reaper-regions-test-markers");
+    let _ = fs::remove_dir_all(&dir);
+    fs::create_dir_all(&dir).unwrap();
+    let path = dir.join("mix.wav");
+    let path = path.to_str().unwrap();
+    pcm_wav(
+        0..1000,
+        &BTreeMap::new(),
+        &[
+            marker(1, "Verse 1", 0, Some(300)),
+            marker(2, "Chrous 1", 300, Some(500)),
+            marker(3, "Chrous 2", 700, Some(900)),
+            marker(4, "Clap", 350, None),
+        ],
+    )
+    .write(fs::File::create(path).unwrap())
+    .unwrap();
+
+    let mut data = parse_markers_from_file(path).unwrap();
+    let edits = [
+        MarkerEdit::Rename {
+            filter: Filter::Ids(vec![2]),
+            name: "Chorus 1".to_string(),
+        },
+        MarkerEdit::Rename {
+            filter: Filter::Name("Chrous 2".to_string()),
+            name: "Chorus 2".to_string(),
+        },
+        MarkerEdit::Delete {
+            filter: Filter::Name("Clap".to_string()),
+        },
+        MarkerEdit::Move {
+            filter: Filter::Ids(vec![3]),
+            start: Position::Seconds(0.6),
+            end: None,
+        },
+        MarkerEdit::Add {
+            name: "Bridge".to_string(),
+            start: "0:00.5".parse().unwrap(),
+            end: Some(Position::Samples(600)),
+        },
+    ];
+    let ids: Vec<_> = edits
+        .iter()
+        .map(|edit| data.apply_edit(edit).unwrap())
+        .collect();
+    assert_eq!(ids, [vec![2], vec![3], vec![4], vec![3], vec![4]]);
+    markers::write_markers(path, &data.markers, &WriteOptions { backup: true }).unwrap();
+
+    let expected = vec![
+        marker(1, "Verse 1", 0, Some(300)),
+        marker(2, "Chorus 1", 300, Some(500)),
+        marker(4, "Bridge", 500, Some(600)),
+        marker(3, "Chorus 2", 600, Some(800)),
+    ];
+    assert_eq!(data.markers, expected);
+    assert_eq!(parse_markers_from_file(path).unwrap().markers, expected);
+    let riff = RiffFile::read(fs::File::open(path).unwrap(), String::new()).unwrap();
+    assert_eq!(samples(&riff), (0..1000).collect::<Vec<_>>());
+
+    // The backup is the original file, and no temporary file is left behind
+    let backup = parse_markers_from_file(&format!("{path}.bak")).unwrap();
+    assert_eq!(backup.markers.len(), 4);
+    assert_eq!(backup.markers[1].name, "Chrous 1");
+    assert_eq!(fs::read_dir(&dir).unwrap().count(), 2);
+
+    fs::remove_dir_all(&dir).unwrap();
+}
+
+#[cfg(feature = "regex")]
+#[test]
+fn test_rename_by_regex() {
+    use regex::Regex;
+
+    let mut data = WavData {
+        sample_rate: 1000,
+        length: Some(1000),
+        markers: vec![
+            marker(1, "Chrous 1", 0, Some(300)),
+            marker(2, "Chrous 2", 300, Some(500)),
+            marker(3, "Verse", 500, None),
+        ],
+        ..Default::default()
+    };
+    // Only the matched text is replaced
+    let edit = MarkerEdit::Rename {
+        filter: Filter::Regex(Regex::new("^Chrous").unwrap()),
+        name: "Chorus".to_string(),
+    };
+    assert_eq!(data.apply_edit(&edit).unwrap(), [1, 2]);
+    let names: Vec<_> = data.markers.iter().map(|m| m.name.as_str()).collect();
+    assert_eq!(names, ["Chorus 1", "Chorus 2", "Verse"]);
+}
+
+#[test]
+fn test_invalid_edits() {
+    let mut data = WavData {
+        sample_rate: 1000,
+        length: Some(1000),
+        markers: vec![
+            marker(1, "Take", 0, None),
+            marker(2, "Take", 10, None),
+            marker(3, "Song", 100, Some(400)),
+        ],
+        ..Default::default()
+    };
+    let take = || Filter::Name("Take".to_string());
+    let invalid = [
+        // Two markers are selected
+        MarkerEdit::Move {
+            filter: take(),
+            start: Position::Samples(5),
+            end: None,
+        },
+        MarkerEdit::Delete {
+            filter: Filter::Ids(vec![4]),
+        },
+        // The region keeps its length, which takes it past the end of the audio
+        MarkerEdit::Move {
+            filter: Filter::Ids(vec![3]),
+            start: Position::Seconds(0.8),
+            end: None,
+        },
+        MarkerEdit::Add {
+            name: "Backwards".to_string(),
+            start: Position::Samples(20),
+            end: Some(Position::Samples(10)),
+        },
+        MarkerEdit::Add {
+            name: "Late".to_string(),
+            start: Position::Seconds(1.5),
+            end: None,
+        },
+    ];
+    for edit in &invalid {
+        assert!(data.apply_edit(edit).is_err(), "{edit:?}");
+    }
+    assert_eq!(data.markers.len(), 3);
+    assert_eq!(data.markers[2], marker(3, "Song", 100, Some(400)));
+    assert!("1:xx".parse::<Position>().is_err());
+    assert!("-1.5".parse::<Position>().is_err());
+}
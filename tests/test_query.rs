@@ -0,0 +1,130 @@
+//! Tests for position queries.
+
+use reaper_regions::markers::Position;
+use reaper_regions::{Marker, WavData};
+
+/// Markers and regions at pseudo-random positions, with many overlapping regions.
+fn data(count: u32) -> WavData {
+    let mut state: u64 = 0x2545_f491_4f6c_dd1d;
+    let mut random = |limit: u32| {
+        state = state
+            .wrapping_mul(6364136223846793005)
+            .wrapping_add(1442695040888963407);
+        ((state >> 33) % limit as u64) as u32
+    };
+    let markers = (1..=count)
+        .map(|id| {
+            let start = random(100_000);
+            let end = match random(3) {
+                0 => None,
+                _ => Some(start + 1 + random(5_000)),
+            };
+            Marker::new(id, format!("M{id}"), start, end, 1000)
+        })
+        .collect();
+    WavData {
+        sample_rate: 1000,
+        markers,
+        ..Default::default()
+    }
+}
+
+fn ids(markers: &[&Marker]) -> Vec<u32> {
+    markers.iter().map(|marker| marker.id).collect()
+}
+
+/// The markers matching a predicate, by start and ID.
+fn expected(data: &WavData, predicate: impl Fn(&Marker) -> bool) -> Vec<u32> {
+    let mut found: Vec<&Marker> = data.markers.iter().filter(|m| predicate(m)).collect();
+    found.sort_by_key(|marker| (marker.start, marker.id));
+    ids(&found)
+}
+
+#[test]
+fn test_queries_match_a_scan() {
+    let data = data(5000);
+    let index = data.index();
+    assert_eq!(index.markers().len(), 5000);
+    let end = |marker: &Marker| marker.end.unwrap_or(marker.start);
+
+    for position in (0..110_000).step_by(997) {
+        let containing = expected(&data, |m| {
+            m.end.is_some() && m.start <= position && position < end(m)
+        });
+        assert_eq!(ids(&index.containing(position)), containing, "{position}");
+
+        let at = expected(&data, |m| m.start == position);
+        assert_eq!(ids(index.at(position)), at);
+
+        let window = position..position + 2500;
+        let overlapping = expected(&data, |m| match m.end {
+            Some(end) => m.start < window.end && end > window.start,
+            None => window.contains(&m.start),
+        });
+        assert_eq!(ids(&index.overlapping(window.clone())), overlapping);
+
+        let within = expected(&data, |m| {
+            m.start >= window.start && m.start < window.end && end(m) <= window.end
+        });
+        assert_eq!(ids(&index.within(window)), within);
+
+        let next = data
+            .markers
+            .iter()
+            .filter(|m| m.start > position)
+            .min_by_key(|m| (m.start, m.id));
+        assert_eq!(index.next(position).map(|m| m.id), next.map(|m| m.id));
+        let prev = data
+            .markers
+            .iter()
+            .filter(|m| m.start < position)
+            .max_by_key(|m| (m.start, m.id));
+        assert_eq!(index.prev(position).map(|m| m.id), prev.map(|m| m.id));
+    }
+
+    for region in data.markers.iter().filter(|m| m.end.is_some()).take(200) {
+        let inside = expected(&data, |m| {
+            m.id != region.id
+                && m.start >= region.start
+                && m.start < end(region)
+                && end(m) <= end(region)
+        });
+        assert_eq!(ids(&index.markers_within(region)), inside);
+    }
+}
+
+#[test]
+fn test_positions_in_seconds() {
+    let data = WavData {
+        sample_rate: 48000,
+        markers: vec![
+            Marker::new(1, "Set".to_string(), 0, Some(480_000), 48000),
+            Marker::new(2, "Song".to_string(), 48000, Some(96000), 48000),
+            Marker::new(3, "Cue".to_string(), 96000, None, 48000),
+        ],
+        ..Default::default()
+    };
+    let index = data.index();
+    assert_eq!(ids(&index.containing(1.5)), [1, 2]);
+    assert_eq!(ids(&index.containing(Position::Samples(96000))), [1]);
+    assert_eq!(ids(&index.containing(10.0)), Vec::<u32>::new());
+    assert_eq!(ids(index.at(2.0)), [3]);
+    assert_eq!(ids(&index.overlapping(1.999..2.0)), [1, 2]);
+    assert_eq!(ids(&index.overlapping(2.0..3.0)), [1, 3]);
+    assert_eq!(ids(&index.markers_within(&data.markers[0])), [2, 3]);
+    // A marker at the end of a region is not within it
+    assert_eq!(
+        ids(&index.markers_within(&data.markers[1])),
+        Vec::<u32>::new()
+    );
+    assert_eq!(index.next(1.0).unwrap().id, 3);
+    assert_eq!(index.prev(1.0).unwrap().id, 1);
+    assert!(index.prev(0.0).is_none());
+    assert!(index.next(2.0).is_none());
+
+    let empty = WavData::default();
+    let index = empty.index();
+    assert!(index.containing(0).is_empty());
+    assert!(index.overlapping(0..u32::MAX).is_empty());
+    assert!(index.next(0).is_none());
+}
@@ -0,0 +1,137 @@
+//! Tests for selecting markers and regions.
+
+use reaper_regions::markers::Position;
+use reaper_regions::select::Filter;
+use reaper_regions::split::{self, SplitOptions};
+use reaper_regions::{MarkerType, WavData};
+use std::collections::BTreeMap;
+use std::fs;
+
+mod common;
+
+use common::{SAMPLE_RATE, marker, pcm_wav};
+
+fn data() -> WavData {
+    WavData {
+        sample_rate: SAMPLE_RATE,
+        markers: vec![
+            marker(1, "Intro", 0, Some(500)),
+            marker(2, "Clap", 600, None),
+            marker(3, "Verse 1", 1000, Some(3000)),
+            marker(4, "Take (alt)", 2500, Some(2600)),
+            marker(5, "Verse 2", 4000, Some(6000)),
+            marker(6, "Drop", 7000, None),
+        ],
+        ..Default::default()
+    }
+}
+
+fn selected(filter: &Filter) -> Vec<u32> {
+    let mut data = data();
+    data.retain_markers(filter);
+    data.markers.iter().map(|marker| marker.id).collect()
+}
+
+fn parsed(expression: &str) -> Vec<u32> {
+    selected(&expression.parse().unwrap())
+}
+
+#[test]
+fn test_filters() {
+    assert_eq!(selected(&Filter::All), [1, 2, 3, 4, 5, 6]);
+    assert_eq!(selected(&Filter::Type(MarkerType::Marker)), [2, 6]);
+    assert_eq!(selected(&Filter::Name("Verse ?".to_string())), [3, 5]);
+    assert_eq!(selected(&Filter::Ids(vec![6, 1])), [1, 6]);
+
+    // Markers inside the window, regions overlapping it; the end is exclusive
+    let window = |from: &str, to: &str| Filter::Time {
+        from: Some(from.parse().unwrap()),
+        to: Some(to.parse().unwrap()),
+    };
+    assert_eq!(selected(&window("0.5", "4s")), [2, 3, 4]);
+    assert_eq!(selected(&window("500", "4000")), [2, 3, 4]);
+    assert_eq!(selected(&window("499", "4001")), [1, 2, 3, 4, 5]);
+    let after = Filter::Time {
+        from: Some(Position::Seconds(6.0)),
+        to: None,
+    };
+    assert_eq!(selected(&after), [6]);
+
+    // Durations only select regions
+    assert_eq!(
+        selected(&Filter::MinDuration(Position::Seconds(2.0))),
+        [3, 5]
+    );
+    assert_eq!(
+        selected(&Filter::MaxDuration(Position::Samples(500))),
+        [1, 4]
+    );
+
+    let filter = Filter::Type(MarkerType::Region)
+        & !(Filter::Name("Verse*".to_string()) | Filter::Ids(vec![1]));
+    assert_eq!(selected(&filter), [4]);
+    assert!((Filter::All & Filter::All).is_all());
+    assert!(matches!(!!Filter::Ids(vec![1]), Filter::Ids(_)));
+}
+
+#[test]
+fn test_expressions() {
+    assert_eq!(parsed(""), [1, 2, 3, 4, 5, 6]);
+    assert_eq!(parsed("type:MARKER"), [2, 6]);
+    assert_eq!(parsed("name:\"Take (alt)\""), [4]);
+    #[cfg(feature = "regex")]
+    assert_eq!(parsed("regex:^V.*\\d$ and id:5,6"), [5]);
+    #[cfg(not(feature = "regex"))]
+    assert!("regex:^V".parse::<Filter>().is_err());
+    assert_eq!(
+        parsed("type:region max-duration:2s not name:Take*"),
+        [1, 3, 5]
+    );
+    assert_eq!(parsed("id:2 or id:3 and type:marker"), [2]);
+    assert_eq!(parsed("(id:2 or id:3) and type:marker"), [2]);
+    assert_eq!(parsed("not not type:marker"), [2, 6]);
+    assert_eq!(parsed("time:0:02..0:05"), [3, 4, 5]);
+    assert_eq!(parsed("time:..1s or time:6.5.."), [1, 2, 6]);
+    assert_eq!(parsed("min-duration:1:00"), Vec::<u32>::new());
+    // Quoted keywords are names
+    assert_eq!(parsed("name:\"or\" or name:Drop"), [6]);
+
+    for invalid in [
+        "Verse",
+        "type:cue",
+        "id:one",
+        "time:1s",
+        "regex:(",
+        "name:\"Verse",
+        "(type:region",
+        "type:region)",
+        "type:region and",
+        "not",
+        "color:red",
+        "min-duration:-1",
+    ] {
+        assert!(invalid.parse::<Filter>().is_err(), "{invalid}");
+    }
+}
+
+#[test]
+fn test_split_selected_regions() {
+    let dir = std::env::temp_dir().join("reaper-regions-test-select");
+    let _ = fs::remove_dir_all(&dir);
+    fs::create_dir_all(&dir).unwrap();
+    let path = dir.join("mix.wav");
+    let markers = data().markers;
+    pcm_wav(0..8000, &BTreeMap::new(), &markers)
+        .write(fs::File::create(&path).unwrap())
+        .unwrap();
+
+    let options = SplitOptions {
+        filter: "name:Verse*".parse().unwrap(),
+        ..Default::default()
+    };
+    let result = split::split(path.to_str().unwrap(), &dir.join("out"), &options).unwrap();
+    let ids: Vec<u32> = result.files.iter().map(|file| file.region_id).collect();
+    assert_eq!(ids, [3, 5]);
+    // Markers inside a selected region are still carried over
+    assert_eq!(result.files[0].markers, 1);
+}
@@ -0,0 +1,120 @@
+//! Tests for splitting a WAV file into one file per region.
+
+use reaper_regions::split::{self, SplitOptions};
+use reaper_regions::wavtag::RiffFile;
+use reaper_regions::{Marker, parse_markers_from_file};
+use std::collections::BTreeMap;
+use std::fs;
+
+mod common;
+
+use common::{SAMPLE_RATE, marker, pcm_wav, samples};
+
+#[test]
+fn test_split_regions() {
+    let dir = std::env::temp_dir().join("reaper-regions-test-split");
+    let _ = fs::remove_dir_all(&dir);
+    fs::create_dir_all(&dir).unwrap();
+    let source = dir.join("source.wav");
+    let info = BTreeMap::from([
+        ("INAM".to_string(), "Live at Home".to_string()),
+        ("IART".to_string(), "The Band".to_string()),
+    ]);
+    let riff = pcm_wav(
+        0..100,
+        &info,
+        &[
+            marker(3, "Encore", 50, Some(120)),
+            marker(1, "AC/DC", 10, Some(40)),
+            marker(2, "Hit", 20, None),
+            marker(4, "Solo", 55, Some(60)),
+            marker(5, "Outside", 45, None),
+        ],
+    );
+    riff.write(fs::File::create(&source).unwrap()).unwrap();
+
+    let result = split::split(
+        source.to_str().unwrap(),
+        &dir.join("out"),
+        &SplitOptions::default(),
+    )
+    .unwrap();
+    let names: Vec<_> = result
+        .files
+        .iter()
+        .map(|file| file.path.file_name().unwrap().to_str().unwrap())
+        .collect();
+    // Nested regions get their own file too
+    assert_eq!(
+        names,
+        ["01 - AC_DC.wav", "02 - Encore.wav", "03 - Solo.wav"]
+    );
+    assert_eq!(result.files[2].length, 5);
+    assert_eq!(result.files[2].markers, 0);
+    // The encore runs past the end of the audio and is cut
+    assert_eq!(result.diagnostics.len(), 1);
+    assert_eq!(result.diagnostics[0].marker_id, Some(3));
+
+    let first = parse_markers_from_file(result.files[0].path.to_str().unwrap()).unwrap();
+    assert_eq!(first.length, Some(30));
+    assert_eq!(first.info["INAM"], "AC/DC");
+    assert_eq!(first.info["ITRK"], "1");
+    assert_eq!(first.info["IPRD"], "Live at Home");
+    assert_eq!(first.info["IART"], "The Band");
+    assert_eq!(first.markers, vec![marker(2, "Hit", 10, None)]);
+
+    let second = parse_markers_from_file(result.files[1].path.to_str().unwrap()).unwrap();
+    assert_eq!(second.length, Some(50));
+    assert_eq!(second.info["ITRK"], "2");
+    assert_eq!(second.markers, vec![marker(4, "Solo", 5, Some(10))]);
+
+    // The audio is copied sample for sample
+    let riff = RiffFile::read(
+        fs::File::open(&result.files[1].path).unwrap(),
+        String::new(),
+    )
+    .unwrap();
+    assert_eq!(samples(&riff), (50..100).collect::<Vec<_>>());
+
+    fs::remove_dir_all(&dir).unwrap();
+}
+
+#[test]
+fn test_file_name_template() {
+    let region = Marker::new(12, " ..".to_string(), 0, Some(10), SAMPLE_RATE);
+    assert_eq!(
+        split::file_name("{id:03}_{name}.wav", 1, &region).unwrap(),
+        "012_Region 12.wav"
+    );
+    assert!(split::file_name("{index} {title}.wav", 1, &region).is_err());
+    assert!(split::file_name("{index.wav", 1, &region).is_err());
+}
+
+#[test]
+fn test_split_rejects_duplicate_file_names() {
+    let dir = std::env::temp_dir().join("reaper-regions-test-split-duplicates");
+    let _ = fs::remove_dir_all(&dir);
+    fs::create_dir_all(&dir).unwrap();
+    let source = dir.join("source.wav");
+    let riff = pcm_wav(
+        0..100,
+        &BTreeMap::new(),
+        &[
+            marker(1, "Chorus", 10, Some(20)),
+            marker(2, "Verse", 20, Some(30)),
+            marker(3, "Chorus", 30, Some(40)),
+        ],
+    );
+    riff.write(fs::File::create(&source).unwrap()).unwrap();
+
+    let options = SplitOptions {
+        template: "{name}.wav".to_string(),
+        ..SplitOptions::default()
+    };
+    let err = split::split(source.to_str().unwrap(), &dir.join("out"), &options).unwrap_err();
+    assert!(err.to_string().contains("Chorus.wav"), "{err}");
+    // Nothing is written
+    assert!(!dir.join("out").exists());
+
+    fs::remove_dir_all(&dir).unwrap();
+}
@@ -0,0 +1,155 @@
+//! Tests for the region structure analysis.
+
+use reaper_regions::WavData;
+use reaper_regions::structure::{Node, OverlapKind};
+
+mod common;
+
+use common::{SAMPLE_RATE, marker};
+
+/// The tree as `Name(Child, ...)`.
+fn outline(nodes: &[Node]) -> String {
+    let names: Vec<String> = nodes
+        .iter()
+        .map(|node| match node.children.as_slice() {
+            [] => node.marker.name.clone(),
+            children => format!("{}({})", node.marker.name, outline(children)),
+        })
+        .collect();
+    names.join(", ")
+}
+
+#[test]
+fn test_structure() {
+    let data = WavData {
+        sample_rate: SAMPLE_RATE,
+        length: Some(10000),
+        markers: vec![
+            marker(1, "Set", 1000, Some(6000)),
+            marker(2, "Song 1", 1000, Some(3000)),
+            marker(3, "Intro", 1000, Some(1500)),
+            marker(4, "Count-in", 1000, None),
+            marker(5, "Song 2", 3000, Some(6000)),
+            // Partly overlaps "Song 2" and the end of "Set"
+            marker(6, "Encore", 5500, Some(7000)),
+            marker(7, "Solo", 5600, Some(5800)),
+            // At the end of "Song 1", so only inside "Set" and "Song 2"
+            marker(8, "Clap", 3000, None),
+            marker(9, "Bow", 9000, Some(9500)),
+            marker(10, "Lights", 8000, None),
+        ],
+        ..Default::default()
+    };
+    let structure = data.structure();
+    assert_eq!(
+        outline(&structure.tree),
+        "Set(Song 1(Intro(Count-in)), Song 2(Clap)), Encore(Solo), Lights, Bow"
+    );
+
+    let overlaps: Vec<(u32, u32, OverlapKind, u32, u32)> = structure
+        .overlaps
+        .iter()
+        .map(|overlap| {
+            let span = overlap.span;
+            (
+                overlap.first,
+                overlap.second,
+                overlap.kind,
+                span.start,
+                span.end,
+            )
+        })
+        .collect();
+    use OverlapKind::{Nested, Partial};
+    assert_eq!(
+        overlaps,
+        [
+            (1, 2, Nested, 1000, 3000),
+            (1, 3, Nested, 1000, 1500),
+            (1, 5, Nested, 3000, 6000),
+            (1, 6, Partial, 5500, 6000),
+            (1, 7, Nested, 5600, 5800),
+            (2, 3, Nested, 1000, 1500),
+            (5, 6, Partial, 5500, 6000),
+            (5, 7, Nested, 5600, 5800),
+            (6, 7, Nested, 5600, 5800),
+        ]
+    );
+
+    let gaps: Vec<(Option<u32>, Option<u32>, u32, u32)> = structure
+        .gaps
+        .iter()
+        .map(|gap| (gap.after, gap.before, gap.span.start, gap.span.end))
+        .collect();
+    assert_eq!(
+        gaps,
+        [
+            (None, Some(1), 0, 1000),
+            (Some(6), Some(9), 7000, 9000),
+            (Some(9), None, 9500, 10000),
+        ]
+    );
+    assert_eq!(structure.coverage.covered, 6500);
+    assert_eq!(structure.coverage.fraction, Some(0.65));
+
+    let text = structure.to_string();
+    assert!(text.starts_with(
+        "region 'Set' (ID 1) at 1.000 s to 6.000 s\n  region 'Song 1' (ID 2) at 1.000 s to 3.000 s\n    region 'Intro'"
+    ));
+    assert!(text.contains("\n  IDs 1 and 6: 5.500 s to 6.000 s (0.500 s), partial\n"));
+    assert!(text.contains("\n  7.000 s to 9.000 s (2.000 s) between IDs 6 and 9\n"));
+    assert!(text.ends_with("\nCoverage: 6.500 s of 10.000 s (65.0%)"));
+
+    let json = serde_json::to_value(&structure).unwrap();
+    assert_eq!(
+        json["tree"][0]["children"][1]["children"][0]["name"],
+        "Clap"
+    );
+    assert!(json["tree"][2].get("children").is_none());
+    assert_eq!(json["overlaps"][3]["kind"], "partial");
+    assert_eq!(json["gaps"][0]["duration"], 1.0);
+    assert!(json["gaps"][0].get("after").is_none());
+}
+
+#[test]
+fn test_structure_without_length() {
+    let data = WavData {
+        sample_rate: SAMPLE_RATE,
+        length: Some(0),
+        markers: vec![
+            marker(1, "A", 500, Some(1500)),
+            marker(2, "B", 1000, Some(2000)),
+        ],
+        ..Default::default()
+    };
+    let structure = data.structure();
+    assert_eq!(structure.tree.len(), 2);
+    assert_eq!(structure.overlaps[0].kind, OverlapKind::Partial);
+    assert_eq!(structure.gaps.len(), 1);
+    assert_eq!(structure.coverage.covered, 1500);
+    assert_eq!(structure.coverage.fraction, None);
+    assert!(structure.to_string().ends_with("Coverage: 1.500 s"));
+}
+
+#[test]
+fn test_structure_empty_region_in_gap() {
+    let data = WavData {
+        sample_rate: SAMPLE_RATE,
+        length: Some(4000),
+        markers: vec![
+            marker(1, "A", 0, Some(1000)),
+            marker(2, "Empty", 2000, Some(2000)),
+            marker(3, "B", 3000, Some(4000)),
+        ],
+        ..Default::default()
+    };
+    let structure = data.structure();
+    assert_eq!(structure.gaps.len(), 1);
+    assert_eq!(structure.gaps[0].after, Some(1));
+    assert_eq!(structure.gaps[0].before, Some(3));
+    assert_eq!(
+        (structure.gaps[0].span.start, structure.gaps[0].span.end),
+        (1000, 3000)
+    );
+    assert_eq!(structure.coverage.covered, 2000);
+}
@@ -0,0 +1,130 @@
+//! Tests for musical time derived from tempo maps.
+
+use reaper_regions::parse_markers_from_file;
+use reaper_regions::tempo::{MusicalTime, TempoMap};
+
+#[test]
+fn test_meter_change_starts_new_bar() {
+    // 15 bars of 4/4 take 30 seconds; the 3/4 bar 16 starts there even if the change is late
+    let map = TempoMap::parse_csv("0,120,4/4\n30.2,120,3/4\n").unwrap();
+    assert_eq!(map.position(30.0).to_string(), "16.1.000");
+    assert_eq!(map.position(30.2).to_string(), "17.1.000");
+    assert_eq!(map.position(31.7).to_string(), "18.1.000");
+}
+
+#[test]
+fn test_linear_tempo_ramp() {
+    // 60 to 120 BPM over 10 seconds averages 90 BPM: 15 beats
+    let map = TempoMap::parse_csv("0,60,4/4,linear\n10,120\n").unwrap();
+    assert!((map.beats_at(10.0) - 15.0).abs() < 1e-9);
+    assert_eq!(map.position(10.0).to_string(), "4.4.000");
+    // Halfway, the tempo is 90 BPM: (60 + 90) / 2 * 5 seconds = 6.25 beats
+    assert!((map.beats_at(5.0) - 6.25).abs() < 1e-9);
+    // Constant after the last change
+    assert!((map.beats_at(11.0) - 17.0).abs() < 1e-9);
+}
+
+#[test]
+fn test_musical_time_of_markers() {
+    let mut data =
+        parse_markers_from_file("tests/fixtures/3-markers-3-regions-overlapping_stripped.wav")
+            .unwrap();
+    data.set_musical_time(&TempoMap::constant(120.0, 4, 4));
+
+    let region = &data.markers[0];
+    assert_eq!(
+        region.musical_start,
+        Some(MusicalTime {
+            bar: 4,
+            beat: 1,
+            ticks: 108
+        })
+    );
+    assert_eq!(region.musical_length.unwrap().to_string(), "6.0.787");
+    assert_eq!(data.markers[1].musical_end, None);
+
+    // Musical times round-trip through JSON as strings
+    let json = serde_json::to_string(region).unwrap();
+    assert!(json.contains("\"musical_start\":\"4.1.108\""));
+    let parsed: reaper_regions::Marker = serde_json::from_str(&json).unwrap();
+    assert_eq!(parsed.musical_start, region.musical_start);
+}
+
+#[test]
+fn test_tempo_map_rejects_invalid_lines() {
+    assert!(TempoMap::parse_csv("0,120,4/4\n10,fast\n").is_err());
+    assert!(TempoMap::parse_csv("0,120,4\n").is_err());
+    assert!(TempoMap::parse_csv("# nothing\n").is_err());
+    assert!(TempoMap::parse_csv("0,120\n-5,90\n").is_err());
+    assert!(TempoMap::parse_csv("0,120\ninf,90\n").is_err());
+    assert!(TempoMap::parse_csv("0,120\nNaN,90\n").is_err());
+    assert!(TempoMap::parse_csv("0,inf\n").is_err());
+    assert!(TempoMap::parse_csv("0,NaN\n").is_err());
+}
+
+#[test]
+fn test_acid_chunk_is_default_tempo() {
+    use reaper_regions::wavtag::{AcidChunk, ChunkType, RiffFile};
+    use std::fs;
+
+    // Copy a fixture with an ACID chunk at 96 BPM in 3/4
+    let fixture = "tests/fixtures/3-markers-3-regions-overlapping_stripped.wav";
+    let mut riff = RiffFile::read(fs::File::open(fixture).unwrap(), fixture.to_string()).unwrap();
+    let acid = AcidChunk {
+        root_note_set: true,
+        root_note: 57,
+        beats: 48,
+        meter_numerator: 3,
+        meter_denominator: 4,
+        tempo: 96.0,
+        ..AcidChunk::default()
+    };
+    riff.set_acid_chunk(acid);
+    let chunk = riff.find_chunk_by_type(ChunkType::Acid).unwrap();
+    assert_eq!(chunk.data.len(), 24);
+    assert_eq!(&chunk.data[0..4], &[0x06, 0, 0, 0]);
+    let path = std::env::temp_dir().join("reaper-regions-test-acid.wav");
+    riff.write(fs::File::create(&path).unwrap()).unwrap();
+
+    let mut data = parse_markers_from_file(path.to_str().unwrap()).unwrap();
+    fs::remove_file(&path).unwrap();
+    assert_eq!(data.acid, Some(acid));
+    assert_eq!(data.markers.len(), 6);
+
+    let json = serde_json::to_value(&data).unwrap();
+    assert_eq!(json["acid"]["tempo"], 96.0);
+    assert_eq!(json["acid"]["root_note"], 57);
+    assert!(json["acid"].get("reserved1").is_none());
+
+    // 6.056 seconds at 96 BPM is 9.69 beats: bar 4, beat 1 in 3/4
+    let tempo = data.tempo.clone().unwrap();
+    assert_eq!(tempo.changes[0].numerator, 3);
+    data.set_musical_time(&tempo);
+    assert_eq!(data.markers[0].musical_start.unwrap().bar, 4);
+    assert_eq!(data.markers[0].musical_start.unwrap().beat, 1);
+}
+
+#[test]
+fn test_malformed_acid_chunk_is_ignored() {
+    use reaper_regions::wavtag::{AcidChunk, ChunkType, RiffFile};
+    use std::fs;
+
+    // Copy a fixture with an ACID chunk cut short after its flags and root note
+    let fixture = "tests/fixtures/3-markers-3-regions-overlapping_stripped.wav";
+    let mut riff = RiffFile::read(fs::File::open(fixture).unwrap(), fixture.to_string()).unwrap();
+    riff.set_acid_chunk(AcidChunk::default());
+    let chunk = riff
+        .chunks
+        .iter_mut()
+        .find(|chunk| chunk.header == ChunkType::Acid)
+        .unwrap();
+    chunk.data.truncate(6);
+    let path = std::env::temp_dir().join("reaper-regions-test-bad-acid.wav");
+    riff.write(fs::File::create(&path).unwrap()).unwrap();
+
+    let data = parse_markers_from_file(path.to_str().unwrap()).unwrap();
+    fs::remove_file(&path).unwrap();
+    assert_eq!(data.acid, None);
+    assert!(data.tempo.is_none());
+    assert_eq!(data.markers.len(), 6);
+}
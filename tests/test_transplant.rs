@@ -0,0 +1,154 @@
+//! Tests for copying markers between WAV files.
+
+use reaper_regions::edit::OutOfRange;
+use reaper_regions::transplant::{self, TransplantOptions};
+use reaper_regions::wavtag::RiffFile;
+use reaper_regions::{Marker, WavData, parse_markers_from_file};
+use std::collections::BTreeMap;
+use std::fs;
+
+mod common;
+
+use common::{marker, pcm_wav, samples};
+
+#[test]
+fn test_transplant_rescales_and_offsets() {
+    let dir = std::env::temp_dir().join("reaper-regions-test-transplant");
+    let _ = fs::remove_dir_all(&dir);
+    fs::create_dir_all(&dir).unwrap();
+    let destination = dir.join("new.wav");
+    pcm_wav(0..1000, &BTreeMap::new(), &[marker(9, "Old", 5, None)])
+        .write(fs::File::create(&destination).unwrap())
+        .unwrap();
+
+    let source_marker =
+        |id, name: &str, start, end| Marker::new(id, name.to_string(), start, end, 441);
+    let source = WavData {
+        sample_rate: 441,
+        markers: vec![
+            source_marker(1, "Start", 0, None),
+            source_marker(2, "Verse", 100, Some(441)),
+            source_marker(3, "Late", 500, None),
+            source_marker(4, "Hit", 220, None),
+        ],
+        ..Default::default()
+    };
+    let options = TransplantOptions {
+        offset: 0.01,
+        out_of_range: OutOfRange::Drop,
+        ..Default::default()
+    };
+    let mut riff = RiffFile::read(fs::File::open(&destination).unwrap(), String::new()).unwrap();
+    let result = transplant::transplant(&source, &mut riff, &options).unwrap();
+    riff.write(fs::File::create(&destination).unwrap()).unwrap();
+
+    // 100 samples at 441 Hz are 226.76 at 1 kHz, 220 are 498.87
+    let expected = vec![
+        marker(1, "Start", 10, None),
+        marker(2, "Verse", 237, Some(1000)),
+        marker(4, "Hit", 509, None),
+    ];
+    assert_eq!(result.markers, expected);
+    let ids: Vec<_> = result
+        .diagnostics
+        .iter()
+        .map(|diagnostic| diagnostic.marker_id)
+        .collect();
+    assert_eq!(ids, [Some(2), Some(3)]);
+
+    let data = parse_markers_from_file(destination.to_str().unwrap()).unwrap();
+    assert_eq!(data.markers, expected);
+    let riff = RiffFile::read(fs::File::open(&destination).unwrap(), String::new()).unwrap();
+    assert_eq!(samples(&riff), (0..1000).collect::<Vec<_>>());
+
+    fs::remove_dir_all(&dir).unwrap();
+}
+
+#[test]
+fn test_transplant_clamps_to_file() {
+    let dir = std::env::temp_dir().join("reaper-regions-test-transplant-clamp");
+    let _ = fs::remove_dir_all(&dir);
+    fs::create_dir_all(&dir).unwrap();
+    let source = dir.join("old.wav");
+    let destination = dir.join("new.wav");
+    let output = dir.join("out.wav");
+    pcm_wav(
+        0..200,
+        &BTreeMap::new(),
+        &[
+            marker(1, "Count-in", 50, None),
+            marker(2, "Song", 120, Some(200)),
+        ],
+    )
+    .write(fs::File::create(&source).unwrap())
+    .unwrap();
+    pcm_wav(0..100, &BTreeMap::new(), &[])
+        .write(fs::File::create(&destination).unwrap())
+        .unwrap();
+
+    // The pre-roll of the old render was cut
+    let options = TransplantOptions {
+        offset: -0.1,
+        out_of_range: OutOfRange::Clamp,
+        ..Default::default()
+    };
+    let result = transplant::transplant_to_file(
+        source.to_str().unwrap(),
+        destination.to_str().unwrap(),
+        output.to_str().unwrap(),
+        &options,
+    )
+    .unwrap();
+    assert_eq!(result.diagnostics.len(), 1);
+    assert_eq!(result.diagnostics[0].marker_id, Some(1));
+
+    let data = parse_markers_from_file(output.to_str().unwrap()).unwrap();
+    assert_eq!(
+        data.markers,
+        [
+            marker(1, "Count-in", 0, None),
+            marker(2, "Song", 20, Some(100))
+        ]
+    );
+
+    fs::remove_dir_all(&dir).unwrap();
+}
+
+#[test]
+fn test_transplant_onto_destination() {
+    let dir = std::env::temp_dir().join("reaper-regions-test-transplant-in-place");
+    let _ = fs::remove_dir_all(&dir);
+    fs::create_dir_all(&dir).unwrap();
+    let source = dir.join("old.wav");
+    let destination = dir.join("new.wav");
+    pcm_wav(
+        0..100,
+        &BTreeMap::new(),
+        &[marker(1, "Verse", 10, Some(60))],
+    )
+    .write(fs::File::create(&source).unwrap())
+    .unwrap();
+    let info = BTreeMap::from([("INAM".to_string(), "New Mix".to_string())]);
+    pcm_wav(0..100, &info, &[marker(7, "Old", 5, None)])
+        .write(fs::File::create(&destination).unwrap())
+        .unwrap();
+
+    let destination = destination.to_str().unwrap();
+    let result = transplant::transplant_to_file(
+        source.to_str().unwrap(),
+        destination,
+        destination,
+        &TransplantOptions::default(),
+    )
+    .unwrap();
+    assert!(result.diagnostics.is_empty());
+
+    let data = parse_markers_from_file(destination).unwrap();
+    assert_eq!(data.markers, [marker(1, "Verse", 10, Some(60))]);
+    assert_eq!(data.info["INAM"], "New Mix");
+    let riff = RiffFile::read(fs::File::open(destination).unwrap(), String::new()).unwrap();
+    assert_eq!(samples(&riff), (0..100).collect::<Vec<_>>());
+    assert_eq!(fs::read_dir(&dir).unwrap().count(), 2);
+
+    fs::remove_dir_all(&dir).unwrap();
+}
@@ -0,0 +1,152 @@
+//! Tests for updating the metadata of WAV files in place.
+
+use reaper_regions::markers::{self, WriteOptions};
+use reaper_regions::parse_markers_from_file;
+use reaper_regions::wavtag::{ChunkType, RiffChunk, RiffFile};
+use reaper_regions::write::{self, Update};
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::Path;
+
+mod common;
+
+use common::{marker, pcm_wav, samples};
+
+fn junk(size: usize) -> RiffChunk {
+    RiffChunk {
+        header: ChunkType::Unknown("JUNK".to_string()),
+        data: vec![0; size],
+    }
+}
+
+fn read(path: &Path) -> RiffFile {
+    let riff = RiffFile::read(fs::File::open(path).unwrap(), String::new()).unwrap();
+    let riff_size = u32::from_le_bytes(fs::read(path).unwrap()[4..8].try_into().unwrap());
+    assert_eq!(riff_size as u64 + 8, fs::metadata(path).unwrap().len());
+    riff
+}
+
+fn tags(riff: &RiffFile) -> Vec<[u8; 4]> {
+    riff.chunks
+        .iter()
+        .map(|chunk| chunk.header.clone().to_tag())
+        .collect()
+}
+
+#[test]
+fn test_markers_updated_in_place() {
+    let dir = std::env::temp_dir().join("reaper-regions-test-update");
+    let _ = fs::remove_dir_all(&dir);
+    fs::create_dir_all(&dir).unwrap();
+    let path = dir.join("mix.wav");
+    let path_str = path.to_str().unwrap();
+    pcm_wav(0..100, &BTreeMap::new(), &[marker(1, "Intro", 0, Some(50))])
+        .write(fs::File::create(&path).unwrap())
+        .unwrap();
+
+    // Growing and shrinking the chunks after the audio
+    let many = vec![
+        marker(1, "Intro with a much longer name", 0, Some(50)),
+        marker(2, "Hit", 20, None),
+        marker(3, "Outro", 50, Some(100)),
+    ];
+    for markers in [many, vec![marker(2, "Hit", 20, None)], Vec::new()] {
+        let size = fs::metadata(&path).unwrap().len();
+        let update = markers::write_markers(path_str, &markers, &WriteOptions::default());
+        assert_eq!(update.unwrap(), Update::InPlace);
+        assert_eq!(parse_markers_from_file(path_str).unwrap().markers, markers);
+        let riff = read(&path);
+        assert_eq!(samples(&riff), (0..100).collect::<Vec<_>>());
+        assert_ne!(fs::metadata(&path).unwrap().len(), size);
+    }
+    // The sampler settings are kept without loops
+    assert_eq!(tags(&read(&path)), [*b"fmt ", *b"data", *b"smpl"]);
+
+    fs::remove_dir_all(&dir).unwrap();
+}
+
+#[test]
+fn test_chunks_before_audio() {
+    let dir = std::env::temp_dir().join("reaper-regions-test-update-before");
+    let _ = fs::remove_dir_all(&dir);
+    fs::create_dir_all(&dir).unwrap();
+    let path = dir.join("mix.wav");
+    let path_str = path.to_str().unwrap();
+    let mut riff = pcm_wav(0..100, &BTreeMap::new(), &[marker(1, "Hit", 10, None)]);
+    riff.insert_chunk(1, junk(64));
+    riff.write(fs::File::create(&path).unwrap()).unwrap();
+    let size = fs::metadata(&path).unwrap().len();
+
+    // A title fits into the space of the JUNK chunk
+    let info = BTreeMap::from([("INAM".to_string(), "Mix".to_string())]);
+    let ((), update) = write::update_metadata(path_str, |riff| {
+        riff.insert_chunk(1, write::info_chunk(&info));
+        Ok(())
+    })
+    .unwrap();
+    assert_eq!(update, Update::InPlace);
+    assert_eq!(fs::metadata(&path).unwrap().len(), size);
+    let riff = read(&path);
+    assert_eq!(
+        tags(&riff),
+        [*b"fmt ", *b"LIST", *b"JUNK", *b"data", *b"cue ", *b"LIST"]
+    );
+    let data = parse_markers_from_file(path_str).unwrap();
+    assert_eq!(data.info["INAM"], "Mix");
+    assert_eq!(data.markers, [marker(1, "Hit", 10, None)]);
+
+    // A longer one does not, and the file is rewritten, leaving other files alone
+    let other = dir.join("mix.wav.tmp");
+    fs::write(&other, "not ours").unwrap();
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::PermissionsExt;
+        fs::set_permissions(&path, fs::Permissions::from_mode(0o640)).unwrap();
+    }
+    let info = BTreeMap::from([("INAM".to_string(), "M".repeat(100))]);
+    let ((), update) = write::update_metadata(path_str, |riff| {
+        let index = riff.chunk_index_by_type(ChunkType::List).unwrap();
+        riff.remove_chunk(index);
+        riff.insert_chunk(index, write::info_chunk(&info));
+        Ok(())
+    })
+    .unwrap();
+    assert_eq!(update, Update::Rewritten);
+    let riff = read(&path);
+    assert_eq!(samples(&riff), (0..100).collect::<Vec<_>>());
+    let data = parse_markers_from_file(path_str).unwrap();
+    assert_eq!(data.info["INAM"], "M".repeat(100));
+    assert_eq!(data.markers, [marker(1, "Hit", 10, None)]);
+    assert_eq!(fs::read_to_string(&other).unwrap(), "not ours");
+    assert_eq!(fs::read_dir(&dir).unwrap().count(), 2);
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::PermissionsExt;
+        let mode = fs::metadata(&path).unwrap().permissions().mode();
+        assert_eq!(mode & 0o777, 0o640);
+    }
+
+    fs::remove_dir_all(&dir).unwrap();
+}
+
+#[test]
+fn test_replace_chunk_keeps_position() {
+    let mut riff = pcm_wav(0..10, &BTreeMap::new(), &[marker(1, "Loop", 0, Some(5))]);
+    riff.insert_chunk(1, junk(4));
+    riff.chunks.push(junk(2));
+    riff.replace_chunk_by_type(junk(8));
+    assert_eq!(
+        tags(&riff),
+        [*b"fmt ", *b"JUNK", *b"data", *b"cue ", *b"LIST", *b"smpl"]
+    );
+    assert_eq!(riff.chunks[1].len(), 8);
+
+    let mut sampler = riff.get_sampler_chunk();
+    sampler.midi_unity_note = 72;
+    riff.set_sampler_chunk(sampler);
+    riff.remove_chunk(1);
+    assert_eq!(
+        tags(&riff),
+        [*b"fmt ", *b"data", *b"cue ", *b"LIST", *b"smpl"]
+    );
+}
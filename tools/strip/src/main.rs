@@ -41,14 +41,14 @@ fn main() -> Result<(), Box<dyn Error>> {
         .filter(|e| e.file_type().is_file())
     {
         let path = entry.path();
-        if let Some(ext) = path.extension() {
-            if ext.eq_ignore_ascii_case("wav") {
-                match process_file(path, &output_dir) {
-                    Ok(_) => processed += 1,
-                    Err(e) => {
-                        eprintln!("Error processing {}: {}", path.display(), e);
-                        errors += 1;
-                    }
+        if let Some(ext) = path.extension()
+            && ext.eq_ignore_ascii_case("wav")
+        {
+            match process_file(path, &output_dir) {
+                Ok(_) => processed += 1,
+                Err(e) => {
+                    eprintln!("Error processing {}: {}", path.display(), e);
+                    errors += 1;
                 }
             }
         }
@@ -107,7 +107,7 @@ fn strip_audio_data(input_path: &str, output_path: &str) -> Result<(), Box<dyn E
 
     // Update the RIFF size field (total file size minus 8 bytes for "RIFF" and size)
     let riff_size = out.len() as u32 - 8;
-    (&mut out[4..8]).copy_from_slice(&riff_size.to_le_bytes());
+    out[4..8].copy_from_slice(&riff_size.to_le_bytes());
 
     // Write the result to disk
     fs::write(output_path, &out)?;